@@ -0,0 +1,98 @@
+use crate::menu::Dish;
+use serde::{Serialize, Deserialize};
+use std::str::FromStr;
+
+// how to handle items conflicting w/ allergies or diets of guests
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Strictness {
+  Flag,   // accept and flag the items
+  Reject, // reject the whole request
+}
+
+impl FromStr for Strictness {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Strictness, String> {
+    match s {
+      "flag" => Ok(Strictness::Flag),
+      "reject" => Ok(Strictness::Reject),
+      _ => Err(format!("Unknown strictness {}", s)),
+    }
+  }
+}
+
+// allergies and diets of a guest at a seat or of the whole table if seat is not specified
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Default)]
+pub struct Restriction {
+  #[serde(default)]
+  pub seat: Option<usize>,
+  #[serde(default)]
+  pub allergies: Vec<String>, // allergens to avoid e.g. "nuts"
+  #[serde(default)]
+  pub diets: Vec<String>,     // diet tags dishes must have e.g. "vegan"
+}
+
+impl Restriction {
+  pub fn is_empty(&self) -> bool {
+    self.allergies.is_empty() && self.diets.is_empty()
+  }
+}
+
+// returns allergens of the dish and diets not met by the dish in the order of restrictions w/o duplicates.
+// dishes not on the menu are flagged as "unknown" if there is any restriction
+pub fn conflicts_of(dish: Option<&Dish>, restrictions: &[&Restriction]) -> Vec<String> {
+  let dish = match dish {
+    Some(x) => x,
+    None if restrictions.iter().all(|x| x.is_empty()) => return vec![],
+    None => return vec!["unknown".to_string()],
+  };
+  let mut conflicts: Vec<String> = vec![];
+  for x in restrictions {
+    let allergens = x.allergies.iter().filter(|x| dish.allergens.contains(x));
+    let diets = x.diets.iter().filter(|x| !dish.diets.contains(x));
+    for tag in allergens.chain(diets) {
+      if !conflicts.contains(tag) {
+        conflicts.push(tag.clone());
+      }
+    }
+  }
+  conflicts
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn restriction_of(seat: Option<usize>, allergies: Vec<&str>, diets: Vec<&str>) -> Restriction {
+    Restriction {
+      seat,
+      allergies: allergies.into_iter().map(|x| x.to_string()).collect(),
+      diets: diets.into_iter().map(|x| x.to_string()).collect(),
+    }
+  }
+
+  #[test]
+  fn test_conflicts_of() {
+    let dish = Dish {
+      name: "gyoza".to_string(),
+      allergens: vec!["gluten".to_string(), "soy".to_string()],
+      diets: vec!["halal".to_string()],
+      ..Default::default()
+    };
+    let r1 = restriction_of(None, vec!["soy"], vec![]);
+    let r2 = restriction_of(Some(1), vec!["nuts", "soy"], vec!["vegan", "halal"]);
+    assert_eq!(vec!["soy", "vegan"], conflicts_of(Some(&dish), &[&r1, &r2]));
+    assert_eq!(Vec::<String>::new(), conflicts_of(Some(&dish), &[]));
+
+    // dish not on the menu
+    assert_eq!(vec!["unknown"], conflicts_of(None, &[&r1]));
+    assert_eq!(Vec::<String>::new(), conflicts_of(None, &[&restriction_of(None, vec![], vec![])]));
+  }
+
+  #[test]
+  fn test_strictness_from_str() {
+    assert_eq!(Ok(Strictness::Flag), "flag".parse());
+    assert_eq!(Ok(Strictness::Reject), "reject".parse());
+    assert!("warn".parse::<Strictness>().is_err());
+  }
+}
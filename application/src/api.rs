@@ -1,6 +1,116 @@
+use crate::{
+  payment::payment::Tender,
+  menu::Station,
+  takeout::TakeoutKind,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct AddItemsParam {
   pub item_names: Vec<String>,
+  // seat of each item in item_names. empty if no item is assigned to a seat
+  #[serde(default)]
+  pub seats: Vec<Option<usize>>,
+  // course of each item in item_names starting from 1. empty if no item belongs to a course
+  #[serde(default)]
+  pub courses: Vec<Option<usize>>,
+  // whether to make the items ready at the same time. follows the setting of the table if omitted
+  #[serde(default)]
+  pub sync: Option<bool>,
+  // modifiers of each item in item_names. empty if no item has modifiers
+  #[serde(default)]
+  pub modifiers: Vec<ModifiersParam>,
+  // quantity of each item in item_names. empty if every item is 1
+  #[serde(default)]
+  pub quantities: Vec<usize>,
+  // slot name to the substituted dish for each combo in item_names. empty if no combo has substitutions
+  #[serde(default)]
+  pub substitutions: Vec<HashMap<String, String>>,
+  // time to serve the items. they are held until it's time to start cooking them. cannot be used w/ courses
+  #[serde(default)]
+  pub serve_at: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ModifiersParam {
+  // choice group name to the chosen option
+  #[serde(default)]
+  pub choices: HashMap<String, String>,
+  #[serde(default)]
+  pub add_ons: Vec<String>,
+  #[serde(default)]
+  pub note: Option<String>,
+}
+
+// predicted schedule of an item in AddItemsParam. held items are not scheduled until fired
+#[derive(Deserialize, Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct Quote {
+  pub item_name: String,
+  pub station: Option<Station>,
+  pub is_held: bool,
+  pub start_at: i64,
+  pub ready_at: i64,
+  pub allergy_flags: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct StockParam {
+  pub quantity: i64,
+  #[serde(default)]
+  pub low_stock: i64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ReservationParam {
+  pub table_id: usize,
+  pub name: String,
+  pub party_size: usize,
+  pub at: i64,
+  // time to serve the pre-ordered items. defaults to at
+  #[serde(default)]
+  pub serve_at: Option<i64>,
+  #[serde(default)]
+  pub pre_order: AddItemsParam,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct TakeoutParam {
+  pub kind: TakeoutKind,
+  pub name: String,
+  pub phone: String,
+  // required for delivery
+  #[serde(default)]
+  pub address: Option<String>,
+  pub due_at: i64,
+  pub items: AddItemsParam,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WaitlistParam {
+  pub name: String,
+  pub party_size: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DecrementParam {
+  pub by: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SyncParam {
+  pub enabled: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct PayParam {
+  pub tender: Tender,
+  pub amount: i64,
+  #[serde(default)]
+  pub tip: i64,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RefundParam {
+  pub amount: i64,
 }
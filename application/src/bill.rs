@@ -0,0 +1,431 @@
+use crate::item::Item;
+use serde::{Serialize, Deserialize};
+use std::{
+  str::FromStr,
+  collections::{BTreeMap, HashMap},
+};
+
+// rates are in basis points i.e. 1000 means 10%
+const BP_DENOM: i64 = 10000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Rounding {
+  HalfUp,
+  HalfEven,
+  Down,
+  Up,
+}
+
+impl FromStr for Rounding {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Rounding, String> {
+    match s {
+      "half_up" => Ok(Rounding::HalfUp),
+      "half_even" => Ok(Rounding::HalfEven),
+      "down" => Ok(Rounding::Down),
+      "up" => Ok(Rounding::Up),
+      _ => Err(format!("Unknown rounding {}", s)),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BillConfig {
+  pub tax_rate_bp: i64,
+  pub service_charge_bp: i64,
+  pub rounding: Rounding,
+}
+
+impl Default for BillConfig {
+  fn default() -> BillConfig {
+    BillConfig {
+      tax_rate_bp: 0,
+      service_charge_bp: 0,
+      rounding: Rounding::HalfUp,
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct BillLine {
+  pub uuid: String,
+  pub name: String,
+  pub seat: Option<usize>,
+  pub price: i64,
+  #[serde(default = "default_quantity")]
+  pub quantity: usize,
+  #[serde(default)]
+  pub modifiers: Vec<String>, // names of the modifiers included in price
+  #[serde(default)]
+  pub components: Vec<String>, // names of the components of a combo
+}
+
+fn default_quantity() -> usize {
+  1
+}
+
+// all amounts are in minor currency unit
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Bill {
+  pub table_id: usize,
+  pub lines: Vec<BillLine>,
+  pub subtotal: i64,
+  pub service_charge: i64,
+  pub tax: i64,
+  pub total: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct SubBill {
+  pub payer: String,
+  pub lines: Vec<BillLine>,
+  pub subtotal: i64,
+  pub service_charge: i64,
+  pub tax: i64,
+  pub total: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SplitMode {
+  // splits the bill evenly into num_payers sub-bills named "1", "2", ...
+  Even { num_payers: usize },
+  // one sub-bill per seat named after the seat. items w/o seat go to "shared" sub-bill
+  Seat,
+  // item uuid to payer name. every item on the bill needs to be allocated
+  Item { payers: HashMap<String, String> },
+}
+
+// returns amount * rate_bp / 10000 rounded w/ the specified rounding
+pub fn apply_rate(amount: i64, rate_bp: i64, rounding: Rounding) -> i64 {
+  let num = amount * rate_bp;
+  let (q, r) = (num.div_euclid(BP_DENOM), num.rem_euclid(BP_DENOM));
+  let round_up = match rounding {
+    Rounding::Down => false,
+    Rounding::Up => r > 0,
+    Rounding::HalfUp => r * 2 >= BP_DENOM,
+    Rounding::HalfEven => r * 2 > BP_DENOM || (r * 2 == BP_DENOM && q % 2 != 0),
+  };
+  if round_up { q + 1 } else { q }
+}
+
+impl Bill {
+  // builds a bill of the items excluding cancelled and voided ones. components of combos are listed under the combos.
+  // service charge is applied to subtotal and tax is applied to subtotal + service charge
+  pub fn of(table_id: usize, items: &[Item], config: &BillConfig) -> Bill {
+    let lines: Vec<BillLine> = items.iter()
+      .filter(|x| !x.is_removed && !x.is_voided && x.parent_id.is_none())
+      .map(|x| BillLine {
+        uuid: x.uuid.clone(),
+        name: x.name.clone(),
+        seat: x.seat,
+        price: x.price,
+        quantity: x.quantity,
+        modifiers: x.modifiers.iter().map(|x| x.name.clone()).collect(),
+        components: items.iter()
+          .filter(|c| c.parent_id.as_ref() == Some(&x.uuid) && !c.is_removed)
+          .map(|c| c.name.clone())
+          .collect(),
+      })
+      .collect();
+
+    let subtotal = lines.iter().map(|x| x.price).sum();
+    let service_charge = apply_rate(subtotal, config.service_charge_bp, config.rounding);
+    let tax = apply_rate(subtotal + service_charge, config.tax_rate_bp, config.rounding);
+
+    Bill {
+      table_id,
+      lines,
+      subtotal,
+      service_charge,
+      tax,
+      total: subtotal + service_charge + tax,
+    }
+  }
+
+  // splits the bill into sub-bills that add up to the bill exactly.
+  // service charge and tax are distributed in proportion to the subtotal of each sub-bill
+  // and the rounding remainder goes to the sub-bills w/ the largest fractions.
+  // returns the name of the bad parameter on error
+  pub fn split(&self, mode: &SplitMode) -> Result<Vec<SubBill>, String> {
+    match mode {
+      SplitMode::Even { num_payers } => {
+        if *num_payers == 0 {
+          return Err("num_payers".to_string())
+        }
+        let weights = vec![1; *num_payers];
+        let subtotals = allocate(self.subtotal, &weights);
+        let service_charges = allocate(self.service_charge, &weights);
+        let taxes = allocate(self.tax, &weights);
+
+        Ok((0..*num_payers).map(|i| SubBill {
+          payer: (i + 1).to_string(),
+          lines: vec![],
+          subtotal: subtotals[i],
+          service_charge: service_charges[i],
+          tax: taxes[i],
+          total: subtotals[i] + service_charges[i] + taxes[i],
+        }).collect())
+      },
+      SplitMode::Seat => {
+        let mut groups: BTreeMap<Option<usize>, Vec<BillLine>> = BTreeMap::new();
+        for line in &self.lines {
+          groups.entry(line.seat).or_insert_with(Vec::new).push(line.clone());
+        }
+        // seats in ascending order and then shared items
+        let mut groups: Vec<(String, Vec<BillLine>)> = groups.into_iter()
+          .map(|(seat, lines)| (seat.map(|x| x.to_string()).unwrap_or_else(|| "shared".to_string()), lines))
+          .collect();
+        if groups.first().map(|x| x.0 == "shared").unwrap_or(false) {
+          let shared = groups.remove(0);
+          groups.push(shared);
+        }
+        Ok(self.split_lines(groups))
+      },
+      SplitMode::Item { payers } => {
+        if payers.len() != self.lines.len() {
+          return Err("payers".to_string())
+        }
+        let mut groups: BTreeMap<String, Vec<BillLine>> = BTreeMap::new();
+        for line in &self.lines {
+          match payers.get(&line.uuid) {
+            Some(payer) => groups.entry(payer.clone()).or_insert_with(Vec::new).push(line.clone()),
+            None => return Err("payers".to_string()),
+          }
+        }
+        Ok(self.split_lines(groups.into_iter().collect()))
+      },
+    }
+  }
+
+  fn split_lines(&self, groups: Vec<(String, Vec<BillLine>)>) -> Vec<SubBill> {
+    let subtotals: Vec<i64> = groups.iter().map(|(_, lines)| lines.iter().map(|x| x.price).sum()).collect();
+    let service_charges = allocate(self.service_charge, &subtotals);
+    let taxes = allocate(self.tax, &subtotals);
+
+    groups.into_iter().enumerate().map(|(i, (payer, lines))| SubBill {
+      payer,
+      lines,
+      subtotal: subtotals[i],
+      service_charge: service_charges[i],
+      tax: taxes[i],
+      total: subtotals[i] + service_charges[i] + taxes[i],
+    }).collect()
+  }
+}
+
+// distributes non-negative amount in proportion to the weights using largest remainder method.
+// amount is distributed evenly if all weights are 0
+fn allocate(amount: i64, weights: &[i64]) -> Vec<i64> {
+  if weights.is_empty() {
+    return vec![]
+  }
+  let weights: Vec<i64> = if weights.iter().all(|x| *x == 0) {
+    vec![1; weights.len()]
+  } else {
+    weights.to_vec()
+  };
+  let total_weight: i64 = weights.iter().sum();
+
+  let mut shares: Vec<i64> = weights.iter().map(|w| amount * w / total_weight).collect();
+  let mut remainder = amount - shares.iter().sum::<i64>();
+
+  // give 1 unit each to the shares w/ the largest fractions. earlier shares win ties
+  let mut by_fraction: Vec<usize> = (0..weights.len()).collect();
+  by_fraction.sort_by_key(|i| -(amount * weights[*i] % total_weight));
+  for i in by_fraction {
+    if remainder == 0 {
+      break
+    }
+    shares[i] += 1;
+    remainder -= 1;
+  }
+  shares
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn item_of(id: &str, price: i64, is_removed: bool, is_voided: bool) -> Item {
+    seat_item_of(id, price, None, is_removed, is_voided)
+  }
+
+  fn seat_item_of(id: &str, price: i64, seat: Option<usize>, is_removed: bool, is_voided: bool) -> Item {
+    Item {
+      uuid: id.to_string(),
+      name: id.to_string(),
+      seat,
+      price,
+      ready_at: 10,
+      is_removed,
+      is_voided,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_apply_rate() {
+    // 1005 * 10% = 100.5
+    assert_eq!(101, apply_rate(1005, 1000, Rounding::HalfUp));
+    assert_eq!(100, apply_rate(1005, 1000, Rounding::HalfEven));
+    assert_eq!(100, apply_rate(1005, 1000, Rounding::Down));
+    assert_eq!(101, apply_rate(1005, 1000, Rounding::Up));
+
+    // 1015 * 10% = 101.5
+    assert_eq!(102, apply_rate(1015, 1000, Rounding::HalfEven));
+
+    // 1004 * 10% = 100.4
+    assert_eq!(100, apply_rate(1004, 1000, Rounding::HalfUp));
+    assert_eq!(101, apply_rate(1004, 1000, Rounding::Up));
+
+    // exact amounts are not affected by rounding
+    assert_eq!(100, apply_rate(1000, 1000, Rounding::Up));
+    assert_eq!(0, apply_rate(1000, 0, Rounding::Up));
+  }
+
+  #[test]
+  fn test_rounding_from_str() {
+    assert_eq!(Ok(Rounding::HalfUp), "half_up".parse());
+    assert_eq!(Ok(Rounding::HalfEven), "half_even".parse());
+    assert_eq!(Ok(Rounding::Down), "down".parse());
+    assert_eq!(Ok(Rounding::Up), "up".parse());
+    assert!("foo".parse::<Rounding>().is_err());
+  }
+
+  #[test]
+  fn test_bill_of() {
+    let items = vec![
+      item_of("ramen", 900, false, false),
+      item_of("gyoza", 455, false, false),
+      item_of("soba", 800, true, false),  // cancelled
+      item_of("beer", 600, false, true),  // voided
+    ];
+    let config = BillConfig {
+      tax_rate_bp: 1000,
+      service_charge_bp: 500,
+      rounding: Rounding::HalfUp,
+    };
+    let bill = Bill::of(3, &items, &config);
+
+    assert_eq!(3, bill.table_id);
+    assert_eq!(2, bill.lines.len());
+    assert_eq!("ramen", bill.lines[0].name);
+    assert_eq!("gyoza", bill.lines[1].name);
+
+    assert_eq!(1355, bill.subtotal);
+    assert_eq!(68, bill.service_charge);  // 67.75
+    assert_eq!(142, bill.tax);            // 142.3
+    assert_eq!(1565, bill.total);
+  }
+
+  #[test]
+  fn test_empty_bill() {
+    let bill = Bill::of(0, &vec![], &BillConfig::default());
+    assert_eq!(0, bill.lines.len());
+    assert_eq!(0, bill.total);
+  }
+
+  fn config_of(tax_rate_bp: i64, service_charge_bp: i64) -> BillConfig {
+    BillConfig {
+      tax_rate_bp,
+      service_charge_bp,
+      rounding: Rounding::HalfUp,
+    }
+  }
+
+  fn assert_reconciled(bill: &Bill, sub_bills: &[SubBill]) {
+    assert_eq!(bill.subtotal, sub_bills.iter().map(|x| x.subtotal).sum::<i64>());
+    assert_eq!(bill.service_charge, sub_bills.iter().map(|x| x.service_charge).sum::<i64>());
+    assert_eq!(bill.tax, sub_bills.iter().map(|x| x.tax).sum::<i64>());
+    assert_eq!(bill.total, sub_bills.iter().map(|x| x.total).sum::<i64>());
+    for x in sub_bills {
+      assert_eq!(x.subtotal + x.service_charge + x.tax, x.total);
+    }
+  }
+
+  #[test]
+  fn test_allocate() {
+    assert_eq!(vec![34, 33, 33], allocate(100, &[1, 1, 1]));
+    assert_eq!(vec![25, 75], allocate(100, &[1, 3]));
+    // 10 * 1/6 = 1.67, 10 * 2/6 = 3.33, 10 * 3/6 = 5
+    assert_eq!(vec![2, 3, 5], allocate(10, &[1, 2, 3]));
+    assert_eq!(vec![1, 1], allocate(2, &[0, 0]));
+    assert_eq!(vec![0, 0], allocate(0, &[5, 0]));
+    assert_eq!(Vec::<i64>::new(), allocate(10, &[]));
+  }
+
+  #[test]
+  fn test_split_even() {
+    let items = vec![
+      item_of("ramen", 900, false, false),
+      item_of("gyoza", 455, false, false),
+    ];
+    let bill = Bill::of(0, &items, &config_of(1000, 500));
+
+    let sub_bills = bill.split(&SplitMode::Even { num_payers: 3 }).unwrap();
+    assert_eq!(3, sub_bills.len());
+    assert_eq!(vec!["1", "2", "3"], sub_bills.iter().map(|x| x.payer.as_str()).collect::<Vec<&str>>());
+    assert_reconciled(&bill, &sub_bills);
+
+    // sub-bills should differ by at most 1 in each component
+    assert_eq!(vec![452, 452, 451], sub_bills.iter().map(|x| x.subtotal).collect::<Vec<i64>>());
+
+    assert_eq!(Err("num_payers".to_string()), bill.split(&SplitMode::Even { num_payers: 0 }));
+  }
+
+  #[test]
+  fn test_split_by_seat() {
+    let items = vec![
+      seat_item_of("ramen", 900, Some(1), false, false),
+      seat_item_of("edamame", 355, None, false, false),
+      seat_item_of("gyoza", 455, Some(0), false, false),
+      seat_item_of("beer", 555, Some(1), false, false),
+    ];
+    let bill = Bill::of(0, &items, &config_of(1000, 1000));
+
+    let sub_bills = bill.split(&SplitMode::Seat).unwrap();
+    assert_eq!(3, sub_bills.len());
+    assert_eq!("0", sub_bills[0].payer);
+    assert_eq!(455, sub_bills[0].subtotal);
+    assert_eq!("1", sub_bills[1].payer);
+    assert_eq!(2, sub_bills[1].lines.len());
+    assert_eq!(1455, sub_bills[1].subtotal);
+    assert_eq!("shared", sub_bills[2].payer);
+    assert_eq!(355, sub_bills[2].subtotal);
+    assert_reconciled(&bill, &sub_bills);
+  }
+
+  #[test]
+  fn test_split_by_item() {
+    let items = vec![
+      item_of("ramen", 900, false, false),
+      item_of("gyoza", 455, false, false),
+      item_of("beer", 555, false, false),
+      item_of("soba", 800, true, false), // cancelled items need no payer
+    ];
+    let bill = Bill::of(0, &items, &config_of(1000, 0));
+
+    let payers_of = |xs: Vec<(&str, &str)>| -> HashMap<String, String> {
+      xs.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    };
+
+    let payers = payers_of(vec![("ramen", "bob"), ("gyoza", "alice"), ("beer", "bob")]);
+    let sub_bills = bill.split(&SplitMode::Item { payers }).unwrap();
+    assert_eq!(2, sub_bills.len());
+    assert_eq!("alice", sub_bills[0].payer);
+    assert_eq!(455, sub_bills[0].subtotal);
+    assert_eq!("bob", sub_bills[1].payer);
+    assert_eq!(1455, sub_bills[1].subtotal);
+    assert_reconciled(&bill, &sub_bills);
+
+    // every item needs to be allocated
+    let payers = payers_of(vec![("ramen", "bob"), ("gyoza", "alice")]);
+    assert_eq!(Err("payers".to_string()), bill.split(&SplitMode::Item { payers }));
+
+    // unknown items cannot be allocated
+    let payers = payers_of(vec![("ramen", "bob"), ("gyoza", "alice"), ("soba", "bob")]);
+    assert_eq!(Err("payers".to_string()), bill.split(&SplitMode::Item { payers }));
+  }
+}
@@ -0,0 +1,73 @@
+use crate::{
+  menu::Dish,
+  modifier::Modifier,
+};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+// a component of a combo e.g. main or drink. the first option is served unless substituted
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Default)]
+pub struct ComboSlot {
+  pub name: String,
+  pub options: Vec<Modifier>, // name of a dish and the price delta of substituting it
+}
+
+// returns the dish chosen for each slot of the combo in the order of the slots. returns the reason on error
+pub fn resolve(combo: &Dish, substitutions: &HashMap<String, String>) -> Result<Vec<Modifier>, String> {
+  if let Some(slot) = substitutions.keys().find(|k| !combo.components.iter().any(|x| &x.name == *k)) {
+    return Err(format!("unknown slot {}", slot))
+  }
+  let mut dishes = vec![];
+  for slot in &combo.components {
+    let option = match substitutions.get(&slot.name) {
+      Some(name) => slot.options.iter().find(|x| &x.name == name),
+      None => slot.options.first(),
+    };
+    match option {
+      Some(x) => dishes.push(x.clone()),
+      None => return Err(format!("no option for {}", slot.name)),
+    }
+  }
+  Ok(dishes)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn option_of(name: &str, price: i64) -> Modifier {
+    Modifier {
+      name: name.to_string(),
+      price,
+    }
+  }
+
+  #[test]
+  fn test_resolve() {
+    let combo = Dish {
+      name: "lunch set".to_string(),
+      price: 1200,
+      components: vec![
+        ComboSlot { name: "main".to_string(), options: vec![option_of("ramen", 0), option_of("udon", 0)] },
+        ComboSlot { name: "drink".to_string(), options: vec![option_of("green tea", 0), option_of("beer", 200)] },
+      ],
+      ..Default::default()
+    };
+    let subs_of = |xs: Vec<(&str, &str)>| -> HashMap<String, String> {
+      xs.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    };
+    assert_eq!(Ok(vec![option_of("ramen", 0), option_of("green tea", 0)]), resolve(&combo, &subs_of(vec![])));
+    assert_eq!(Ok(vec![option_of("ramen", 0), option_of("beer", 200)]), resolve(&combo, &subs_of(vec![("drink", "beer")])));
+
+    // unknown slot and dish not allowed in the slot
+    assert!(resolve(&combo, &subs_of(vec![("side", "gyoza")])).is_err());
+    assert!(resolve(&combo, &subs_of(vec![("main", "gyoza")])).is_err());
+
+    // slot w/o options
+    let combo = Dish {
+      components: vec![ComboSlot { name: "main".to_string(), options: vec![] }],
+      ..combo
+    };
+    assert!(resolve(&combo, &subs_of(vec![])).is_err());
+  }
+}
@@ -0,0 +1,123 @@
+use crate::item::Item;
+use serde::{Serialize, Deserialize};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ExpoSort {
+  TableId,
+  ReadyAt,
+}
+
+impl FromStr for ExpoSort {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<ExpoSort, String> {
+    match s {
+      "table_id" => Ok(ExpoSort::TableId),
+      "ready_at" => Ok(ExpoSort::ReadyAt),
+      _ => Err(format!("Unknown sort key {}", s)),
+    }
+  }
+}
+
+// readiness of the items of the current session of a table for the expediter
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct ExpoTable {
+  pub table_id: usize,
+  pub num_held: usize,
+  pub num_cooking: usize,
+  pub num_ready: usize,
+  pub num_served: usize,
+  pub latest_ready_at: Option<i64>, // none if any item is held since its ready_at is unknown
+  pub is_complete: bool,            // all items are ready or served
+}
+
+impl ExpoTable {
+  // cancelled items and combos are excluded. items w/ ready_at after now are being cooked
+  pub fn of(table_id: usize, items: &[Item], now: i64) -> ExpoTable {
+    let items: Vec<&Item> = items.iter().filter(|x| !x.is_removed && !x.is_combo).collect();
+    let num_held = items.iter().filter(|x| x.is_held).count();
+    let num_cooking = items.iter().filter(|x| !x.is_held && x.ready_at > now).count();
+    let num_served = items.iter().filter(|x| x.served_at.is_some()).count();
+    let num_ready = items.len() - num_held - num_cooking - num_served;
+
+    let latest_ready_at = if num_held > 0 {
+      None
+    } else {
+      items.iter().map(|x| x.ready_at).max()
+    };
+    ExpoTable {
+      table_id,
+      num_held,
+      num_cooking,
+      num_ready,
+      num_served,
+      latest_ready_at,
+      is_complete: !items.is_empty() && num_held == 0 && num_cooking == 0,
+    }
+  }
+}
+
+// tables that will be fully ready sooner come first when sorted by ready_at. tables w/ held items come last
+pub fn sort(tables: &mut [ExpoTable], sort: ExpoSort) {
+  match sort {
+    ExpoSort::TableId => tables.sort_by_key(|x| x.table_id),
+    ExpoSort::ReadyAt => tables.sort_by_key(|x| (x.latest_ready_at.unwrap_or(i64::MAX), x.table_id)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn item_of(ready_at: i64, is_held: bool, served_at: Option<i64>, is_removed: bool) -> Item {
+    Item {
+      ready_at,
+      is_held,
+      served_at,
+      is_removed,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_expo_table_of() {
+    let items = vec![
+      item_of(10, false, Some(12), false),
+      item_of(20, false, None, false),
+      item_of(30, false, None, false),
+      item_of(40, false, None, true),
+    ];
+    let t = ExpoTable::of(1, &items, 25);
+    assert_eq!((1, 1, 1), (t.num_cooking, t.num_ready, t.num_served));
+    assert_eq!(Some(30), t.latest_ready_at);
+    assert!(!t.is_complete);
+
+    let t = ExpoTable::of(1, &items, 30);
+    assert_eq!((0, 2, 1), (t.num_cooking, t.num_ready, t.num_served));
+    assert!(t.is_complete);
+
+    // table w/ held item is not complete and its ready_at is unknown
+    let t = ExpoTable::of(1, &[item_of(0, true, None, false), item_of(10, false, None, false)], 30);
+    assert_eq!(1, t.num_held);
+    assert_eq!(None, t.latest_ready_at);
+    assert!(!t.is_complete);
+
+    // table w/o items is not complete
+    assert!(!ExpoTable::of(1, &[], 30).is_complete);
+  }
+
+  #[test]
+  fn test_sort() {
+    let mut tables = vec![
+      ExpoTable::of(0, &[item_of(0, true, None, false)], 0),
+      ExpoTable::of(1, &[item_of(30, false, None, false)], 0),
+      ExpoTable::of(2, &[item_of(20, false, None, false)], 0),
+    ];
+    sort(&mut tables, ExpoSort::ReadyAt);
+    assert_eq!(vec![2, 1, 0], tables.iter().map(|x| x.table_id).collect::<Vec<usize>>());
+    sort(&mut tables, ExpoSort::TableId);
+    assert_eq!(vec![0, 1, 2], tables.iter().map(|x| x.table_id).collect::<Vec<usize>>());
+    assert!("name".parse::<ExpoSort>().is_err());
+  }
+}
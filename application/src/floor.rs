@@ -0,0 +1,148 @@
+use crate::item::Item;
+use serde::{Serialize, Deserialize};
+
+// party seated at a table. a table w/ a party or items in the current session is occupied
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Party {
+  pub name: String,
+  pub party_size: usize,
+  pub seated_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct TableStatus {
+  pub table_id: usize,
+  pub party: Option<Party>,
+  pub is_free: bool,
+  pub free_at: i64, // estimated time the table becomes free
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WaitlistStatus {
+  Waiting,
+  Seated,
+  Left,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct WaitlistEntry {
+  pub id: String,
+  pub name: String,
+  pub party_size: usize,
+  pub quoted_wait: i64, // in seconds when the party joined
+  pub status: WaitlistStatus,
+  pub table_id: Option<usize>,
+  pub created_at: i64,
+}
+
+// entries are kept after being seated or leaving
+pub struct Waitlist {
+  entries: Vec<WaitlistEntry>,
+}
+
+impl Waitlist {
+  pub fn new() -> Waitlist {
+    Waitlist {
+      entries: vec![],
+    }
+  }
+
+  pub fn add(&mut self, entry: WaitlistEntry) {
+    self.entries.push(entry);
+  }
+
+  pub fn get(&self, id: &str) -> Option<WaitlistEntry> {
+    self.entries.iter().find(|x| x.id == id).cloned()
+  }
+
+  // waiting parties in the order of joining
+  pub fn get_waiting(&self) -> Vec<WaitlistEntry> {
+    self.entries.iter().filter(|x| x.status == WaitlistStatus::Waiting).cloned().collect()
+  }
+
+  // updates an entry only if it's in the specified status
+  pub fn update<F: FnOnce(&mut WaitlistEntry)>(&mut self, id: &str, status: WaitlistStatus, f: F) -> Option<WaitlistEntry> {
+    let x = self.entries.iter_mut().find(|x| x.id == id && x.status == status)?;
+    f(x);
+    Some(x.clone())
+  }
+}
+
+// estimated time the table becomes free. the party stays for dining_secs after the last item is ready.
+// held items are assumed to be fired now unless they have a tentative schedule
+pub fn free_at_of(items: &[Item], party: Option<&Party>, dining_secs: i64, now: i64) -> i64 {
+  let last_ready_at = items.iter()
+    .filter(|x| !x.is_removed && !x.is_combo)
+    .map(|x| if x.is_held { x.ready_at.max(now + x.cook_secs) } else { x.ready_at })
+    .max();
+  match last_ready_at.or_else(|| party.map(|x| x.seated_at)) {
+    Some(x) => (x + dining_secs).max(now),
+    None => now,
+  }
+}
+
+// seconds to wait for the party at the position of the waitlist. tables are taken in the order of free_at
+// and each of them takes another party every dining_secs after that
+pub fn quote_wait(free_ats: &[i64], position: usize, dining_secs: i64, now: i64) -> i64 {
+  let mut free_ats = free_ats.to_vec();
+  free_ats.sort();
+  let at = free_ats[position % free_ats.len()] + (position / free_ats.len()) as i64 * dining_secs;
+  (at - now).max(0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn item_of(ready_at: i64, cook_secs: i64, is_held: bool) -> Item {
+    Item {
+      ready_at,
+      cook_secs,
+      is_held,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_free_at_of() {
+    let party = Party { name: "Sato".to_string(), party_size: 2, seated_at: 100 };
+    assert_eq!(1000, free_at_of(&[], None, 600, 1000));
+    assert_eq!(1000, free_at_of(&[], Some(&party), 600, 1000));
+    assert_eq!(700, free_at_of(&[], Some(&party), 600, 300));
+
+    // held item is cooked after now
+    let items = vec![item_of(500, 300, false), item_of(0, 300, true)];
+    assert_eq!(1500, free_at_of(&items, Some(&party), 600, 600));
+    assert_eq!(1100, free_at_of(&items[..1], None, 600, 600));
+  }
+
+  #[test]
+  fn test_quote_wait() {
+    let free_ats = vec![300, 100, 1000];
+    assert_eq!(0, quote_wait(&free_ats, 0, 600, 100));
+    assert_eq!(200, quote_wait(&free_ats, 1, 600, 100));
+    assert_eq!(900, quote_wait(&free_ats, 2, 600, 100));
+    assert_eq!(600, quote_wait(&free_ats, 3, 600, 100));
+  }
+
+  #[test]
+  fn test_waitlist() {
+    let entry_of = |id: &str| WaitlistEntry {
+      id: id.to_string(),
+      name: id.to_string(),
+      party_size: 2,
+      quoted_wait: 0,
+      status: WaitlistStatus::Waiting,
+      table_id: None,
+      created_at: 0,
+    };
+    let mut waitlist = Waitlist::new();
+    waitlist.add(entry_of("w1"));
+    waitlist.add(entry_of("w2"));
+    assert!(waitlist.update("w1", WaitlistStatus::Waiting, |x| x.status = WaitlistStatus::Left).is_some());
+    assert!(waitlist.update("w1", WaitlistStatus::Waiting, |x| x.status = WaitlistStatus::Seated).is_none());
+    assert_eq!(vec![entry_of("w2")], waitlist.get_waiting());
+    assert_eq!(WaitlistStatus::Left, waitlist.get("w1").unwrap().status);
+  }
+}
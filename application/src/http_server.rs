@@ -1,26 +1,90 @@
 use crate::{
-  item::Item,
+  item::{Item, CountRule},
   order_mgr::{OrderMgr, Error},
   clock::{
     clock::Clock,
   },
-  api::AddItemsParam,
+  api::{AddItemsParam, PayParam, RefundParam, SyncParam, StockParam, DecrementParam, ReservationParam, WaitlistParam, TakeoutParam, Quote},
+  reservation::Reservation,
+  floor::{TableStatus, WaitlistEntry},
+  takeout::Takeout,
+  query::{ItemFilter, ItemPage, ItemSort, ItemState},
+  sla::{LateItem, OverdueEvent, SlaConfig},
+  menu::{Menu, Station},
+  bill::{Bill, BillConfig, Rounding, SplitMode, SubBill},
+  payment::payment::{Payment, PaymentSummary},
+  printout::PrintFormat,
+  kitchen::KdsEvent,
+  expo::{ExpoSort, ExpoTable},
+  inventory::{Inventory, Stock, EightySixBoard},
+  allergen::{Restriction, Strictness},
+};
+use std::{
+  sync::Arc,
+  collections::hash_map::HashMap,
 };
-use std::sync::Arc;
 use rocket_contrib::json::Json;
 use rocket::{
   fairing::AdHoc,
-  {routes, post, get, delete, State},
-  http::Status,
+  {routes, post, get, put, delete, State},
+  http::{Status, ContentType},
+  response::Content,
 };
 
+// current time of the server in the same unit as the times of items
+pub const SERVER_TIME_HEADER: &str = "X-Server-Time";
+
+// text is the default format
+fn print_format_of(format: Option<String>) -> Result<PrintFormat, Status> {
+  format.map(|x| x.parse::<PrintFormat>()).unwrap_or(Ok(PrintFormat::Text))
+    .map_err(|_| Status::BadRequest)
+}
+
+fn item_state_of(state: Option<String>) -> Result<Option<ItemState>, Status> {
+  state.map(|x| x.parse::<ItemState>().map(Some)).unwrap_or(Ok(None))
+    .map_err(|_| Status::BadRequest)
+}
+
+// ready_at is the default sort key
+fn item_sort_of(sort: Option<String>) -> Result<ItemSort, Status> {
+  sort.map(|x| x.parse::<ItemSort>()).unwrap_or(Ok(ItemSort::ReadyAt))
+    .map_err(|_| Status::BadRequest)
+}
+
+fn content_type_of(format: PrintFormat) -> ContentType {
+  match format {
+    PrintFormat::Text => ContentType::Plain,
+    PrintFormat::Html => ContentType::HTML,
+    PrintFormat::EscPos => ContentType::Binary,
+  }
+}
+
 macro_rules! return_result {
   ($res: expr) => {
+    return_result!($res, Json)
+  };
+  ($res: expr, $wrap: expr) => {
     match $res {
-      Ok(x) => Ok(Json(x)),
+      Ok(x) => Ok($wrap(x)),
       Err(Error::ItemNotFound) => Err(Status::NotFound),
       Err(Error::MaxItemsExceeded) => Err(Status::TooManyRequests),
       Err(Error::BadTableId(_id)) => Err(Status::NotAcceptable),
+      Err(Error::BadSeat(_seat)) => Err(Status::NotAcceptable),
+      Err(Error::BadParam(_name)) => Err(Status::BadRequest),
+      Err(Error::PaymentNotFound) => Err(Status::NotFound),
+      Err(Error::PaymentDeclined) => Err(Status::PaymentRequired),
+      Err(Error::BillNotSettled) => Err(Status::Conflict),
+      Err(Error::BadItemState) => Err(Status::Conflict),
+      Err(Error::OutOfStock(_name)) => Err(Status::Gone),
+      Err(Error::AllergenConflict(_name)) => Err(Status::UnprocessableEntity),
+      Err(Error::ReservationNotFound) => Err(Status::NotFound),
+      Err(Error::BadReservationState) => Err(Status::Conflict),
+      Err(Error::TableOccupied) => Err(Status::Conflict),
+      Err(Error::WaitlistEntryNotFound) => Err(Status::NotFound),
+      Err(Error::BadWaitlistState) => Err(Status::Conflict),
+      Err(Error::TakeoutNotFound) => Err(Status::NotFound),
+      Err(Error::BadTakeoutState) => Err(Status::Conflict),
+      Err(Error::MaxTakeoutsExceeded) => Err(Status::TooManyRequests),
     }
   };
 }
@@ -31,7 +95,16 @@ pub fn add_items(
   req: Json<AddItemsParam>,
   order_mgr: State<OrderMgr>,
 ) -> Result<Json<Vec<Item>>, Status> {
-  return_result!(order_mgr.add_items(table_id, &req.item_names))
+  return_result!(order_mgr.add_items(table_id, &req))
+}
+
+#[post("/table/<table_id>/items/quote", data = "<req>")]
+pub fn quote_items(
+  table_id: usize,
+  req: Json<AddItemsParam>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<Quote>>, Status> {
+  return_result!(order_mgr.quote_items(table_id, &req))
 }
 
 #[delete("/table/<table_id>/item/<uuid>")]
@@ -43,12 +116,54 @@ pub fn remove_item(
   return_result!(order_mgr.remove_item(table_id, &uuid))
 }
 
-#[get("/table/<table_id>/items")]
+#[post("/table/<table_id>/item/<uuid>/restore")]
+pub fn restore_item(
+  table_id: usize,
+  uuid: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Item>, Status> {
+  return_result!(order_mgr.restore_item(table_id, &uuid))
+}
+
+#[post("/table/<table_id>/item/<uuid>/decrement", data = "<req>")]
+pub fn decrement_item(
+  table_id: usize,
+  uuid: String,
+  req: Json<DecrementParam>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Item>, Status> {
+  return_result!(order_mgr.decrement_item(table_id, &uuid, &req))
+}
+
+#[get("/table/<table_id>/items?<seat>&<state>&<name_prefix>&<sort>&<offset>&<limit>")]
 pub fn get_all_items(
   table_id: usize,
+  seat: Option<usize>,
+  state: Option<String>,
+  name_prefix: Option<String>,
+  sort: Option<String>,
+  offset: Option<usize>,
+  limit: Option<usize>,
   order_mgr: State<OrderMgr>,
 ) -> Result<Json<Vec<Item>>, Status> {
-  return_result!(order_mgr.get_all_items(table_id))
+  let filter = ItemFilter { seat, state: item_state_of(state)?, name_prefix, ..Default::default() };
+  let sort = item_sort_of(sort)?;
+  return_result!(order_mgr.get_table_items(table_id, &filter, sort, offset.unwrap_or(0), limit))
+}
+
+#[get("/items?<table_id>&<name>&<state>&<from>&<to>&<cursor>&<limit>")]
+pub fn get_items(
+  table_id: Option<usize>,
+  name: Option<String>,
+  state: Option<String>,
+  from: Option<i64>,
+  to: Option<i64>,
+  cursor: Option<String>,
+  limit: Option<usize>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<ItemPage>, Status> {
+  let filter = ItemFilter { table_id, name, state: item_state_of(state)?, from, to, ..Default::default() };
+  return_result!(order_mgr.get_items(&filter, cursor.as_deref(), limit))
 }
 
 #[get("/table/<table_id>/item/<uuid>")]
@@ -60,17 +175,483 @@ pub fn get_item(
   return_result!(order_mgr.get_item(table_id, &uuid))
 }
 
+#[get("/station/<station>/items")]
+pub fn get_station_items(
+  station: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<Item>>, Status> {
+  let station = station.parse::<Station>().map_err(|_| Status::NotFound)?;
+  return_result!(order_mgr.get_station_items(station))
+}
+
+#[post("/kds/table/<table_id>/item/<uuid>/start")]
+pub fn start_item(
+  table_id: usize,
+  uuid: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Item>, Status> {
+  return_result!(order_mgr.start_item(table_id, &uuid))
+}
+
+#[post("/kds/table/<table_id>/item/<uuid>/bump")]
+pub fn bump_item(
+  table_id: usize,
+  uuid: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Item>, Status> {
+  return_result!(order_mgr.bump_item(table_id, &uuid))
+}
+
+#[post("/kds/table/<table_id>/item/<uuid>/recall")]
+pub fn recall_item(
+  table_id: usize,
+  uuid: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Item>, Status> {
+  return_result!(order_mgr.recall_item(table_id, &uuid))
+}
+
+#[post("/kds/takeout/<takeout_id>/item/<uuid>/start")]
+pub fn start_takeout_item(
+  takeout_id: String,
+  uuid: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Item>, Status> {
+  return_result!(order_mgr.start_takeout_item(&takeout_id, &uuid))
+}
+
+#[post("/kds/takeout/<takeout_id>/item/<uuid>/bump")]
+pub fn bump_takeout_item(
+  takeout_id: String,
+  uuid: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Item>, Status> {
+  return_result!(order_mgr.bump_takeout_item(&takeout_id, &uuid))
+}
+
+#[post("/kds/takeout/<takeout_id>/item/<uuid>/recall")]
+pub fn recall_takeout_item(
+  takeout_id: String,
+  uuid: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Item>, Status> {
+  return_result!(order_mgr.recall_takeout_item(&takeout_id, &uuid))
+}
+
+#[get("/kds/events?<station>")]
+pub fn get_kds_events(
+  station: Option<String>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<KdsEvent>>, Status> {
+  let station = match station {
+    Some(x) => Some(x.parse::<Station>().map_err(|_| Status::NotFound)?),
+    None => None,
+  };
+  return_result!(order_mgr.get_kds_events(station))
+}
+
+#[post("/table/<table_id>/item/<uuid>/serve")]
+pub fn serve_item(
+  table_id: usize,
+  uuid: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Item>, Status> {
+  return_result!(order_mgr.serve_item(table_id, &uuid))
+}
+
+#[get("/expo?<sort>")]
+pub fn get_expo(
+  sort: Option<String>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<ExpoTable>>, Status> {
+  let sort = sort.map(|x| x.parse::<ExpoSort>()).unwrap_or(Ok(ExpoSort::TableId))
+    .map_err(|_| Status::BadRequest)?;
+  return_result!(order_mgr.get_expo(sort))
+}
+
+#[get("/expo/late")]
+pub fn get_late_items(
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<LateItem>>, Status> {
+  return_result!(order_mgr.get_late_items())
+}
+
+#[get("/expo/overdue?<station>")]
+pub fn get_overdue_events(
+  station: Option<String>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<OverdueEvent>>, Status> {
+  let station = match station {
+    Some(x) => Some(x.parse::<Station>().map_err(|_| Status::NotFound)?),
+    None => None,
+  };
+  return_result!(order_mgr.get_overdue_events(station))
+}
+
+#[get("/inventory")]
+pub fn get_stocks(
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<Stock>>, Status> {
+  return_result!(order_mgr.get_stocks())
+}
+
+#[get("/inventory/alerts")]
+pub fn get_low_stocks(
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<Stock>>, Status> {
+  return_result!(order_mgr.get_low_stocks())
+}
+
+#[put("/inventory/<ingredient>", data = "<req>")]
+pub fn set_stock(
+  ingredient: String,
+  req: Json<StockParam>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Stock>, Status> {
+  return_result!(order_mgr.set_stock(&ingredient, &req))
+}
+
+#[get("/86")]
+pub fn get_86_board(
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<EightySixBoard>, Status> {
+  return_result!(order_mgr.get_86_board())
+}
+
+#[put("/86/<dish>")]
+pub fn eighty_six(
+  dish: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<()>, Status> {
+  return_result!(order_mgr.eighty_six(&dish))
+}
+
+#[delete("/86/<dish>")]
+pub fn un_eighty_six(
+  dish: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<()>, Status> {
+  return_result!(order_mgr.un_eighty_six(&dish))
+}
+
+#[put("/table/<table_id>/sync", data = "<req>")]
+pub fn set_sync_ready(
+  table_id: usize,
+  req: Json<SyncParam>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<()>, Status> {
+  return_result!(order_mgr.set_sync_ready(table_id, &req))
+}
+
+#[post("/reservations", data = "<req>")]
+pub fn create_reservation(
+  req: Json<ReservationParam>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Reservation>, Status> {
+  return_result!(order_mgr.create_reservation(&req))
+}
+
+#[get("/reservations")]
+pub fn get_reservations(
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<Reservation>>, Status> {
+  return_result!(order_mgr.get_reservations())
+}
+
+#[get("/reservations/<id>")]
+pub fn get_reservation(
+  id: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Reservation>, Status> {
+  return_result!(order_mgr.get_reservation(&id))
+}
+
+#[put("/reservations/<id>", data = "<req>")]
+pub fn update_reservation(
+  id: String,
+  req: Json<ReservationParam>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Reservation>, Status> {
+  return_result!(order_mgr.update_reservation(&id, &req))
+}
+
+#[delete("/reservations/<id>")]
+pub fn cancel_reservation(
+  id: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Reservation>, Status> {
+  return_result!(order_mgr.cancel_reservation(&id))
+}
+
+#[post("/reservations/<id>/seat?<table_id>")]
+pub fn seat_reservation(
+  id: String,
+  table_id: Option<usize>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<Item>>, Status> {
+  return_result!(order_mgr.seat_reservation(&id, table_id))
+}
+
+#[post("/waitlist", data = "<req>")]
+pub fn join_waitlist(
+  req: Json<WaitlistParam>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<WaitlistEntry>, Status> {
+  return_result!(order_mgr.join_waitlist(&req))
+}
+
+#[get("/waitlist")]
+pub fn get_waitlist(
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<WaitlistEntry>>, Status> {
+  return_result!(order_mgr.get_waitlist())
+}
+
+#[delete("/waitlist/<id>")]
+pub fn leave_waitlist(
+  id: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<WaitlistEntry>, Status> {
+  return_result!(order_mgr.leave_waitlist(&id))
+}
+
+#[post("/waitlist/<id>/seat?<table_id>")]
+pub fn seat_waitlist(
+  id: String,
+  table_id: Option<usize>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<WaitlistEntry>, Status> {
+  return_result!(order_mgr.seat_waitlist(&id, table_id))
+}
+
+#[post("/takeouts", data = "<req>")]
+pub fn create_takeout(
+  req: Json<TakeoutParam>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Takeout>, Status> {
+  return_result!(order_mgr.create_takeout(&req))
+}
+
+#[get("/takeouts")]
+pub fn get_takeouts(
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<Takeout>>, Status> {
+  return_result!(order_mgr.get_takeouts())
+}
+
+#[get("/takeouts/<id>")]
+pub fn get_takeout(
+  id: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Takeout>, Status> {
+  return_result!(order_mgr.get_takeout(&id))
+}
+
+#[get("/takeouts/<id>/items")]
+pub fn get_takeout_items(
+  id: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<Item>>, Status> {
+  return_result!(order_mgr.get_takeout_items(&id))
+}
+
+#[post("/takeouts/<id>/complete")]
+pub fn complete_takeout(
+  id: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Takeout>, Status> {
+  return_result!(order_mgr.complete_takeout(&id))
+}
+
+#[delete("/takeouts/<id>")]
+pub fn cancel_takeout(
+  id: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Takeout>, Status> {
+  return_result!(order_mgr.cancel_takeout(&id))
+}
+
+#[get("/tables")]
+pub fn get_tables(
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<TableStatus>>, Status> {
+  return_result!(order_mgr.get_tables())
+}
+
+#[put("/table/<table_id>/allergies", data = "<req>")]
+pub fn set_restriction(
+  table_id: usize,
+  req: Json<Restriction>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<()>, Status> {
+  return_result!(order_mgr.set_restriction(table_id, &req))
+}
+
+#[get("/table/<table_id>/allergies")]
+pub fn get_restrictions(
+  table_id: usize,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<Restriction>>, Status> {
+  return_result!(order_mgr.get_restrictions(table_id))
+}
+
+#[post("/table/<table_id>/course/<course>/fire")]
+pub fn fire_course(
+  table_id: usize,
+  course: usize,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<Item>>, Status> {
+  return_result!(order_mgr.fire_course(table_id, course))
+}
+
+#[post("/table/<table_id>/item/<uuid>/void")]
+pub fn void_item(
+  table_id: usize,
+  uuid: String,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Item>, Status> {
+  return_result!(order_mgr.void_item(table_id, &uuid))
+}
+
+#[get("/table/<table_id>/bill")]
+pub fn get_bill(
+  table_id: usize,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Bill>, Status> {
+  return_result!(order_mgr.get_bill(table_id))
+}
+
+#[post("/table/<table_id>/bill/split", data = "<req>")]
+pub fn split_bill(
+  table_id: usize,
+  req: Json<SplitMode>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Vec<SubBill>>, Status> {
+  return_result!(order_mgr.split_bill(table_id, &req))
+}
+
+#[get("/table/<table_id>/receipt?<format>")]
+pub fn get_receipt(
+  table_id: usize,
+  format: Option<String>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Content<Vec<u8>>, Status> {
+  let format = print_format_of(format)?;
+  return_result!(order_mgr.get_receipt(table_id, format), |x| Content(content_type_of(format), x))
+}
+
+#[get("/table/<table_id>/ticket?<format>")]
+pub fn get_ticket(
+  table_id: usize,
+  format: Option<String>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Content<Vec<u8>>, Status> {
+  let format = print_format_of(format)?;
+  return_result!(order_mgr.get_ticket(table_id, format), |x| Content(content_type_of(format), x))
+}
+
+#[post("/table/<table_id>/payments", data = "<req>")]
+pub fn pay(
+  table_id: usize,
+  req: Json<PayParam>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Payment>, Status> {
+  return_result!(order_mgr.pay(table_id, &req))
+}
+
+#[get("/table/<table_id>/payments")]
+pub fn get_payments(
+  table_id: usize,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<PaymentSummary>, Status> {
+  return_result!(order_mgr.get_payments(table_id))
+}
+
+#[post("/table/<table_id>/payment/<payment_id>/refund", data = "<req>")]
+pub fn refund(
+  table_id: usize,
+  payment_id: String,
+  req: Json<RefundParam>,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<Payment>, Status> {
+  return_result!(order_mgr.refund(table_id, &payment_id, &req))
+}
+
+#[post("/table/<table_id>/close")]
+pub fn close_session(
+  table_id: usize,
+  order_mgr: State<OrderMgr>,
+) -> Result<Json<()>, Status> {
+  return_result!(order_mgr.close_session(table_id))
+}
+
 pub fn build_rocket(clock: Arc<dyn Clock>) -> rocket::Rocket {
+  let server_clock = clock.clone();
   rocket::ignite()
     .mount(
       "/v1",
       routes![
         add_items,
+        quote_items,
         remove_item,
+        restore_item,
+        decrement_item,
         get_all_items,
+        get_items,
         get_item,
+        get_station_items,
+        start_item,
+        bump_item,
+        recall_item,
+        start_takeout_item,
+        bump_takeout_item,
+        recall_takeout_item,
+        get_kds_events,
+        fire_course,
+        set_sync_ready,
+        set_restriction,
+        get_restrictions,
+        create_reservation,
+        get_reservations,
+        get_reservation,
+        update_reservation,
+        cancel_reservation,
+        seat_reservation,
+        join_waitlist,
+        get_waitlist,
+        leave_waitlist,
+        seat_waitlist,
+        get_tables,
+        create_takeout,
+        get_takeouts,
+        get_takeout,
+        get_takeout_items,
+        complete_takeout,
+        cancel_takeout,
+        serve_item,
+        get_expo,
+        get_late_items,
+        get_overdue_events,
+        get_stocks,
+        get_low_stocks,
+        set_stock,
+        get_86_board,
+        eighty_six,
+        un_eighty_six,
+        void_item,
+        get_bill,
+        split_bill,
+        get_receipt,
+        get_ticket,
+        pay,
+        get_payments,
+        refund,
+        close_session,
       ],
     )
+    .attach(AdHoc::on_response("Server Time", move |_, res| {
+      res.set_raw_header(SERVER_TIME_HEADER, server_clock.now().to_string());
+    }))
     .attach(AdHoc::on_attach("Order Manager", move |rocket| {
       let num_tables = rocket.config().get_int("num_tables").unwrap() as usize;
       if num_tables == 0 {
@@ -84,7 +665,121 @@ pub fn build_rocket(clock: Arc<dyn Clock>) -> rocket::Rocket {
       if one_min_in_sec < 1 {
         panic!("one_min_in_sec must be a positive integer")
       }
-      let order_mgr = OrderMgr::new(num_tables, max_table_items, one_min_in_sec, clock);
+      let num_seats = rocket.config().get_int("num_seats").unwrap_or(4);
+      if num_seats < 1 {
+        panic!("num_seats must be a positive integer")
+      }
+      let table_seats: HashMap<usize, usize> = match rocket.config().get_table("table_seats") {
+        Ok(table) => table.iter().map(|(k, v)| {
+          let table_id = match k.parse::<usize>() {
+            Ok(x) if x < num_tables => x,
+            _ => panic!("table_seats must be keyed by table ids"),
+          };
+          match v.as_integer() {
+            Some(x) if x > 0 => (table_id, x as usize),
+            _ => panic!("table_seats must be positive integers"),
+          }
+        }).collect(),
+        Err(_) => HashMap::new(),
+      };
+      let menu = match rocket.config().get_str("menu_file") {
+        Ok(path) => Menu::from_file(rocket.config().root_relative(path)).unwrap(),
+        Err(_) => Menu::new(vec![]),
+      };
+      let tax_rate_bp = rocket.config().get_int("tax_rate_bp").unwrap_or(0);
+      if tax_rate_bp < 0 {
+        panic!("tax_rate_bp must be a non-negative integer")
+      }
+      let service_charge_bp = rocket.config().get_int("service_charge_bp").unwrap_or(0);
+      if service_charge_bp < 0 {
+        panic!("service_charge_bp must be a non-negative integer")
+      }
+      let rounding = rocket.config().get_str("rounding").unwrap_or("half_up")
+        .parse::<Rounding>().unwrap();
+      let bill_config = BillConfig {
+        tax_rate_bp,
+        service_charge_bp,
+        rounding,
+      };
+      let station_capacities: HashMap<Station, usize> = match rocket.config().get_table("station_capacity") {
+        Ok(table) => table.iter().map(|(k, v)| {
+          let station = k.parse::<Station>().unwrap();
+          match v.as_integer() {
+            Some(x) if x > 0 => (station, x as usize),
+            _ => panic!("station_capacity must be positive integers"),
+          }
+        }).collect(),
+        Err(_) => HashMap::new(),
+      };
+      let print_width = rocket.config().get_int("print_width").unwrap_or(42);
+      if print_width < 20 {
+        panic!("print_width must be 20 or larger")
+      }
+      let max_note_len = rocket.config().get_int("max_note_len").unwrap_or(140);
+      if max_note_len < 0 {
+        panic!("max_note_len must be a non-negative integer")
+      }
+      let count_rule = rocket.config().get_str("count_rule").unwrap_or("lines")
+        .parse::<CountRule>().unwrap();
+      let auto_fire = rocket.config().get_bool("auto_fire").unwrap_or(false);
+      let allergen_strictness = rocket.config().get_str("allergen_strictness").unwrap_or("flag")
+        .parse::<Strictness>().unwrap();
+      let default_cook_mins = rocket.config().get_int("default_cook_mins").unwrap_or(10);
+      if default_cook_mins < 1 {
+        panic!("default_cook_mins must be a positive integer")
+      }
+      let max_quantity = rocket.config().get_int("max_quantity").unwrap_or(99);
+      if max_quantity < 1 {
+        panic!("max_quantity must be a positive integer")
+      }
+      let dining_mins = rocket.config().get_int("dining_mins").unwrap_or(45);
+      if dining_mins < 1 {
+        panic!("dining_mins must be a positive integer")
+      }
+      let max_takeouts = rocket.config().get_int("max_takeouts").unwrap_or(20);
+      if max_takeouts < 0 {
+        panic!("max_takeouts must be a non-negative integer")
+      }
+      let sla_mins = rocket.config().get_int("sla_mins").unwrap_or(10);
+      if sla_mins < 0 {
+        panic!("sla_mins must be a non-negative integer")
+      }
+      let restore_grace_mins = rocket.config().get_int("restore_grace_mins").unwrap_or(5);
+      if restore_grace_mins < 0 {
+        panic!("restore_grace_mins must be a non-negative integer")
+      }
+      let station_sla_mins: HashMap<Station, i64> = match rocket.config().get_table("station_sla_mins") {
+        Ok(table) => table.iter().map(|(k, v)| {
+          let station = k.parse::<Station>().unwrap();
+          match v.as_integer() {
+            Some(x) if x >= 0 => (station, x),
+            _ => panic!("station_sla_mins must be non-negative integers"),
+          }
+        }).collect(),
+        Err(_) => HashMap::new(),
+      };
+      let inventory = match rocket.config().get_str("inventory_file") {
+        Ok(path) => Inventory::from_file(rocket.config().root_relative(path)).unwrap(),
+        Err(_) => Inventory::new(vec![]),
+      };
+      let order_mgr = OrderMgr::new(num_tables, max_table_items, one_min_in_sec, clock)
+        .with_num_seats(num_seats as usize)
+        .with_table_seats(table_seats)
+        .with_menu(menu)
+        .with_bill_config(bill_config)
+        .with_station_capacities(station_capacities)
+        .with_print_width(print_width as usize)
+        .with_max_note_len(max_note_len as usize)
+        .with_count_rule(count_rule)
+        .with_auto_fire(auto_fire)
+        .with_allergen_strictness(allergen_strictness)
+        .with_default_cook_mins(default_cook_mins)
+        .with_max_quantity(max_quantity as usize)
+        .with_dining_mins(dining_mins)
+        .with_max_takeouts(max_takeouts as usize)
+        .with_sla_config(SlaConfig { sla_mins, station_sla_mins })
+        .with_restore_grace_mins(restore_grace_mins)
+        .with_inventory(inventory);
 
       Ok(rocket.manage(order_mgr))
     }))
@@ -97,7 +792,10 @@ mod tests {
     sync::atomic::Ordering,
     sync::Arc,
   };
-  use crate::clock::arbitrary_clock::ArbitraryClock;
+  use crate::{
+    clock::arbitrary_clock::ArbitraryClock,
+    takeout::TakeoutStatus,
+  };
   use rocket::{
     local::Client,
     http::Status,
@@ -106,6 +804,16 @@ mod tests {
   fn add_req(item_names: Vec<&str>) -> String {
     let req = AddItemsParam {
       item_names: item_names.into_iter().map(|x| x.to_string()).collect(),
+      ..Default::default()
+    };
+    serde_json::to_string(&req).unwrap()
+  }
+
+  fn add_seat_req(item_names: Vec<&str>, seats: Vec<Option<usize>>) -> String {
+    let req = AddItemsParam {
+      item_names: item_names.into_iter().map(|x| x.to_string()).collect(),
+      seats,
+      ..Default::default()
     };
     serde_json::to_string(&req).unwrap()
   }
@@ -194,27 +902,61 @@ mod tests {
   }
 
   #[test]
-  fn test_remove_item() {
+  fn test_get_seat_items() {
     let rocket = build_rocket(get_clock());
     let cli = Client::new(rocket).unwrap();
 
-    // add items
-    let mut res = cli.post("/v1/table/0/items").body(add_req(vec!["ramen", "soba", "tamago"])).dispatch();
+    // add items to seats 0 and 1
+    let req = add_seat_req(vec!["ramen", "soba", "tamago"], vec![Some(0), Some(1), Some(0)]);
+    let res = cli.post("/v1/table/0/items").body(req).dispatch();
     assert_eq!(Status::Ok, res.status());
 
-    let mut added_items = match serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()) {
+    // get items of seat 0
+    let mut res = cli.get("/v1/table/0/items?seat=0").dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    let mut got_items = match serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()) {
       Ok(xs) => xs,
       Err(_) => { assert!(false); vec![] },
     };
-    added_items.sort_by(|a, b| a.name.cmp(&b.name));
+    got_items.sort_by(|a, b| a.name.cmp(&b.name));
 
-    // remove ramen
-    let res = cli.delete(format!("/v1/table/0/item/{}", added_items[0].uuid)).dispatch();
-    assert_eq!(Status::Ok, res.status());
+    assert_eq!(2, got_items.len());
+    assert_eq!("ramen", got_items[0].name);
+    assert_eq!("tamago", got_items[1].name);
 
-    // ramen is no longer available
-    let res = cli.get(format!("/v1/table/0/item/{}", added_items[0].uuid)).dispatch();
-    assert_eq!(Status::NotFound, res.status());
+    // seat out of range should fail
+    let res = cli.get("/v1/table/0/items?seat=100").dispatch();
+    assert_eq!(Status::NotAcceptable, res.status());
+
+    // seat count mismatch should fail
+    let req = add_seat_req(vec!["ramen", "soba"], vec![Some(0)]);
+    let res = cli.post("/v1/table/0/items").body(req).dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+  }
+
+  #[test]
+  fn test_remove_item() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    // add items
+    let mut res = cli.post("/v1/table/0/items").body(add_req(vec!["ramen", "soba", "tamago"])).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    let mut added_items = match serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()) {
+      Ok(xs) => xs,
+      Err(_) => { assert!(false); vec![] },
+    };
+    added_items.sort_by(|a, b| a.name.cmp(&b.name));
+
+    // remove ramen
+    let res = cli.delete(format!("/v1/table/0/item/{}", added_items[0].uuid)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    // ramen is no longer available
+    let res = cli.get(format!("/v1/table/0/item/{}", added_items[0].uuid)).dispatch();
+    assert_eq!(Status::NotFound, res.status());
 
     // remove soba and tamago
     let res = cli.delete(format!("/v1/table/0/item/{}", added_items[1].uuid)).dispatch();
@@ -224,6 +966,36 @@ mod tests {
     assert_eq!(Status::Ok, res.status());
   }
 
+  #[test]
+  fn test_restore_item() {
+    let clock = get_clock();
+    let rocket = build_rocket(clock.clone());
+    let cli = Client::new(rocket).unwrap();
+
+    let mut res = cli.post("/v1/table/0/items").body(add_req(vec!["ramen"])).dispatch();
+    let item = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap().remove(0);
+    let res = cli.delete(format!("/v1/table/0/item/{}", item.uuid)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    // mis-tapped ramen comes back w/ its original ready_at
+    clock.now.store(1, Ordering::Relaxed);
+    let mut res = cli.post(format!("/v1/table/0/item/{}/restore", item.uuid)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let restored = serde_json::from_str::<Item>(&res.body_string().unwrap()).unwrap();
+    assert_eq!((false, item.ready_at), (restored.is_removed, restored.ready_at));
+    let res = cli.get(format!("/v1/table/0/item/{}", item.uuid)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let res = cli.post(format!("/v1/table/0/item/{}/restore", item.uuid)).dispatch();
+    assert_eq!(Status::Conflict, res.status());
+
+    // purged after restore_grace_mins
+    let res = cli.delete(format!("/v1/table/0/item/{}", item.uuid)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    clock.now.store(6, Ordering::Relaxed);
+    let res = cli.post(format!("/v1/table/0/item/{}/restore", item.uuid)).dispatch();
+    assert_eq!(Status::NotFound, res.status());
+  }
+
   #[test]
   fn test_items_served() {
     let clock = get_clock();
@@ -254,4 +1026,686 @@ mod tests {
     let res = cli.get(format!("/v1/table/0/item/{}", item_cooked_first.uuid)).dispatch();
     assert_eq!(Status::NotFound, res.status());
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn test_get_bill() {
+    let clock = get_clock();
+    let rocket = build_rocket(clock.clone());
+    let cli = Client::new(rocket).unwrap();
+
+    // add items
+    let mut res = cli.post("/v1/table/0/items").body(add_req(vec!["ramen", "gyoza", "beer"])).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    let added_items = match serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()) {
+      Ok(xs) => xs,
+      Err(_) => { assert!(false); vec![] },
+    };
+
+    // cancel gyoza
+    let res = cli.delete(format!("/v1/table/0/item/{}", added_items[1].uuid)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    // void beer after it's served
+    clock.now.store(added_items[2].ready_at, Ordering::Relaxed);
+    let res = cli.post(format!("/v1/table/0/item/{}/void", added_items[2].uuid)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    // only ramen should be on the bill
+    let mut res = cli.get("/v1/table/0/bill").dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    let bill = match serde_json::from_str::<Bill>(&res.body_string().unwrap()) {
+      Ok(x) => x,
+      Err(_) => { assert!(false); return },
+    };
+    assert_eq!(1, bill.lines.len());
+    assert_eq!("ramen", bill.lines[0].name);
+    assert_eq!(bill.subtotal + bill.service_charge + bill.tax, bill.total);
+
+    // bad table id should fail
+    let res = cli.get("/v1/table/100/bill").dispatch();
+    assert_eq!(Status::NotAcceptable, res.status());
+  }
+
+  #[test]
+  fn test_split_bill() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    // add items
+    let req = add_seat_req(vec!["ramen", "gyoza", "beer"], vec![Some(0), Some(1), Some(1)]);
+    let res = cli.post("/v1/table/0/items").body(req).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    let mut res = cli.get("/v1/table/0/bill").dispatch();
+    let bill = match serde_json::from_str::<Bill>(&res.body_string().unwrap()) {
+      Ok(x) => x,
+      Err(_) => { assert!(false); return },
+    };
+
+    // split by seat
+    let mut res = cli.post("/v1/table/0/bill/split").body(r#"{"mode":"seat"}"#).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    let sub_bills = match serde_json::from_str::<Vec<SubBill>>(&res.body_string().unwrap()) {
+      Ok(xs) => xs,
+      Err(_) => { assert!(false); vec![] },
+    };
+    assert_eq!(2, sub_bills.len());
+    assert_eq!(bill.total, sub_bills.iter().map(|x| x.total).sum::<i64>());
+
+    // split evenly by 0 payers should fail
+    let res = cli.post("/v1/table/0/bill/split").body(r#"{"mode":"even","num_payers":0}"#).dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+
+    // more payers than the seats of the table
+    let res = cli.post("/v1/table/0/bill/split").body(r#"{"mode":"even","num_payers":4000000000}"#).dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+  }
+
+  #[test]
+  fn test_pay_and_close_session() {
+    let clock = get_clock();
+    let rocket = build_rocket(clock.clone());
+    let cli = Client::new(rocket).unwrap();
+
+    // add items
+    let res = cli.post("/v1/table/0/items").body(add_req(vec!["ramen", "gyoza"])).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    let mut res = cli.get("/v1/table/0/bill").dispatch();
+    let bill = match serde_json::from_str::<Bill>(&res.body_string().unwrap()) {
+      Ok(x) => x,
+      Err(_) => { assert!(false); return },
+    };
+
+    // cannot close before paying
+    let res = cli.post("/v1/table/0/close").dispatch();
+    assert_eq!(Status::Conflict, res.status());
+
+    // pay by card w/ tip and then by cash
+    let req = format!(r#"{{"tender":"card","amount":{},"tip":100}}"#, bill.total - 100);
+    let mut res = cli.post("/v1/table/0/payments").body(req).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    let payment = match serde_json::from_str::<Payment>(&res.body_string().unwrap()) {
+      Ok(x) => x,
+      Err(_) => { assert!(false); return },
+    };
+
+    let res = cli.post("/v1/table/0/payments").body(r#"{"tender":"cash","amount":100}"#).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    // overpayment should fail
+    let res = cli.post("/v1/table/0/payments").body(r#"{"tender":"cash","amount":1}"#).dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+
+    // refund makes the bill unsettled again
+    let res = cli.post(format!("/v1/table/0/payment/{}/refund", payment.id)).body(r#"{"amount":50}"#).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    let mut res = cli.get("/v1/table/0/payments").dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    let summary = match serde_json::from_str::<PaymentSummary>(&res.body_string().unwrap()) {
+      Ok(x) => x,
+      Err(_) => { assert!(false); return },
+    };
+    assert_eq!(50, summary.balance);
+    assert_eq!(100, summary.tip);
+
+    let res = cli.post("/v1/table/0/close").dispatch();
+    assert_eq!(Status::Conflict, res.status());
+
+    // pay the rest and close
+    let res = cli.post("/v1/table/0/payments").body(r#"{"tender":"voucher","amount":50}"#).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    // items being cooked block closing
+    let res = cli.post("/v1/table/0/close").dispatch();
+    assert_eq!(Status::Conflict, res.status());
+
+    clock.now.store(86400, Ordering::Relaxed);
+    let res = cli.post("/v1/table/0/close").dispatch();
+    assert_eq!(Status::Ok, res.status());
+  }
+
+  #[test]
+  fn test_get_receipt_and_ticket() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    // add items
+    let res = cli.post("/v1/table/0/items").body(add_req(vec!["ramen", "gyoza"])).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    // receipt is plain text by default
+    let mut res = cli.get("/v1/table/0/receipt").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    assert_eq!(Some(ContentType::Plain), res.content_type());
+    assert!(res.body_string().unwrap().contains("RECEIPT"));
+
+    let mut res = cli.get("/v1/table/0/receipt?format=html").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    assert_eq!(Some(ContentType::HTML), res.content_type());
+    assert!(res.body_string().unwrap().contains("<h1>RECEIPT</h1>"));
+
+    // ESC/POS starts w/ printer initialization
+    let mut res = cli.get("/v1/table/0/ticket?format=escpos").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    assert_eq!(Some(ContentType::Binary), res.content_type());
+    assert_eq!(vec![0x1b, b'@'], res.body_bytes().unwrap()[..2].to_vec());
+
+    // unknown format should fail
+    let res = cli.get("/v1/table/0/ticket?format=pdf").dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+  }
+
+  #[test]
+  fn test_get_station_items() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    // add items to 2 tables
+    let res = cli.post("/v1/table/0/items").body(add_req(vec!["ramen", "beer"])).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let res = cli.post("/v1/table/1/items").body(add_req(vec!["soba", "gyoza"])).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    // grill should see ramen and soba in the order of ready_at
+    let mut res = cli.get("/v1/station/grill/items").dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    let got_items = match serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()) {
+      Ok(xs) => xs,
+      Err(_) => { assert!(false); vec![] },
+    };
+    assert_eq!(2, got_items.len());
+    assert!(got_items[0].ready_at <= got_items[1].ready_at);
+    assert!(got_items.iter().all(|x| x.station == Some(Station::Grill)));
+
+    // unknown station should fail
+    let res = cli.get("/v1/station/oven/items").dispatch();
+    assert_eq!(Status::NotFound, res.status());
+  }
+
+  #[test]
+  fn test_kds() {
+    let clock = get_clock();
+    let rocket = build_rocket(clock.clone());
+    let cli = Client::new(rocket).unwrap();
+
+    let mut res = cli.post("/v1/table/0/items").body(add_req(vec!["ramen"])).dispatch();
+    let item = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap().remove(0);
+
+    let mut res = cli.get("/v1/station/grill/items").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let items = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(vec![item.uuid.clone()], items.iter().map(|x| x.uuid.clone()).collect::<Vec<String>>());
+
+    // start twice should fail
+    clock.now.store(1, Ordering::Relaxed);
+    let res = cli.post(format!("/v1/kds/table/0/item/{}/start", item.uuid)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let res = cli.post(format!("/v1/kds/table/0/item/{}/start", item.uuid)).dispatch();
+    assert_eq!(Status::Conflict, res.status());
+
+    // bumped item leaves the station and can be recalled only once
+    clock.now.store(2, Ordering::Relaxed);
+    let res = cli.post(format!("/v1/kds/table/0/item/{}/bump", item.uuid)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let mut res = cli.get("/v1/station/grill/items").dispatch();
+    assert_eq!("[]", res.body_string().unwrap());
+
+    let res = cli.post(format!("/v1/kds/table/0/item/{}/recall", item.uuid)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let res = cli.post(format!("/v1/kds/table/0/item/{}/recall", item.uuid)).dispatch();
+    assert_eq!(Status::Conflict, res.status());
+
+    let res = cli.post("/v1/kds/table/0/item/foo/bump").dispatch();
+    assert_eq!(Status::NotFound, res.status());
+
+    let mut res = cli.get("/v1/kds/events?station=grill").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let events = serde_json::from_str::<Vec<KdsEvent>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(vec![1, 2, 2], events.iter().map(|x| x.at).collect::<Vec<i64>>());
+
+    let res = cli.get("/v1/kds/events?station=oven").dispatch();
+    assert_eq!(Status::NotFound, res.status());
+  }
+
+  #[test]
+  fn test_fire_course() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    let req = r#"{"item_names":["tamago","ramen"],"courses":[1,2]}"#;
+    let mut res = cli.post("/v1/table/0/items").body(req).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let items = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(vec![false, true], items.iter().map(|x| x.is_held).collect::<Vec<bool>>());
+
+    let mut res = cli.post("/v1/table/0/course/2/fire").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let fired = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(1, fired.len());
+    assert_eq!(items[1].uuid, fired[0].uuid);
+    assert!(!fired[0].is_held);
+
+    // # of courses differs from # of items
+    let res = cli.post("/v1/table/0/items").body(r#"{"item_names":["ramen"],"courses":[1,2]}"#).dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+
+    let res = cli.post("/v1/table/0/course/0/fire").dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+  }
+
+  #[test]
+  fn test_sync_ready() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    let res = cli.put("/v1/table/0/sync").body(r#"{"enabled":true}"#).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    // ramen and edamame should be ready at the same time
+    let mut res = cli.post("/v1/table/0/items").body(add_req(vec!["ramen", "edamame"])).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let items = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(items[0].ready_at, items[1].ready_at);
+    assert!(items[0].start_at < items[1].start_at);
+
+    let res = cli.put("/v1/table/100/sync").body(r#"{"enabled":true}"#).dispatch();
+    assert_eq!(Status::NotAcceptable, res.status());
+  }
+
+  #[test]
+  fn test_decrement_item() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    let mut res = cli.post("/v1/table/0/items").body(r#"{"item_names":["gyoza"],"quantities":[3]}"#).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let item = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap().remove(0);
+    assert_eq!((3, 1350), (item.quantity, item.price));
+
+    let mut res = cli.post(format!("/v1/table/0/item/{}/decrement", item.uuid)).body(r#"{"by":1}"#).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let item = serde_json::from_str::<Item>(&res.body_string().unwrap()).unwrap();
+    assert_eq!((2, 900), (item.quantity, item.price));
+
+    let res = cli.post(format!("/v1/table/0/item/{}/decrement", item.uuid)).body(r#"{"by":2}"#).dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+    let res = cli.post("/v1/table/0/item/foo/decrement").body(r#"{"by":1}"#).dispatch();
+    assert_eq!(Status::NotFound, res.status());
+
+    // quantity above max_quantity
+    let res = cli.post("/v1/table/0/items").body(r#"{"item_names":["gyoza"],"quantities":[4000000000]}"#).dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+  }
+
+  #[test]
+  fn test_combos() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    let req = r#"{"item_names":["lunch set"],"substitutions":[{"drink":"beer"}]}"#;
+    let mut res = cli.post("/v1/table/0/items").body(req).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let items = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(vec!["lunch set", "ramen", "gyoza", "beer"], items.iter().map(|x| x.name.as_str()).collect::<Vec<&str>>());
+    assert_eq!(1500, items[0].price);
+
+    let mut res = cli.get("/v1/table/0/receipt").dispatch();
+    assert!(res.body_string().unwrap().contains("  - ramen\n  - gyoza\n  - beer\n"));
+
+    // beer cannot be the main
+    let req = r#"{"item_names":["lunch set"],"substitutions":[{"main":"beer"}]}"#;
+    let res = cli.post("/v1/table/0/items").body(req).dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+
+    // components are cancelled w/ the combo
+    let res = cli.delete(format!("/v1/table/0/item/{}", items[1].uuid)).dispatch();
+    assert_eq!(Status::Conflict, res.status());
+    let res = cli.delete(format!("/v1/table/0/item/{}", items[0].uuid)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let mut res = cli.get("/v1/table/0/items").dispatch();
+    assert_eq!(0, serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap().len());
+  }
+
+  #[test]
+  fn test_modifiers() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    // temperature of udon is required
+    let res = cli.post("/v1/table/0/items").body(add_req(vec!["udon"])).dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+
+    let req = r#"{"item_names":["udon"],"modifiers":[{"choices":{"temperature":"cold"},"add_ons":["tempura"],"note":"no scallions"}]}"#;
+    let mut res = cli.post("/v1/table/0/items").body(req).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let items = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(950, items[0].price);
+    assert_eq!(Some("no scallions".to_string()), items[0].note);
+
+    let mut res = cli.get("/v1/table/0/ticket").dispatch();
+    assert!(res.body_string().unwrap().contains("  + cold\n  + tempura\n  \"no scallions\"\n"));
+    let mut res = cli.get("/v1/table/0/bill").dispatch();
+    let bill = serde_json::from_str::<Bill>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(vec!["cold".to_string(), "tempura".to_string()], bill.lines[0].modifiers);
+  }
+
+  #[test]
+  fn test_reservations() {
+    let clock = get_clock();
+    let rocket = build_rocket(clock.clone());
+    let cli = Client::new(rocket).unwrap();
+
+    let req = r#"{"table_id":3,"name":"Sato","party_size":2,"at":3600,"pre_order":{"item_names":["ramen"]}}"#;
+    let mut res = cli.post("/v1/reservations").body(req).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let r = serde_json::from_str::<Reservation>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(3600, r.serve_at);
+
+    let req = r#"{"table_id":3,"name":"Sato","party_size":20,"at":3600}"#;
+    let res = cli.put(format!("/v1/reservations/{}", r.id)).body(req).dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+    let res = cli.get("/v1/reservations/foo").dispatch();
+    assert_eq!(Status::NotFound, res.status());
+
+    // ramen is held until it is time to start cooking it
+    let mut res = cli.post(format!("/v1/reservations/{}/seat", r.id)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let items = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap();
+    assert!(items[0].is_held);
+    assert_eq!(Some(3600), items[0].serve_at);
+
+    let res = cli.delete(format!("/v1/reservations/{}", r.id)).dispatch();
+    assert_eq!(Status::Conflict, res.status());
+    let mut res = cli.get("/v1/reservations").dispatch();
+    assert_eq!(1, serde_json::from_str::<Vec<Reservation>>(&res.body_string().unwrap()).unwrap().len());
+  }
+
+  #[test]
+  fn test_waitlist() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    let mut res = cli.post("/v1/waitlist").body(r#"{"name":"Sato","party_size":2}"#).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let entry = serde_json::from_str::<WaitlistEntry>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(0, entry.quoted_wait);
+    let res = cli.post("/v1/waitlist").body(r#"{"name":"Ito","party_size":20}"#).dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+
+    let mut res = cli.post(format!("/v1/waitlist/{}/seat?table_id=3", entry.id)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let entry = serde_json::from_str::<WaitlistEntry>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(Some(3), entry.table_id);
+    let res = cli.delete(format!("/v1/waitlist/{}", entry.id)).dispatch();
+    assert_eq!(Status::Conflict, res.status());
+    let res = cli.delete("/v1/waitlist/foo").dispatch();
+    assert_eq!(Status::NotFound, res.status());
+
+    let mut res = cli.get("/v1/tables").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let tables = serde_json::from_str::<Vec<TableStatus>>(&res.body_string().unwrap()).unwrap();
+    assert!(tables[0].is_free);
+    assert!(!tables[3].is_free);
+    assert_eq!(Some(2), tables[3].party.as_ref().map(|x| x.party_size));
+  }
+
+  #[test]
+  fn test_get_items() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    for table_id in 0..3 {
+      let res = cli.post(format!("/v1/table/{}/items", table_id)).body(add_req(vec!["ramen", "beer"])).dispatch();
+      assert_eq!(Status::Ok, res.status());
+    }
+    let mut res = cli.get("/v1/items?name=ramen&limit=2").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let page = serde_json::from_str::<ItemPage>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(2, page.items.len());
+    assert!(page.items[0].ready_at <= page.items[1].ready_at);
+
+    let mut res = cli.get(format!("/v1/items?name=ramen&limit=2&cursor={}", page.next_cursor.unwrap())).dispatch();
+    let page = serde_json::from_str::<ItemPage>(&res.body_string().unwrap()).unwrap();
+    assert_eq!((1, None), (page.items.len(), page.next_cursor));
+
+    let mut res = cli.get("/v1/items?table_id=1&state=cooking").dispatch();
+    let page = serde_json::from_str::<ItemPage>(&res.body_string().unwrap()).unwrap();
+    assert!(page.items.iter().all(|x| x.table_id == 1));
+
+    let res = cli.get("/v1/items?state=foo").dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+    let res = cli.get("/v1/items?limit=0").dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+  }
+
+  #[test]
+  fn test_get_table_items() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    let res = cli.post("/v1/table/0/items").body(add_req(vec!["ramen", "beer", "soba", "edamame"])).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    // same order on every request
+    let mut res = cli.get("/v1/table/0/items?sort=name&offset=1&limit=2").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let items = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(vec!["edamame", "ramen"], items.iter().map(|x| x.name.as_str()).collect::<Vec<&str>>());
+
+    let mut res = cli.get("/v1/table/0/items?name_prefix=so&state=cooking").dispatch();
+    let items = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(vec!["soba"], items.iter().map(|x| x.name.as_str()).collect::<Vec<&str>>());
+
+    let res = cli.get("/v1/table/0/items?sort=foo").dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+  }
+
+  #[test]
+  fn test_times() {
+    let clock = get_clock();
+    let rocket = build_rocket(clock.clone());
+    let cli = Client::new(rocket).unwrap();
+
+    let mut res = cli.post("/v1/table/0/items").body(add_req(vec!["ramen"])).dispatch();
+    let item = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap().remove(0);
+
+    clock.now.store(2, Ordering::Relaxed);
+    let mut res = cli.get(format!("/v1/table/0/item/{}", item.uuid)).dispatch();
+    assert_eq!(Some("2"), res.headers().get_one(SERVER_TIME_HEADER));
+    let item = serde_json::from_str::<Item>(&res.body_string().unwrap()).unwrap();
+    assert_eq!((item.ready_at - 2, 2, false), (item.remaining_secs, item.elapsed_secs, item.is_overdue));
+
+    // listing of the table has the times as well
+    let mut res = cli.get("/v1/table/0/items").dispatch();
+    let items = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(vec![(item.ready_at - 2, 2, false)],
+      items.iter().map(|x| (x.remaining_secs, x.elapsed_secs, x.is_overdue)).collect::<Vec<(i64, i64, bool)>>());
+
+    // headers are added to errors as well
+    let res = cli.get("/v1/table/0/item/foo").dispatch();
+    assert_eq!(Some("2"), res.headers().get_one(SERVER_TIME_HEADER));
+  }
+
+  #[test]
+  fn test_takeouts() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    let req = r#"{"kind":"pickup","name":"Sato","phone":"090-0000-0000","due_at":3600,"items":{"item_names":["ramen"]}}"#;
+    let mut res = cli.post("/v1/takeouts").body(req).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let t = serde_json::from_str::<Takeout>(&res.body_string().unwrap()).unwrap();
+    let req = r#"{"kind":"delivery","name":"Ito","phone":"090-0000-0001","due_at":3600,"items":{"item_names":["ramen"]}}"#;
+    let res = cli.post("/v1/takeouts").body(req).dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+
+    let mut res = cli.get(format!("/v1/takeouts/{}/items", t.id)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let items = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(Some(t.id.clone()), items[0].takeout_id);
+    assert!(items[0].is_held);
+
+    // ramen is not ready until due_at
+    let res = cli.post(format!("/v1/takeouts/{}/complete", t.id)).dispatch();
+    assert_eq!(Status::Conflict, res.status());
+    let res = cli.post(format!("/v1/kds/takeout/{}/item/{}/start", t.id, items[0].uuid)).dispatch();
+    assert_eq!(Status::Conflict, res.status());
+    let res = cli.post(format!("/v1/kds/takeout/foo/item/{}/start", items[0].uuid)).dispatch();
+    assert_eq!(Status::NotFound, res.status());
+
+    let mut res = cli.get("/v1/takeouts").dispatch();
+    assert_eq!(1, serde_json::from_str::<Vec<Takeout>>(&res.body_string().unwrap()).unwrap().len());
+    let res = cli.delete(format!("/v1/takeouts/{}", t.id)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let mut res = cli.get(format!("/v1/takeouts/{}", t.id)).dispatch();
+    let t = serde_json::from_str::<Takeout>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(TakeoutStatus::Cancelled, t.status);
+  }
+
+  #[test]
+  fn test_allergies() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    let res = cli.put("/v1/table/0/allergies").body(r#"{"allergies":["gluten"]}"#).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let res = cli.put("/v1/table/0/allergies").body(r#"{"seat":100,"diets":["vegan"]}"#).dispatch();
+    assert_eq!(Status::NotAcceptable, res.status());
+
+    let mut res = cli.get("/v1/table/0/allergies").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let xs = serde_json::from_str::<Vec<Restriction>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(1, xs.len());
+
+    // ramen contains gluten and edamame does not
+    let mut res = cli.post("/v1/table/0/items").body(add_req(vec!["ramen", "edamame"])).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let items = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(vec!["gluten".to_string()], items[0].allergy_flags);
+    assert!(items[1].allergy_flags.is_empty());
+
+    let mut res = cli.get("/v1/table/0/ticket").dispatch();
+    assert!(res.body_string().unwrap().contains("!! ALLERGY: gluten"));
+  }
+
+  #[test]
+  fn test_expo() {
+    let clock = get_clock();
+    let rocket = build_rocket(clock.clone());
+    let cli = Client::new(rocket).unwrap();
+
+    let mut res = cli.post("/v1/table/0/items").body(add_req(vec!["beer"])).dispatch();
+    let item = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap().remove(0);
+    let res = cli.post("/v1/table/1/items").body(add_req(vec!["ramen"])).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    // beer is not ready yet
+    let res = cli.post(format!("/v1/table/0/item/{}/serve", item.uuid)).dispatch();
+    assert_eq!(Status::Conflict, res.status());
+
+    clock.now.store(item.ready_at, Ordering::Relaxed);
+    let res = cli.post(format!("/v1/table/0/item/{}/serve", item.uuid)).dispatch();
+    assert_eq!(Status::Ok, res.status());
+
+    let mut res = cli.get("/v1/expo?sort=ready_at").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let tables = serde_json::from_str::<Vec<ExpoTable>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(vec![0, 1], tables.iter().map(|x| x.table_id).collect::<Vec<usize>>());
+    assert!(tables[0].is_complete);
+    assert_eq!(1, tables[0].num_served);
+
+    let res = cli.get("/v1/expo?sort=name").dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+  }
+
+  #[test]
+  fn test_late_items() {
+    let clock = get_clock();
+    let rocket = build_rocket(clock.clone());
+    let cli = Client::new(rocket).unwrap();
+
+    let mut res = cli.post("/v1/table/0/items").body(add_req(vec!["ramen"])).dispatch();
+    let item = serde_json::from_str::<Vec<Item>>(&res.body_string().unwrap()).unwrap().remove(0);
+
+    // ramen is routed to grill that has no SLA of its own
+    clock.now.store(item.ready_at + 11, Ordering::Relaxed);
+    let mut res = cli.get("/v1/expo/late").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let items = serde_json::from_str::<Vec<LateItem>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(vec![item.uuid.clone()], items.iter().map(|x| x.item.uuid.clone()).collect::<Vec<String>>());
+    assert_eq!((10, 1), (items[0].sla_secs, items[0].late_secs));
+
+    let mut res = cli.get("/v1/expo/overdue?station=grill").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let events = serde_json::from_str::<Vec<OverdueEvent>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(item.ready_at + 10, events[0].at);
+    let res = cli.get("/v1/expo/overdue?station=oven").dispatch();
+    assert_eq!(Status::NotFound, res.status());
+  }
+
+  #[test]
+  fn test_quote_items() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    let mut res = cli.post("/v1/table/0/items/quote").body(add_req(vec!["ramen", "beer"])).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let quotes = serde_json::from_str::<Vec<Quote>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(2, quotes.len());
+    assert_eq!("ramen", quotes[0].item_name);
+
+    // nothing should be added
+    let mut res = cli.get("/v1/table/0/items").dispatch();
+    assert_eq!("[]", res.body_string().unwrap());
+
+    let res = cli.post("/v1/table/100/items/quote").body(add_req(vec!["ramen"])).dispatch();
+    assert_eq!(Status::NotAcceptable, res.status());
+  }
+
+  #[test]
+  fn test_inventory() {
+    let rocket = build_rocket(get_clock());
+    let cli = Client::new(rocket).unwrap();
+
+    let mut res = cli.put("/v1/inventory/noodle").body(r#"{"quantity":3,"low_stock":5}"#).dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let stock = serde_json::from_str::<Stock>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(3, stock.quantity);
+
+    let mut res = cli.get("/v1/inventory/alerts").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let stocks = serde_json::from_str::<Vec<Stock>>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(vec![stock], stocks);
+
+    let res = cli.put("/v1/inventory/noodle").body(r#"{"quantity":-1}"#).dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+
+    // 86-ed dish cannot be ordered
+    let res = cli.put("/v1/86/green%20tea").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let res = cli.post("/v1/table/0/items").body(add_req(vec!["ramen", "green tea"])).dispatch();
+    assert_eq!(Status::Gone, res.status());
+
+    let mut res = cli.get("/v1/86").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let board = serde_json::from_str::<EightySixBoard>(&res.body_string().unwrap()).unwrap();
+    assert_eq!(vec!["green tea".to_string()], board.manual);
+
+    let res = cli.delete("/v1/86/green%20tea").dispatch();
+    assert_eq!(Status::Ok, res.status());
+    let res = cli.delete("/v1/86/green%20tea").dispatch();
+    assert_eq!(Status::NotFound, res.status());
+    let res = cli.put("/v1/86/foo").dispatch();
+    assert_eq!(Status::BadRequest, res.status());
+    let res = cli.post("/v1/table/0/items").body(add_req(vec!["ramen", "green tea"])).dispatch();
+    assert_eq!(Status::Ok, res.status());
+  }
+}
@@ -0,0 +1,228 @@
+use crate::menu::Menu;
+use serde::{Serialize, Deserialize};
+use std::{
+  fs,
+  path::Path,
+  collections::{BTreeSet, HashMap},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Stock {
+  pub ingredient: String,
+  pub quantity: i64,
+  #[serde(default)]
+  pub low_stock: i64, // stock is low when quantity is less than or equal to this
+}
+
+impl Stock {
+  pub fn is_low(&self) -> bool {
+    self.quantity <= self.low_stock
+  }
+}
+
+// dishes that cannot be ordered
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct EightySixBoard {
+  pub manual: Vec<String>,
+  pub out_of_stock: Vec<String>,
+}
+
+// ingredients not in stock are not tracked and never run out
+pub struct Inventory {
+  stocks: HashMap<String, Stock>,
+  eighty_sixed: BTreeSet<String>,
+}
+
+impl Inventory {
+  pub fn new(stocks: Vec<Stock>) -> Inventory {
+    Inventory {
+      stocks: stocks.into_iter().map(|x| (x.ingredient.clone(), x)).collect(),
+      eighty_sixed: BTreeSet::new(),
+    }
+  }
+
+  // reads inventory from a JSON file that contains an array of stocks
+  pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Inventory, String> {
+    let json = fs::read_to_string(&path)
+      .map_err(|e| format!("Failed to read inventory file {:?}: {}", path.as_ref(), e))?;
+    let stocks = serde_json::from_str::<Vec<Stock>>(&json)
+      .map_err(|e| format!("Failed to parse inventory file {:?}: {}", path.as_ref(), e))?;
+    Ok(Inventory::new(stocks))
+  }
+
+  pub fn set(&mut self, stock: Stock) {
+    self.stocks.insert(stock.ingredient.clone(), stock);
+  }
+
+  // stocks in the order of ingredient name
+  pub fn get_all(&self) -> Vec<Stock> {
+    let mut stocks: Vec<Stock> = self.stocks.values().cloned().collect();
+    stocks.sort_by(|a, b| a.ingredient.cmp(&b.ingredient));
+    stocks
+  }
+
+  pub fn get_low(&self) -> Vec<Stock> {
+    self.get_all().into_iter().filter(|x| x.is_low()).collect()
+  }
+
+  // dishes are pairs of dish name and # of dishes. returns the name of the first dish that cannot be cooked
+  // w/ the current stock together w/ the dishes before it
+  pub fn check(&self, menu: &Menu, dishes: &[(String, usize)]) -> Result<(), String> {
+    let mut required: HashMap<&str, i64> = HashMap::new();
+    for (name, num_dishes) in dishes {
+      if self.eighty_sixed.contains(name) {
+        return Err(name.to_string())
+      }
+      for (ingredient, quantity) in menu.ingredients_of(name) {
+        let x = required.entry(ingredient).or_insert(0);
+        *x = x.saturating_add(quantity.saturating_mul(*num_dishes as i64));
+        if let Some(stock) = self.stocks.get(ingredient) {
+          if *x > stock.quantity {
+            return Err(name.to_string())
+          }
+        }
+      }
+    }
+    Ok(())
+  }
+
+  // takes ingredients of all dishes or nothing if any of them is out of stock.
+  // returns the stocks that have become low as a result
+  pub fn take(&mut self, menu: &Menu, dishes: &[(String, usize)]) -> Result<Vec<Stock>, String> {
+    self.check(menu, dishes)?;
+
+    let mut became_low = vec![];
+    for (name, num_dishes) in dishes {
+      for (ingredient, quantity) in menu.ingredients_of(name) {
+        if let Some(stock) = self.stocks.get_mut(ingredient) {
+          let was_low = stock.is_low();
+          stock.quantity -= quantity * *num_dishes as i64;
+          if !was_low && stock.is_low() {
+            became_low.push(stock.clone());
+          }
+        }
+      }
+    }
+    Ok(became_low)
+  }
+
+  // puts back ingredients of the cancelled quantity of a dish
+  pub fn restore(&mut self, menu: &Menu, dish_name: &str, num_dishes: usize) {
+    for (ingredient, quantity) in menu.ingredients_of(dish_name) {
+      if let Some(stock) = self.stocks.get_mut(ingredient) {
+        stock.quantity += quantity * num_dishes as i64;
+      }
+    }
+  }
+
+  // manually marks a dish as unavailable
+  pub fn eighty_six(&mut self, dish_name: &str) {
+    self.eighty_sixed.insert(dish_name.to_string());
+  }
+
+  // returns false if the dish was not 86-ed
+  pub fn un_eighty_six(&mut self, dish_name: &str) -> bool {
+    self.eighty_sixed.remove(dish_name)
+  }
+
+  // dishes on the menu that cannot be cooked even once are out of stock
+  pub fn board(&self, menu: &Menu) -> EightySixBoard {
+    EightySixBoard {
+      manual: self.eighty_sixed.iter().cloned().collect(),
+      out_of_stock: menu.get_all().into_iter()
+        .filter(|x| x.ingredients.iter().any(|(ingredient, quantity)| {
+          self.stocks.get(ingredient).map(|x| x.quantity < *quantity).unwrap_or(false)
+        }))
+        .map(|x| x.name)
+        .collect(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::menu::Dish;
+
+  fn menu_of() -> Menu {
+    let dish_of = |name: &str, ingredients: Vec<(&str, i64)>| Dish {
+      name: name.to_string(),
+      ingredients: ingredients.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+      ..Default::default()
+    };
+    Menu::new(vec![
+      dish_of("ramen", vec![("noodle", 1), ("pork", 2)]),
+      dish_of("chashu don", vec![("rice", 1), ("pork", 3)]),
+      dish_of("tea", vec![]),
+    ])
+  }
+
+  fn stock_of(ingredient: &str, quantity: i64, low_stock: i64) -> Stock {
+    Stock {
+      ingredient: ingredient.to_string(),
+      quantity,
+      low_stock,
+    }
+  }
+
+  fn names_of(names: Vec<&str>) -> Vec<String> {
+    names.into_iter().map(|x| x.to_string()).collect()
+  }
+
+  fn dishes_of(dishes: Vec<(&str, usize)>) -> Vec<(String, usize)> {
+    dishes.into_iter().map(|(name, num_dishes)| (name.to_string(), num_dishes)).collect()
+  }
+
+  #[test]
+  fn test_take_and_restore() {
+    let menu = menu_of();
+    let mut inv = Inventory::new(vec![stock_of("noodle", 10, 0), stock_of("pork", 6, 2)]);
+
+    // rice is not tracked
+    match inv.take(&menu, &dishes_of(vec![("ramen", 1), ("chashu don", 1)])) {
+      Ok(xs) => assert_eq!(vec![stock_of("pork", 1, 2)], xs),
+      Err(_) => assert!(false),
+    }
+
+    // nothing is taken if any dish is out of stock
+    assert_eq!(Err("ramen".to_string()), inv.take(&menu, &dishes_of(vec![("tea", 1), ("ramen", 1)])));
+    assert_eq!(vec![stock_of("noodle", 9, 0), stock_of("pork", 1, 2)], inv.get_all());
+    assert_eq!(vec![stock_of("pork", 1, 2)], inv.get_low());
+
+    inv.restore(&menu, "ramen", 1);
+    assert_eq!(Ok(()), inv.check(&menu, &dishes_of(vec![("ramen", 1)])));
+    assert_eq!(Err("ramen".to_string()), inv.check(&menu, &dishes_of(vec![("ramen", 1), ("ramen", 1)])));
+    assert_eq!(Err("ramen".to_string()), inv.check(&menu, &dishes_of(vec![("ramen", 2)])));
+    assert_eq!(Err("ramen".to_string()), inv.check(&menu, &dishes_of(vec![("ramen", 4000000000)])));
+    inv.restore(&menu, "chashu don", 2);
+    assert_eq!(vec![stock_of("noodle", 10, 0), stock_of("pork", 9, 2)], inv.get_all());
+  }
+
+  #[test]
+  fn test_board() {
+    let menu = menu_of();
+    let mut inv = Inventory::new(vec![stock_of("pork", 2, 0)]);
+    inv.eighty_six("tea");
+    assert_eq!(Err("tea".to_string()), inv.check(&menu, &dishes_of(vec![("tea", 1)])));
+
+    let board = inv.board(&menu);
+    assert_eq!(names_of(vec!["tea"]), board.manual);
+    assert_eq!(names_of(vec!["chashu don"]), board.out_of_stock);
+
+    assert!(inv.un_eighty_six("tea"));
+    assert!(!inv.un_eighty_six("tea"));
+    assert_eq!(Ok(()), inv.check(&menu, &dishes_of(vec![("tea", 1)])));
+  }
+
+  #[test]
+  fn test_from_file() {
+    let path = std::env::temp_dir().join(format!("inventory-{}.json", uuid::Uuid::new_v4()));
+    fs::write(&path, r#"[{"ingredient": "pork", "quantity": 10, "low_stock": 3}, {"ingredient": "rice", "quantity": 5}]"#).unwrap();
+    let inv = Inventory::from_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(vec![stock_of("pork", 10, 3), stock_of("rice", 5, 0)], inv.get_all());
+
+    // missing file should be an error
+    assert!(Inventory::from_file(&path).is_err());
+  }
+}
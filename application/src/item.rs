@@ -1,14 +1,106 @@
-use std::cmp::Ordering;
+use crate::{
+  menu::Station,
+  modifier::Modifier,
+};
+use std::{
+  cmp::Ordering,
+  str::FromStr,
+};
 use serde::{Serialize, Deserialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Default)]
 pub struct Item {
   pub uuid: String,
   pub name: String,
-  pub table_id: usize,
+  #[serde(default)]
+  pub table_id: usize, // slot of the takeout order for its items. omitted for them in ItemPage
+  #[serde(default)]
+  pub takeout_id: Option<String>, // takeout order the item belongs to instead of a table
+  #[serde(default)]
+  pub seat: Option<usize>,
+  #[serde(default)]
+  pub price: i64, // price of the whole line i.e. unit price times quantity
+  #[serde(default = "default_quantity")]
+  pub quantity: usize,
+  #[serde(default)]
+  pub station: Option<Station>,
   pub created_at: i64,
+  #[serde(default)]
+  pub start_at: i64,
   pub ready_at: i64,
+  #[serde(default)]
+  pub cook_secs: i64,
+  #[serde(default)]
+  pub started_at: Option<i64>, // when the chef actually started cooking
+  #[serde(default)]
+  pub bumped_at: Option<i64>,  // when the chef marked the item as ready
+  #[serde(default)]
+  pub served_at: Option<i64>,  // when the item was delivered to the table
+  #[serde(default)]
+  pub course: Option<usize>,
+  #[serde(default)]
+  pub is_held: bool, // held items wait for their course to be fired w/o being scheduled
+  #[serde(default)]
+  pub serve_at: Option<i64>, // items to be ready at this time are held until it's time to start cooking
+  #[serde(default)]
+  pub allergy_flags: Vec<String>, // allergies and diets of the guests that the item conflicts with
+  #[serde(default)]
+  pub modifiers: Vec<Modifier>, // price includes the price deltas of the modifiers
+  #[serde(default)]
+  pub note: Option<String>,
+  #[serde(default)]
+  pub parent_id: Option<String>, // uuid of the combo the item is a component of
+  #[serde(default)]
+  pub is_combo: bool, // combos are billed, but not cooked. their components are cooked, but not billed
   pub is_removed: bool,
+  #[serde(default)]
+  pub removed_at: Option<i64>, // removed items can be restored for a grace period after this time
+  #[serde(default)]
+  pub is_voided: bool,
+  // below are computed by the server when the item is returned
+  #[serde(default)]
+  pub remaining_secs: i64, // until ready_at
+  #[serde(default)]
+  pub remaining_minutes: i64, // remaining_secs rounded up to minutes of one_min_in_sec
+  #[serde(default)]
+  pub elapsed_secs: i64, // since created_at
+  #[serde(default)]
+  pub is_overdue: bool, // not served by ready_at
+}
+
+fn default_quantity() -> usize {
+  1
+}
+
+impl Item {
+  // fills in the times relative to now. held items and combos are not overdue since they have no schedule
+  pub fn with_times(mut self, now: i64, one_min_in_sec: i64) -> Item {
+    self.remaining_secs = (self.ready_at - now).max(0);
+    self.remaining_minutes = (self.remaining_secs + one_min_in_sec - 1) / one_min_in_sec;
+    self.elapsed_secs = (now - self.created_at).max(0);
+    self.is_overdue = !self.is_held && !self.is_combo && !self.is_removed && !self.is_voided
+      && self.served_at.unwrap_or(now) > self.ready_at;
+    self
+  }
+}
+
+// how items are counted against max_table_items
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CountRule {
+  Lines, // an item counts as 1 regardless of its quantity
+  Units, // an item counts as its quantity
+}
+
+impl FromStr for CountRule {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<CountRule, String> {
+    match s {
+      "lines" => Ok(CountRule::Lines),
+      "units" => Ok(CountRule::Units),
+      _ => Err(format!("Unknown count rule {}", s)),
+    }
+  }
 }
 
 impl Ord for Item {
@@ -0,0 +1,507 @@
+use crate::menu::{Station, STATIONS};
+use serde::{Serialize, Deserialize};
+use std::{
+  sync::Mutex,
+  collections::{
+    VecDeque,
+    hash_map::HashMap,
+  },
+};
+
+pub const DEFAULT_STATION_CAPACITY: usize = 4;
+const MAX_KDS_EVENTS: usize = 10000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum KdsAction {
+  Start,
+  Bump,
+  Recall,
+}
+
+// chef action on kitchen display system
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct KdsEvent {
+  pub uuid: String,
+  pub table_id: usize,
+  #[serde(default)]
+  pub takeout_id: Option<String>,
+  pub station: Option<Station>,
+  pub action: KdsAction,
+  pub at: i64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Job {
+  uuid: String,
+  table_id: usize,
+  duration: i64,
+  not_before: i64, // job doesn't start before this time even if a slot is available
+  start_at: i64,
+  ready_at: i64,
+  is_pinned: bool, // restored items keep their original schedule as if they had started
+}
+
+// schedules the items routed to a station across all tables.
+// items are cooked in the order they are added on the earliest available one of `capacity` slots
+// (burners, fryers, ...). only references to items are kept and items themselves are owned by TableOrders
+#[derive(Clone)]
+pub struct StationQueue {
+  capacity: usize,
+  jobs: Vec<Job>, // in the order of being added
+}
+
+impl StationQueue {
+  // a station needs at least 1 slot to cook anything
+  pub fn new(capacity: usize) -> StationQueue {
+    assert!(capacity >= 1, "Station capacity must be at least 1");
+    StationQueue {
+      capacity,
+      jobs: vec![],
+    }
+  }
+
+  // schedules an item that takes duration to cook and returns (table_id, uuid) of the items whose
+  // schedule has changed as a result including the scheduled item
+  pub fn schedule(&mut self, table_id: usize, item_uuid: &str, duration: i64, now: i64) -> Vec<(usize, String)> {
+    self.jobs.push(Job {
+      uuid: item_uuid.to_string(),
+      table_id,
+      duration,
+      not_before: i64::MIN,
+      start_at: i64::MAX, // not started yet
+      ready_at: i64::MAX,
+      is_pinned: false,
+    });
+    self.reschedule(now)
+  }
+
+  // starts cooking an item that is waiting for a slot and returns (table_id, uuid) of the items
+  // whose schedule has changed as a result including the started item
+  pub fn start(&mut self, item_uuid: &str, now: i64) -> Vec<(usize, String)> {
+    let job = match self.jobs.iter_mut().find(|x| x.uuid == item_uuid) {
+      Some(x) if x.start_at > now => x,
+      _ => return vec![],
+    };
+    job.start_at = now;
+    job.ready_at = now + job.duration;
+    job.is_pinned = false;
+    let started = (job.table_id, job.uuid.clone());
+
+    let mut changed = self.reschedule(now);
+    changed.insert(0, started);
+    changed
+  }
+
+  // delays the start of an item that has not started before now and returns (table_id, uuid) of
+  // the items whose schedule has changed as a result including the delayed item
+  pub fn delay(&mut self, item_uuid: &str, not_before: i64, now: i64) -> Vec<(usize, String)> {
+    let job = match self.jobs.iter_mut().find(|x| x.uuid == item_uuid) {
+      Some(x) if x.start_at >= now => x,
+      _ => return vec![],
+    };
+    job.not_before = not_before;
+    job.start_at = i64::MAX; // make it pending again
+    job.is_pinned = false;
+    self.reschedule(now)
+  }
+
+  // removes an item and returns (table_id, uuid) of the items whose schedule has changed as a result
+  pub fn remove(&mut self, item_uuid: &str, now: i64) -> Vec<(usize, String)> {
+    let len = self.jobs.len();
+    self.jobs.retain(|x| x.uuid != item_uuid);
+    if self.jobs.len() == len {
+      return vec![]
+    }
+    self.reschedule(now)
+  }
+
+  // puts back a removed item w/ its original schedule if a slot is still free for it. otherwise the item
+  // waits for a slot again. returns (table_id, uuid) of the items whose schedule has changed as a result
+  // including the restored item if it has been rescheduled
+  pub fn restore(&mut self, table_id: usize, item_uuid: &str, start_at: i64, ready_at: i64, now: i64) -> Vec<(usize, String)> {
+    let from = start_at.max(now);
+    let num_busy = self.jobs.iter()
+      .filter(|x| (x.start_at <= now || x.is_pinned) && x.start_at < ready_at && from < x.ready_at)
+      .count();
+    let is_pinned = num_busy < self.capacity;
+    self.jobs.push(Job {
+      uuid: item_uuid.to_string(),
+      table_id,
+      duration: ready_at - start_at,
+      not_before: if is_pinned { start_at } else { i64::MIN },
+      start_at: if is_pinned { start_at } else { i64::MAX },
+      ready_at: if is_pinned { ready_at } else { i64::MAX },
+      is_pinned,
+    });
+    self.reschedule(now)
+  }
+
+  // whether an item can start now w/o exceeding the capacity. items that have started can go on
+  pub fn can_start(&self, item_uuid: &str, now: i64) -> bool {
+    match self.jobs.iter().find(|x| x.uuid == item_uuid) {
+      Some(x) if x.start_at > now => (),
+      _ => return true,
+    }
+    let num_busy = self.jobs.iter().filter(|x| x.start_at <= now && now < x.ready_at).count();
+    num_busy < self.capacity
+  }
+
+  // returns (start_at, ready_at) of an item
+  pub fn get(&self, item_uuid: &str) -> Option<(i64, i64)> {
+    self.jobs.iter().find(|x| x.uuid == item_uuid).map(|x| (x.start_at, x.ready_at))
+  }
+
+  // returns (table_id, uuid) of the queued items in the order of ready_at
+  pub fn get_all(&self) -> Vec<(usize, String)> {
+    let mut jobs: Vec<&Job> = self.jobs.iter().collect();
+    jobs.sort_by(|a, b| (a.ready_at, &a.uuid).cmp(&(b.ready_at, &b.uuid)));
+    jobs.into_iter().map(|x| (x.table_id, x.uuid.clone())).collect()
+  }
+
+  pub fn remove_before_eq_threshold(&mut self, threshold: i64) {
+    self.jobs.retain(|x| x.ready_at > threshold);
+  }
+
+  // items that have already started or are pinned keep their slots and the rest are assigned to the earliest
+  // available slots in the order of being added. a delayed item holds its slot only from not_before,
+  // so the items added after it can use the slot until then. returns (table_id, uuid) of the rescheduled items
+  fn reschedule(&mut self, now: i64) -> Vec<(usize, String)> {
+    let mut started: Vec<i64> = self.jobs.iter()
+      .filter(|x| x.start_at <= now || x.is_pinned)
+      .map(|x| x.ready_at)
+      .collect();
+    started.sort();
+
+    // slots are freed in the order of ready_at of the started items. if more items have started
+    // than the capacity, a slot becomes available only after the excess items are done
+    let num_busy = started.len().min(self.capacity);
+    // busy periods of each slot in the order of start
+    let mut slots: Vec<Vec<(i64, i64)>> = started[started.len() - num_busy..].iter()
+      .map(|x| vec![(i64::MIN, *x)])
+      .chain((num_busy..self.capacity).map(|_| vec![]))
+      .collect();
+
+    let mut changed = vec![];
+    for job in self.jobs.iter_mut().filter(|x| x.start_at > now && !x.is_pinned) {
+      let (slot, start_at) = slots.iter()
+        .map(|busy| StationQueue::earliest_fit(busy, now.max(job.not_before), job.duration))
+        .enumerate()
+        .min_by_key(|(i, start_at)| (*start_at, *i))
+        .unwrap();
+      let ready_at = start_at + job.duration;
+      let busy = &mut slots[slot];
+      let pos = busy.iter().position(|x| x.0 > start_at).unwrap_or(busy.len());
+      busy.insert(pos, (start_at, ready_at));
+
+      if (start_at, ready_at) != (job.start_at, job.ready_at) {
+        job.start_at = start_at;
+        job.ready_at = ready_at;
+        changed.push((job.table_id, job.uuid.clone()));
+      }
+    }
+    changed
+  }
+
+  // earliest start at or after from that lets an item of duration fit in a gap between the busy periods
+  fn earliest_fit(busy: &[(i64, i64)], from: i64, duration: i64) -> i64 {
+    let mut start_at = from;
+    for (busy_from, busy_until) in busy {
+      if start_at + duration <= *busy_from {
+        break
+      }
+      start_at = start_at.max(*busy_until);
+    }
+    start_at
+  }
+}
+
+pub struct Kitchen {
+  stations: HashMap<Station, Mutex<StationQueue>>,
+  events: Mutex<VecDeque<KdsEvent>>, // latest MAX_KDS_EVENTS events in chronological order
+}
+
+impl Kitchen {
+  // stations not in capacities get the default capacity
+  pub fn new(capacities: &HashMap<Station, usize>) -> Kitchen {
+    Kitchen {
+      stations: STATIONS.iter()
+        .map(|x| {
+          let capacity = capacities.get(x).cloned().unwrap_or(DEFAULT_STATION_CAPACITY);
+          (*x, Mutex::new(StationQueue::new(capacity)))
+        })
+        .collect(),
+      events: Mutex::new(VecDeque::new()),
+    }
+  }
+
+  pub fn station(&self, station: Station) -> &Mutex<StationQueue> {
+    &self.stations[&station]
+  }
+
+  pub fn add_event(&self, event: KdsEvent) {
+    let mut events = self.events.lock().unwrap();
+    if events.len() == MAX_KDS_EVENTS {
+      events.pop_front();
+    }
+    events.push_back(event);
+  }
+
+  // returns events of the station or all stations if not specified
+  pub fn get_events(&self, station: Option<Station>) -> Vec<KdsEvent> {
+    self.events.lock().unwrap().iter()
+      .filter(|x| station.is_none() || x.station == station)
+      .cloned()
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_schedule() {
+    // 2 burners
+    let mut sq = StationQueue::new(2);
+
+    // first 2 items start immediately
+    assert_eq!(vec![(0, "i1".to_string())], sq.schedule(0, "i1", 10, 0));
+    sq.schedule(1, "i2", 5, 0);
+    assert_eq!(Some((0, 10)), sq.get("i1"));
+    assert_eq!(Some((0, 5)), sq.get("i2"));
+
+    // 3rd item waits for the burner of i2
+    sq.schedule(0, "i3", 7, 0);
+    assert_eq!(Some((5, 12)), sq.get("i3"));
+
+    // 4th item waits for the burner of i1
+    sq.schedule(0, "i4", 3, 1);
+    assert_eq!(Some((10, 13)), sq.get("i4"));
+
+    // after all items are done, new item starts immediately
+    sq.schedule(0, "i5", 1, 20);
+    assert_eq!(Some((20, 21)), sq.get("i5"));
+
+    assert_eq!(None, sq.get("foo"));
+  }
+
+  #[test]
+  fn test_can_start() {
+    let mut sq = StationQueue::new(1);
+    sq.schedule(0, "i1", 10, 0);
+    sq.schedule(0, "i2", 10, 0);
+
+    // i2 waits for the only slot
+    assert!(sq.can_start("i1", 5));
+    assert!(!sq.can_start("i2", 5));
+    assert!(sq.can_start("foo", 5));
+
+    // i2 can start once i1 has left the slot
+    sq.remove("i1", 5);
+    assert!(sq.can_start("i2", 5));
+  }
+
+  #[test]
+  fn test_get_all() {
+    let mut sq = StationQueue::new(2);
+    assert_eq!(0, sq.get_all().len());
+
+    sq.schedule(0, "i1", 30, 0);
+    sq.schedule(1, "i2", 10, 0);
+    sq.schedule(0, "i3", 5, 0);
+
+    // items should be in the order of ready_at regardless of table
+    assert_eq!(
+      vec![(1, "i2".to_string()), (0, "i3".to_string()), (0, "i1".to_string())],
+      sq.get_all(),
+    );
+  }
+
+  #[test]
+  fn test_remove() {
+    let mut sq = StationQueue::new(1);
+    sq.schedule(0, "i1", 10, 0);
+    sq.schedule(1, "i2", 10, 0);
+    sq.schedule(2, "i3", 10, 0);
+    assert_eq!(Some((20, 30)), sq.get("i3"));
+
+    // removing waiting item moves up the items behind it
+    assert_eq!(vec![(2, "i3".to_string())], sq.remove("i2", 1));
+    assert_eq!(Some((10, 20)), sq.get("i3"));
+
+    // removing item being cooked frees up its slot immediately
+    assert_eq!(vec![(2, "i3".to_string())], sq.remove("i1", 4));
+    assert_eq!(Some((4, 14)), sq.get("i3"));
+
+    // removing the last item changes nothing else
+    assert_eq!(Vec::<(usize, String)>::new(), sq.remove("i3", 5));
+    assert_eq!(Vec::<(usize, String)>::new(), sq.remove("i3", 5));
+    assert_eq!(0, sq.get_all().len());
+  }
+
+  #[test]
+  fn test_remove_keeps_started_items() {
+    let mut sq = StationQueue::new(2);
+    sq.schedule(0, "i1", 10, 0);
+    sq.schedule(0, "i2", 20, 0);
+    sq.schedule(0, "i3", 10, 0);
+    sq.schedule(0, "i4", 10, 0);
+    assert_eq!(Some((10, 20)), sq.get("i3"));
+    assert_eq!(Some((20, 30)), sq.get("i4"));
+
+    // at 12, i1 is done, i2 and i3 are being cooked. removing i2 moves up i4
+    sq.remove_before_eq_threshold(12);
+    assert_eq!(vec![(0, "i4".to_string())], sq.remove("i2", 12));
+    assert_eq!(Some((10, 20)), sq.get("i3"));
+    assert_eq!(Some((12, 22)), sq.get("i4"));
+  }
+
+  #[test]
+  fn test_restore() {
+    let mut sq = StationQueue::new(1);
+    sq.schedule(0, "i1", 10, 0);
+    sq.schedule(1, "i2", 10, 0);
+    sq.schedule(2, "i3", 10, 0);
+    sq.remove("i2", 1);
+    assert_eq!(Some((10, 20)), sq.get("i3"));
+
+    // restored item takes back its original slot and pushes back the items behind it
+    assert_eq!(vec![(2, "i3".to_string())], sq.restore(1, "i2", 10, 20, 2));
+    assert_eq!(Some((10, 20)), sq.get("i2"));
+    assert_eq!(Some((20, 30)), sq.get("i3"));
+
+    // pinned item is not moved up when an item before it is removed
+    assert_eq!(Vec::<(usize, String)>::new(), sq.remove("i1", 3));
+    assert_eq!(Some((10, 20)), sq.get("i2"));
+  }
+
+  #[test]
+  fn test_restore_after_slot_taken() {
+    let mut sq = StationQueue::new(1);
+    sq.schedule(0, "i1", 10, 0);
+    sq.schedule(1, "i2", 10, 0);
+    sq.remove("i1", 1);
+    assert_eq!(Some((1, 11)), sq.get("i2"));
+
+    // i2 has started on the slot freed by i1, so i1 waits for the slot instead of taking it back
+    assert_eq!(vec![(0, "i1".to_string())], sq.restore(0, "i1", 0, 10, 3));
+    assert_eq!(Some((11, 21)), sq.get("i1"));
+    assert_eq!(Some((1, 11)), sq.get("i2"));
+    assert!(!sq.can_start("i1", 3));
+  }
+
+  #[test]
+  fn test_remove_before_eq_threshold() {
+    let mut sq = StationQueue::new(3);
+    sq.schedule(0, "i1", 30, 0);
+    sq.schedule(1, "i2", 10, 0);
+    sq.schedule(2, "i3", 20, 0);
+
+    sq.remove_before_eq_threshold(9);
+    assert_eq!(3, sq.get_all().len());
+
+    sq.remove_before_eq_threshold(20);
+    assert_eq!(vec![(0, "i1".to_string())], sq.get_all());
+
+    sq.remove_before_eq_threshold(30);
+    assert_eq!(0, sq.get_all().len());
+  }
+
+  #[test]
+  fn test_start() {
+    let mut sq = StationQueue::new(1);
+    sq.schedule(0, "i1", 10, 0);
+    sq.schedule(1, "i2", 10, 0);
+    sq.schedule(2, "i3", 10, 0);
+
+    // chef starts i2 early on a spare burner. i3 now waits for both i1 and i2
+    assert_eq!(vec![(1, "i2".to_string()), (2, "i3".to_string())], sq.start("i2", 2));
+    assert_eq!(Some((2, 12)), sq.get("i2"));
+    assert_eq!(Some((12, 22)), sq.get("i3"));
+
+    // items already started cannot be started again
+    assert_eq!(Vec::<(usize, String)>::new(), sq.start("i1", 3));
+    assert_eq!(Vec::<(usize, String)>::new(), sq.start("foo", 3));
+  }
+
+  #[test]
+  fn test_delay() {
+    let mut sq = StationQueue::new(1);
+    sq.schedule(0, "i1", 5, 0);
+    sq.schedule(1, "i2", 10, 0);
+
+    // delaying i1 makes the slot idle until then and i2 waits for i1
+    assert_eq!(vec![(0, "i1".to_string()), (1, "i2".to_string())], sq.delay("i1", 3, 0));
+    assert_eq!(Some((3, 8)), sq.get("i1"));
+    assert_eq!(Some((8, 18)), sq.get("i2"));
+
+    // item that can be done before i1 starts uses the idle slot
+    assert_eq!(vec![(2, "i3".to_string())], sq.schedule(2, "i3", 3, 0));
+    assert_eq!(Some((0, 3)), sq.get("i3"));
+    sq.schedule(2, "i4", 1, 0);
+    assert_eq!(Some((18, 19)), sq.get("i4"));
+
+    // started items cannot be delayed
+    assert_eq!(Vec::<(usize, String)>::new(), sq.delay("i1", 10, 4));
+
+    // delayed item can start early on a free slot
+    let mut sq = StationQueue::new(1);
+    sq.schedule(0, "i1", 10, 0);
+    sq.schedule(0, "i2", 10, 0);
+    sq.remove("i1", 5);
+    sq.delay("i2", 20, 5);
+    assert_eq!(Some((20, 30)), sq.get("i2"));
+    assert!(sq.can_start("i2", 5));
+  }
+
+  #[test]
+  fn test_events() {
+    let kitchen = Kitchen::new(&HashMap::new());
+    let event_of = |uuid: &str, station: Option<Station>, action: KdsAction, at: i64| KdsEvent {
+      uuid: uuid.to_string(),
+      table_id: 0,
+      takeout_id: None,
+      station,
+      action,
+      at,
+    };
+    kitchen.add_event(event_of("i1", Some(Station::Grill), KdsAction::Start, 1));
+    kitchen.add_event(event_of("i2", Some(Station::Bar), KdsAction::Bump, 2));
+    kitchen.add_event(event_of("i1", Some(Station::Grill), KdsAction::Bump, 3));
+
+    assert_eq!(3, kitchen.get_events(None).len());
+    let events = kitchen.get_events(Some(Station::Grill));
+    assert_eq!(2, events.len());
+    assert_eq!(KdsAction::Start, events[0].action);
+    assert_eq!(3, events[1].at);
+  }
+
+  #[test]
+  fn test_kitchen() {
+    let capacities = vec![(Station::Fry, 1)].into_iter().collect();
+    let kitchen = Kitchen::new(&capacities);
+
+    // fry has 1 fryer and the others have the default capacity
+    let mut fry = kitchen.station(Station::Fry).lock().unwrap();
+    fry.schedule(0, "i1", 10, 0);
+    fry.schedule(0, "i2", 10, 0);
+    assert_eq!(Some((10, 20)), fry.get("i2"));
+    let mut grill = kitchen.station(Station::Grill).lock().unwrap();
+    for i in 0..DEFAULT_STATION_CAPACITY {
+      grill.schedule(0, &i.to_string(), 10, 0);
+      assert_eq!(Some((0, 10)), grill.get(&i.to_string()));
+    }
+    drop((fry, grill));
+
+    // stations have separate queues
+    assert_eq!(0, kitchen.station(Station::Bar).lock().unwrap().get_all().len());
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_zero_capacity() {
+    let capacities = vec![(Station::Fry, 0)].into_iter().collect();
+    Kitchen::new(&capacities);
+  }
+}
@@ -2,6 +2,21 @@
 #[macro_use] extern crate log;
 
 pub mod item;
+pub mod menu;
+pub mod bill;
+pub mod payment;
+pub mod printout;
+pub mod kitchen;
+pub mod expo;
+pub mod inventory;
+pub mod allergen;
+pub mod modifier;
+pub mod combo;
+pub mod reservation;
+pub mod floor;
+pub mod takeout;
+pub mod query;
+pub mod sla;
 pub mod order_mgr;
 pub mod clock;
 pub mod api;
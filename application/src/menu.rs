@@ -0,0 +1,173 @@
+use crate::{
+  modifier::{ChoiceGroup, Modifier},
+  combo::ComboSlot,
+};
+use serde::{Serialize, Deserialize};
+use std::{
+  fs,
+  path::Path,
+  str::FromStr,
+  collections::hash_map::HashMap,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Station {
+  Grill,
+  Fry,
+  Cold,
+  Bar,
+}
+
+pub const STATIONS: [Station; 4] = [Station::Grill, Station::Fry, Station::Cold, Station::Bar];
+
+impl FromStr for Station {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Station, String> {
+    match s {
+      "grill" => Ok(Station::Grill),
+      "fry" => Ok(Station::Fry),
+      "cold" => Ok(Station::Cold),
+      "bar" => Ok(Station::Bar),
+      _ => Err(format!("Unknown station {}", s)),
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Default)]
+pub struct Dish {
+  pub name: String,
+  pub price: i64, // in minor currency unit
+  #[serde(default)]
+  pub station: Option<Station>,
+  #[serde(default)]
+  pub cook_mins: Option<i64>,
+  #[serde(default)]
+  pub ingredients: HashMap<String, i64>, // quantity of each ingredient to cook the dish
+  #[serde(default)]
+  pub allergens: Vec<String>, // e.g. "gluten", "nuts"
+  #[serde(default)]
+  pub diets: Vec<String>,     // diets the dish is suitable for e.g. "vegan"
+  #[serde(default)]
+  pub choices: Vec<ChoiceGroup>,
+  #[serde(default)]
+  pub add_ons: Vec<Modifier>,
+  #[serde(default)]
+  pub components: Vec<ComboSlot>, // a dish w/ components is a combo
+  #[serde(default)]
+  pub sla_mins: Option<i64>, // minutes the dish may wait to be served after ready_at
+}
+
+pub struct Menu {
+  dishes: HashMap<String, Dish>,
+}
+
+impl Menu {
+  pub fn new(dishes: Vec<Dish>) -> Menu {
+    Menu {
+      dishes: dishes.into_iter().map(|x| (x.name.clone(), x)).collect(),
+    }
+  }
+
+  // reads menu from a JSON file that contains an array of dishes
+  pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Menu, String> {
+    let json = fs::read_to_string(&path)
+      .map_err(|e| format!("Failed to read menu file {:?}: {}", path.as_ref(), e))?;
+    let dishes = serde_json::from_str::<Vec<Dish>>(&json)
+      .map_err(|e| format!("Failed to parse menu file {:?}: {}", path.as_ref(), e))?;
+    Ok(Menu::new(dishes))
+  }
+
+  pub fn get(&self, name: &str) -> Option<&Dish> {
+    self.dishes.get(name)
+  }
+
+  // dishes not on the menu are free of charge
+  pub fn price_of(&self, name: &str) -> i64 {
+    self.get(name).map(|x| x.price).unwrap_or(0)
+  }
+
+  // dishes not on the menu are not routed to any station
+  pub fn station_of(&self, name: &str) -> Option<Station> {
+    self.get(name).and_then(|x| x.station)
+  }
+
+  pub fn cook_mins_of(&self, name: &str) -> Option<i64> {
+    self.get(name).and_then(|x| x.cook_mins)
+  }
+
+  // dishes not on the menu don't consume any ingredient
+  pub fn ingredients_of(&self, name: &str) -> Vec<(&str, i64)> {
+    self.get(name)
+      .map(|x| x.ingredients.iter().map(|(k, v)| (k.as_str(), *v)).collect())
+      .unwrap_or_default()
+  }
+
+  // dishes in the order of name
+  pub fn get_all(&self) -> Vec<Dish> {
+    let mut dishes: Vec<Dish> = self.dishes.values().cloned().collect();
+    dishes.sort_by(|a, b| a.name.cmp(&b.name));
+    dishes
+  }
+
+  pub fn len(&self) -> usize {
+    self.dishes.len()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn dish_of(name: &str, price: i64) -> Dish {
+    Dish {
+      name: name.to_string(),
+      price,
+      station: Some(Station::Grill),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_get() {
+    let menu = Menu::new(vec![dish_of("ramen", 900), dish_of("gyoza", 450)]);
+    assert_eq!(2, menu.len());
+    assert_eq!(Some(&dish_of("ramen", 900)), menu.get("ramen"));
+    assert_eq!(None, menu.get("pizza"));
+
+    assert_eq!(450, menu.price_of("gyoza"));
+    assert_eq!(0, menu.price_of("pizza"));
+
+    assert_eq!(Some(Station::Grill), menu.station_of("ramen"));
+    assert_eq!(None, menu.station_of("pizza"));
+  }
+
+  #[test]
+  fn test_station_from_str() {
+    for x in STATIONS.iter() {
+      let name = serde_json::to_string(x).unwrap();
+      assert_eq!(Ok(*x), name.trim_matches('"').parse());
+    }
+    assert!("oven".parse::<Station>().is_err());
+  }
+
+  #[test]
+  fn test_from_file() {
+    let path = std::env::temp_dir().join(format!("menu-{}.json", uuid::Uuid::new_v4()));
+    fs::write(&path, r#"[{"name": "ramen", "price": 900, "station": "grill", "cook_mins": 8, "ingredients": {"noodle": 1}}, {"name": "tea", "price": 200}]"#).unwrap();
+    let menu = Menu::from_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(900, menu.price_of("ramen"));
+    assert_eq!(Some(Station::Grill), menu.station_of("ramen"));
+    assert_eq!(None, menu.station_of("tea"));
+    assert_eq!(Some(8), menu.cook_mins_of("ramen"));
+    assert_eq!(None, menu.cook_mins_of("tea"));
+    assert_eq!(vec![("noodle", 1)], menu.ingredients_of("ramen"));
+    assert_eq!(0, menu.ingredients_of("tea").len());
+    assert_eq!(vec!["ramen", "tea"], menu.get_all().into_iter().map(|x| x.name).collect::<Vec<String>>());
+
+    // missing file should be an error
+    assert!(Menu::from_file(&path).is_err());
+  }
+}
@@ -0,0 +1,112 @@
+use crate::{
+  api::ModifiersParam,
+  menu::Dish,
+};
+use serde::{Serialize, Deserialize};
+
+// an option of a choice group or an add-on
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Default)]
+pub struct Modifier {
+  pub name: String,
+  #[serde(default)]
+  pub price: i64, // price delta in minor currency unit
+}
+
+// exactly one option needs to be chosen from each group e.g. doneness
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Default)]
+pub struct ChoiceGroup {
+  pub name: String,
+  pub options: Vec<Modifier>,
+}
+
+// returns the chosen options in the order of the groups followed by the add-ons in the order of the menu.
+// dishes not on the menu have no choice groups nor add-ons. returns the reason on error
+pub fn resolve(dish: Option<&Dish>, req: &ModifiersParam, max_note_len: usize) -> Result<Vec<Modifier>, String> {
+  let (choices, add_ons) = match dish {
+    Some(x) => (&x.choices[..], &x.add_ons[..]),
+    None => (&[][..], &[][..]),
+  };
+  if let Some(group) = req.choices.keys().find(|k| !choices.iter().any(|x| &x.name == *k)) {
+    return Err(format!("unknown choice group {}", group))
+  }
+  for (i, name) in req.add_ons.iter().enumerate() {
+    if !add_ons.iter().any(|x| &x.name == name) {
+      return Err(format!("unknown add-on {}", name))
+    }
+    if req.add_ons[..i].contains(name) {
+      return Err(format!("duplicate add-on {}", name))
+    }
+  }
+  if let Some(note) = &req.note {
+    if note.chars().count() > max_note_len {
+      return Err(format!("note longer than {} characters", max_note_len))
+    }
+  }
+
+  let mut modifiers = vec![];
+  for group in choices {
+    let option = match req.choices.get(&group.name) {
+      Some(x) => x,
+      None => return Err(format!("no option chosen for {}", group.name)),
+    };
+    match group.options.iter().find(|x| &x.name == option) {
+      Some(x) => modifiers.push(x.clone()),
+      None => return Err(format!("unknown option {} for {}", option, group.name)),
+    }
+  }
+  modifiers.extend(add_ons.iter().filter(|x| req.add_ons.contains(&x.name)).cloned());
+  Ok(modifiers)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn modifier_of(name: &str, price: i64) -> Modifier {
+    Modifier {
+      name: name.to_string(),
+      price,
+    }
+  }
+
+  fn param_of(choices: Vec<(&str, &str)>, add_ons: Vec<&str>, note: Option<&str>) -> ModifiersParam {
+    ModifiersParam {
+      choices: choices.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+      add_ons: add_ons.into_iter().map(|x| x.to_string()).collect(),
+      note: note.map(|x| x.to_string()),
+    }
+  }
+
+  #[test]
+  fn test_resolve() {
+    let dish = Dish {
+      name: "ramen".to_string(),
+      choices: vec![
+        ChoiceGroup { name: "soup".to_string(), options: vec![modifier_of("shoyu", 0), modifier_of("miso", 50)] },
+        ChoiceGroup { name: "noodle".to_string(), options: vec![modifier_of("thin", 0), modifier_of("thick", 0)] },
+      ],
+      add_ons: vec![modifier_of("egg", 100), modifier_of("nori", 50)],
+      ..Default::default()
+    };
+    let req = param_of(vec![("noodle", "thick"), ("soup", "miso")], vec!["nori", "egg"], Some("no onions"));
+    assert_eq!(
+      Ok(vec![modifier_of("miso", 50), modifier_of("thick", 0), modifier_of("egg", 100), modifier_of("nori", 50)]),
+      resolve(Some(&dish), &req, 10),
+    );
+
+    // note is too long
+    assert!(resolve(Some(&dish), &req, 8).is_err());
+    // missing group
+    assert!(resolve(Some(&dish), &param_of(vec![("soup", "miso")], vec![], None), 10).is_err());
+    // unknown group, option and add-on
+    assert!(resolve(Some(&dish), &param_of(vec![("noodle", "thin"), ("soup", "miso"), ("size", "l")], vec![], None), 10).is_err());
+    assert!(resolve(Some(&dish), &param_of(vec![("noodle", "thin"), ("soup", "salt")], vec![], None), 10).is_err());
+    assert!(resolve(Some(&dish), &param_of(vec![("noodle", "thin"), ("soup", "miso")], vec!["corn"], None), 10).is_err());
+    // duplicate add-on
+    assert!(resolve(Some(&dish), &param_of(vec![("noodle", "thin"), ("soup", "miso")], vec!["egg", "egg"], None), 10).is_err());
+
+    // dishes not on the menu only take a note
+    assert_eq!(Ok(vec![]), resolve(None, &param_of(vec![], vec![], Some("no ice")), 10));
+    assert!(resolve(None, &param_of(vec![], vec!["egg"], None), 10).is_err());
+  }
+}
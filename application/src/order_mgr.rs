@@ -1,14 +1,34 @@
 use crate::{
-  item::Item,
+  item::{Item, CountRule},
   table_orders::TableOrders,
   clock::clock::Clock,
+  api::{AddItemsParam, PayParam, RefundParam, SyncParam, StockParam, DecrementParam, ReservationParam, WaitlistParam, TakeoutParam, Quote},
+  reservation::{Reservation, ReservationBook, ReservationStatus},
+  floor::{self, Party, TableStatus, Waitlist, WaitlistEntry, WaitlistStatus},
+  takeout::{Takeout, TakeoutBook, TakeoutKind, TakeoutStatus},
+  query::{self, Cursor, ItemFilter, ItemPage, ItemSort},
+  sla::{self, LateItem, OverdueEvent, OverdueLog, SlaConfig},
+  allergen::{self, Restriction, Strictness},
+  modifier::{self, Modifier},
+  combo,
+  menu::{Menu, Station},
+  kitchen::{Kitchen, KdsAction, KdsEvent, StationQueue},
+  expo::{self, ExpoSort, ExpoTable},
+  inventory::{Inventory, Stock, EightySixBoard},
+  bill::{Bill, BillConfig, SplitMode, SubBill},
+  printout::{self, PrintFormat},
+  payment::{
+    payment::{Payment, PaymentSummary},
+    payment_processor::PaymentProcessor,
+    mock_payment_processor::MockPaymentProcessor,
+  },
 };
 use std::{
   fmt,
-  sync::{Arc, Mutex},
+  sync::{Arc, Mutex, MutexGuard},
+  collections::hash_map::HashMap,
 };
 use uuid::Uuid;
-use rand::{thread_rng, Rng};
 
 macro_rules! vec_no_clone {
   ($val:expr; $n:expr) => {{
@@ -25,11 +45,65 @@ macro_rules! validate_table_id {
   };
 }
 
+macro_rules! validate_seat {
+  ($seat: expr, $num_seats: expr) => {
+    if $seat >= $num_seats {
+      error!("Valid seat range is 0-{}, but seat {} is specifed", $num_seats, $seat);
+      return Err(Error::BadSeat($seat))
+    }
+  };
+}
+
+// station queues that items are scheduled on. quotes are scheduled on copies of the queues
+// taken on first use so that the kitchen is not affected
+enum Queues {
+  Kitchen,
+  Copy(HashMap<Station, StationQueue>),
+}
+
+// a line of an add request. combos are expanded into the parent line followed by its components
+struct OrderLine {
+  name: String,
+  seat: Option<usize>,
+  course: Option<usize>,
+  quantity: usize,
+  price: i64, // of the whole line. components are priced on the parent
+  modifiers: Vec<Modifier>,
+  note: Option<String>,
+  parent: Option<usize>, // index of the parent line of a component
+  is_combo: bool,
+}
+
+const DEFAULT_NUM_SEATS: usize = 4;
+const DEFAULT_PRINT_WIDTH: usize = 42;
+const DEFAULT_MAX_NOTE_LEN: usize = 140;
+const DEFAULT_COOK_MINS: i64 = 10;
+const DEFAULT_MAX_QUANTITY: usize = 99;
+const DEFAULT_DINING_MINS: i64 = 45;
+const DEFAULT_MAX_TAKEOUTS: usize = 20;
+const DEFAULT_RESTORE_GRACE_MINS: i64 = 5;
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error {
   ItemNotFound,
   MaxItemsExceeded,
   BadTableId(usize),
+  BadSeat(usize),
+  BadParam(String),
+  PaymentNotFound,
+  PaymentDeclined,
+  BillNotSettled,
+  BadItemState,
+  OutOfStock(String),
+  AllergenConflict(String),
+  ReservationNotFound,
+  BadReservationState,
+  TableOccupied,
+  WaitlistEntryNotFound,
+  BadWaitlistState,
+  TakeoutNotFound,
+  BadTakeoutState,
+  MaxTakeoutsExceeded,
 }
 
 impl fmt::Display for Error {
@@ -42,8 +116,31 @@ pub struct OrderMgr {
   num_tables: usize,
   max_table_items: usize,
   one_min_in_sec: i64,
+  num_seats: usize,
+  table_seats: HashMap<usize, usize>, // # of seats of the tables that differ from num_seats
+  menu: Menu,
+  bill_config: BillConfig,
+  payment_processor: Arc<dyn PaymentProcessor>,
+  print_width: usize,
+  max_note_len: usize,
+  count_rule: CountRule,
+  auto_fire: bool,
+  allergen_strictness: Strictness,
+  default_cook_mins: i64,
+  max_quantity: usize,
+  dining_mins: i64,
+  max_takeouts: usize,
+  sla_config: SlaConfig,
+  restore_grace_mins: i64,
+  inventory: Mutex<Inventory>,
+  reservations: Mutex<ReservationBook>,
+  waitlist: Mutex<Waitlist>,
+  takeouts: Mutex<TakeoutBook>,
+  kitchen: Kitchen,
+  deferred: Mutex<Vec<(Station, String)>>, // items rescheduled while their tables could not be locked
+  overdue_events: OverdueLog,
   clock: Arc<dyn Clock>,
-  tables: Vec<Mutex<TableOrders>>,
+  tables: Vec<Mutex<TableOrders>>, // tables followed by the slots of takeout orders
 }
 
 impl OrderMgr {
@@ -53,64 +150,740 @@ impl OrderMgr {
     one_min_in_sec: i64,
     clock: Arc<dyn Clock>,
   ) -> OrderMgr {
-    let tables = vec_no_clone![Mutex::new(TableOrders::new()); num_tables];
+    let tables = vec_no_clone![Mutex::new(TableOrders::new()); num_tables + DEFAULT_MAX_TAKEOUTS];
     OrderMgr {
       num_tables,
       max_table_items,
       one_min_in_sec,
+      num_seats: DEFAULT_NUM_SEATS,
+      table_seats: HashMap::new(),
+      menu: Menu::new(vec![]),
+      bill_config: BillConfig::default(),
+      payment_processor: Arc::new(MockPaymentProcessor::new()),
+      print_width: DEFAULT_PRINT_WIDTH,
+      max_note_len: DEFAULT_MAX_NOTE_LEN,
+      count_rule: CountRule::Lines,
+      auto_fire: false,
+      allergen_strictness: Strictness::Flag,
+      default_cook_mins: DEFAULT_COOK_MINS,
+      max_quantity: DEFAULT_MAX_QUANTITY,
+      dining_mins: DEFAULT_DINING_MINS,
+      max_takeouts: DEFAULT_MAX_TAKEOUTS,
+      sla_config: SlaConfig::default(),
+      restore_grace_mins: DEFAULT_RESTORE_GRACE_MINS,
+      inventory: Mutex::new(Inventory::new(vec![])),
+      reservations: Mutex::new(ReservationBook::new()),
+      waitlist: Mutex::new(Waitlist::new()),
+      takeouts: Mutex::new(TakeoutBook::new()),
+      kitchen: Kitchen::new(&HashMap::new()),
+      deferred: Mutex::new(vec![]),
+      overdue_events: OverdueLog::new(),
       clock,
       tables,
     }
   }
 
-  #[inline]
+  pub fn with_num_seats(mut self, num_seats: usize) -> OrderMgr {
+    self.num_seats = num_seats;
+    self
+  }
+
+  pub fn with_table_seats(mut self, table_seats: HashMap<usize, usize>) -> OrderMgr {
+    self.table_seats = table_seats;
+    self
+  }
+
+  pub fn with_menu(mut self, menu: Menu) -> OrderMgr {
+    self.menu = menu;
+    self
+  }
+
+  pub fn with_bill_config(mut self, bill_config: BillConfig) -> OrderMgr {
+    self.bill_config = bill_config;
+    self
+  }
+
+  pub fn with_payment_processor(mut self, payment_processor: Arc<dyn PaymentProcessor>) -> OrderMgr {
+    self.payment_processor = payment_processor;
+    self
+  }
+
+  // # of items that each station can cook in parallel
+  pub fn with_station_capacities(mut self, capacities: HashMap<Station, usize>) -> OrderMgr {
+    self.kitchen = Kitchen::new(&capacities);
+    self
+  }
+
+  pub fn with_print_width(mut self, print_width: usize) -> OrderMgr {
+    self.print_width = print_width;
+    self
+  }
+
+  pub fn with_max_note_len(mut self, max_note_len: usize) -> OrderMgr {
+    self.max_note_len = max_note_len;
+    self
+  }
+
+  pub fn with_count_rule(mut self, count_rule: CountRule) -> OrderMgr {
+    self.count_rule = count_rule;
+    self
+  }
+
+  // fires the next course of a table automatically when the previous course is served
+  pub fn with_auto_fire(mut self, auto_fire: bool) -> OrderMgr {
+    self.auto_fire = auto_fire;
+    self
+  }
+
+  pub fn with_allergen_strictness(mut self, strictness: Strictness) -> OrderMgr {
+    self.allergen_strictness = strictness;
+    self
+  }
+
+  // cook time of the dishes w/o cook_mins on the menu
+  pub fn with_default_cook_mins(mut self, default_cook_mins: i64) -> OrderMgr {
+    self.default_cook_mins = default_cook_mins;
+    self
+  }
+
+  // max quantity of an item
+  pub fn with_max_quantity(mut self, max_quantity: usize) -> OrderMgr {
+    self.max_quantity = max_quantity;
+    self
+  }
+
+  pub fn with_dining_mins(mut self, dining_mins: i64) -> OrderMgr {
+    self.dining_mins = dining_mins;
+    self
+  }
+
+  // max # of open takeout orders
+  pub fn with_max_takeouts(mut self, max_takeouts: usize) -> OrderMgr {
+    self.max_takeouts = max_takeouts;
+    self.tables = vec_no_clone![Mutex::new(TableOrders::new()); self.num_tables + max_takeouts];
+    self
+  }
+
+  // how long cooked items may wait to be served before being reported overdue
+  pub fn with_sla_config(mut self, sla_config: SlaConfig) -> OrderMgr {
+    self.sla_config = sla_config;
+    self
+  }
+
+  // how long removed items can be restored before being purged
+  pub fn with_restore_grace_mins(mut self, restore_grace_mins: i64) -> OrderMgr {
+    self.restore_grace_mins = restore_grace_mins;
+    self
+  }
+
+  pub fn with_inventory(mut self, inventory: Inventory) -> OrderMgr {
+    self.inventory = Mutex::new(inventory);
+    self
+  }
+
+  // takeout slots have num_seats seats
+  fn seats_of(&self, table_id: usize) -> usize {
+    self.table_seats.get(&table_id).cloned().unwrap_or(self.num_seats)
+  }
+
+  fn max_seats(&self) -> usize {
+    (0..self.num_tables).map(|x| self.seats_of(x)).max().unwrap_or(self.num_seats)
+  }
+
   fn remove_cooked_items_from_orders(&self, orders: &mut TableOrders) {
-    orders.remove_before_eq_threshold(self.clock.now());
+    self.remove_cooked_items_on(&mut Queues::Kitchen, orders)
+  }
+
+  // quotes pass copies of the queues and the orders. nothing is purged or reported overdue on the copies
+  fn remove_cooked_items_on(&self, queues: &mut Queues, orders: &mut TableOrders) {
+    let now = self.clock.now();
+    let is_kitchen = matches!(queues, Queues::Kitchen);
+    self.apply_deferred_on(queues, orders);
+    orders.remove_before_eq_threshold(now);
+
+    // removed items can no longer be restored after the grace period
+    if is_kitchen {
+      for x in orders.purge_removed(now - self.restore_grace_mins * self.one_min_in_sec) {
+        info!("Purged item {} of table {}", x.uuid, x.table_id);
+      }
+    }
+
+    // scheduled items are fired once the time to start cooking has come. they are scheduled now rather than
+    // at that time since the station may have started the items queued since then
+    let due = orders.get_due(now);
+    for x in &due {
+      let (start_at, ready_at) = self.schedule_on(queues, x.station, x.table_id, &x.uuid, x.cook_secs, now);
+      orders.unhold(&x.uuid, start_at, ready_at);
+      if is_kitchen {
+        info!("Fired item {} of table {} to be served at {}", x.uuid, x.table_id, x.serve_at.unwrap());
+      }
+    }
+    if !due.is_empty() {
+      self.apply_deferred_on(queues, orders);
+      orders.remove_before_eq_threshold(now);
+    }
+    if is_kitchen {
+      self.report_overdue_items(orders, now);
+    }
+
+    // courses are fired now even if the preceding ones were served earlier since the station may
+    // have started the items queued since then. items taking no time are served as soon as they are
+    // fired, so repeat until no course is fired
+    if !self.auto_fire {
+      return
+    }
+    while let Some((course, at)) = orders.next_auto_fire().filter(|(_, at)| *at <= now) {
+      let items = self.fire_on(queues, orders, course, now);
+      if is_kitchen {
+        info!("Auto-fired {} items of course {} served at {}", items.len(), course, at);
+      }
+      orders.remove_before_eq_threshold(now);
+    }
+  }
+
+  // raises an overdue event for each item that has exceeded its SLA since the last check
+  fn report_overdue_items(&self, orders: &mut TableOrders, now: i64) {
+    for x in orders.get_session() {
+      let sla_secs = self.sla_secs_of(&x);
+      if sla::late_secs_of(&x, sla_secs, now).is_none() || !orders.set_overdue(&x.uuid) {
+        continue
+      }
+      warn!("Item {} of table {} is not served within {} secs after ready", x.uuid, x.table_id, sla_secs);
+      self.overdue_events.add(OverdueEvent {
+        uuid: x.uuid,
+        table_id: x.table_id,
+        name: x.name,
+        station: x.station,
+        ready_at: x.ready_at,
+        sla_secs,
+        at: x.ready_at + sla_secs,
+      });
+    }
+  }
+
+  fn sla_secs_of(&self, item: &Item) -> i64 {
+    self.sla_config.mins_of(self.menu.get(&item.name), item.station) * self.one_min_in_sec
+  }
+
+  // schedules the held items of the course as if they were added at the specified time
+  fn fire(&self, orders: &mut TableOrders, course: usize, at: i64) -> Vec<Item> {
+    self.fire_on(&mut Queues::Kitchen, orders, course, at)
+  }
+
+  fn fire_on(&self, queues: &mut Queues, orders: &mut TableOrders, course: usize, at: i64) -> Vec<Item> {
+    orders.set_fired(course);
+    let items: Vec<String> = orders.get_held(course).into_iter()
+      .map(|x| {
+        let (start_at, ready_at) = self.schedule_on(queues, x.station, x.table_id, &x.uuid, x.cook_secs, at);
+        orders.unhold(&x.uuid, start_at, ready_at);
+        x.uuid
+      })
+      .collect();
+    // firing an item can move the items fired before it
+    self.apply_deferred_on(queues, orders);
+    items.iter().filter_map(|x| orders.get(x)).collect()
+  }
+
+  // dishes w/o cook time on the menu take the default time so that quotes match the actual schedule
+  fn time2cook(&self, item_name: &str) -> i64 {
+    let mins = self.menu.cook_mins_of(item_name).unwrap_or(self.default_cook_mins);
+    self.one_min_in_sec * mins
+  }
+
+  // returns (start_at, ready_at) of an item based on the load of its station.
+  // items w/o station start immediately. the other items rescheduled as a result are deferred
+  // until their tables are locked
+  fn schedule(&self, station: Option<Station>, table_id: usize, item_uuid: &str, time2cook: i64, now: i64) -> (i64, i64) {
+    self.schedule_on(&mut Queues::Kitchen, station, table_id, item_uuid, time2cook, now)
+  }
+
+  fn schedule_on(
+    &self,
+    queues: &mut Queues,
+    station: Option<Station>,
+    table_id: usize,
+    item_uuid: &str,
+    time2cook: i64,
+    now: i64,
+  ) -> (i64, i64) {
+    match station {
+      Some(station) => {
+        let (schedule, refs) = self.with_queue(queues, station, |queue| {
+          queue.remove_before_eq_threshold(now);
+          let refs = queue.schedule(table_id, item_uuid, time2cook, now);
+          (queue.get(item_uuid).unwrap(), refs)
+        });
+        if let Queues::Kitchen = queues {
+          self.deferred.lock().unwrap().extend(refs.into_iter()
+            .filter(|(_, uuid)| uuid != item_uuid)
+            .map(|(_, uuid)| (station, uuid)));
+        }
+        schedule
+      },
+      None => (now, now + time2cook),
+    }
+  }
+
+  fn with_queue<R, F: FnOnce(&mut StationQueue) -> R>(&self, queues: &mut Queues, station: Station, f: F) -> R {
+    match queues {
+      Queues::Kitchen => f(&mut self.kitchen.station(station).lock().unwrap()),
+      Queues::Copy(copies) => {
+        let queue = copies.entry(station)
+          .or_insert_with(|| self.kitchen.station(station).lock().unwrap().clone());
+        f(queue)
+      },
+    }
+  }
+
+  // copies the deferred schedule to the items of the locked table. items no longer queued are dropped
+  fn apply_deferred_schedule(&self, orders: &mut TableOrders) {
+    let mut deferred = self.deferred.lock().unwrap();
+    deferred.retain(|(station, uuid)| {
+      let schedule = self.kitchen.station(*station).lock().unwrap().get(uuid);
+      match schedule {
+        Some((start_at, ready_at)) if orders.reschedule(uuid, start_at, ready_at) => {
+          info!("Rescheduled item {} to {}-{}", uuid, start_at, ready_at);
+          false
+        },
+        Some(_) => true,
+        None => false,
+      }
+    });
+  }
+
+  // copies of the queues don't defer the items they move, so the copies of the orders read all of their
+  // items from them instead
+  fn apply_deferred_on(&self, queues: &mut Queues, orders: &mut TableOrders) {
+    if let Queues::Kitchen = queues {
+      return self.apply_deferred_schedule(orders)
+    }
+    for x in orders.get_all().into_iter().filter(|x| !x.is_held) {
+      if let Some(station) = x.station {
+        if let Some((start_at, ready_at)) = self.with_queue(queues, station, |queue| queue.get(&x.uuid)) {
+          orders.reschedule(&x.uuid, start_at, ready_at);
+        }
+      }
+    }
+  }
+
+  // copies the schedule of the station to the items. tables are locked one at a time,
+  // so this must be called w/o holding any table lock
+  fn apply_schedule(&self, station: Station, refs: Vec<(usize, String)>) {
+    for (table_id, uuid) in refs {
+      let mut orders = self.tables[table_id].lock().unwrap();
+      let schedule = self.kitchen.station(station).lock().unwrap().get(&uuid);
+      if let Some((start_at, ready_at)) = schedule {
+        orders.reschedule(&uuid, start_at, ready_at);
+        info!("Rescheduled item {} of table {} to {}-{}", uuid, table_id, start_at, ready_at);
+      }
+    }
   }
 
   pub fn add_items(
     &self,
     table_id: usize,
-    item_names: &Vec<String>
+    req: &AddItemsParam,
   ) -> Result<Vec<Item>, Error> {
     validate_table_id!(table_id, self.num_tables);
+    self.place_items(table_id, req, false).map(|xs| self.timed_all(xs))
+  }
+
+  // predicts the schedule of the items as if they were added now w/o adding them
+  pub fn quote_items(&self, table_id: usize, req: &AddItemsParam) -> Result<Vec<Quote>, Error> {
+    validate_table_id!(table_id, self.num_tables);
+    let quotes: Vec<Quote> = self.place_items(table_id, req, true)?.into_iter()
+      .map(|x| Quote {
+        item_name: x.name,
+        station: x.station,
+        is_held: x.is_held,
+        start_at: x.start_at,
+        ready_at: x.ready_at,
+        allergy_flags: x.allergy_flags,
+      })
+      .collect();
+    info!("Quoted {} items for table {}", quotes.len(), table_id);
+    Ok(quotes)
+  }
+
+  // adds the items to the table or takeout slot, or only schedules them on copies of the station queues
+  // if is_quote is true
+  fn place_items(&self, table_id: usize, req: &AddItemsParam, is_quote: bool) -> Result<Vec<Item>, Error> {
+    let item_names = &req.item_names;
+
+    // seats are either omitted or specified for every item
+    if !req.seats.is_empty() && req.seats.len() != item_names.len() {
+      error!("{} seats are specified for {} items", req.seats.len(), item_names.len());
+      return Err(Error::BadParam("seats".to_string()))
+    }
+    for seat in req.seats.iter().flatten() {
+      validate_seat!(*seat, self.seats_of(table_id));
+    }
+    if !req.courses.is_empty() && req.courses.len() != item_names.len() {
+      error!("{} courses are specified for {} items", req.courses.len(), item_names.len());
+      return Err(Error::BadParam("courses".to_string()))
+    }
+    if req.courses.contains(&Some(0)) {
+      error!("Course starts from 1");
+      return Err(Error::BadParam("courses".to_string()))
+    }
+    if !req.modifiers.is_empty() && req.modifiers.len() != item_names.len() {
+      error!("{} modifiers are specified for {} items", req.modifiers.len(), item_names.len());
+      return Err(Error::BadParam("modifiers".to_string()))
+    }
+    if !req.quantities.is_empty() && req.quantities.len() != item_names.len() {
+      error!("{} quantities are specified for {} items", req.quantities.len(), item_names.len());
+      return Err(Error::BadParam("quantities".to_string()))
+    }
+    if req.quantities.iter().any(|x| *x == 0 || *x > self.max_quantity) {
+      error!("Quantity must be between 1 and {}", self.max_quantity);
+      return Err(Error::BadParam("quantities".to_string()))
+    }
+    if req.serve_at.is_some() && !req.courses.is_empty() {
+      error!("serve_at cannot be used w/ courses");
+      return Err(Error::BadParam("serve_at".to_string()))
+    }
+    if !req.substitutions.is_empty() && req.substitutions.len() != item_names.len() {
+      error!("{} substitutions are specified for {} items", req.substitutions.len(), item_names.len());
+      return Err(Error::BadParam("substitutions".to_string()))
+    }
+    let lines = self.lines_of(req)?;
     let now = self.clock.now();
+    if req.serve_at.map(|x| x < now).unwrap_or(false) {
+      error!("serve_at {} is in the past", req.serve_at.unwrap());
+      return Err(Error::BadParam("serve_at".to_string()))
+    }
 
-    // get orders for the table
-    let orders_mut = &self.tables[table_id];
-    let mut orders = orders_mut.lock().unwrap();
-    self.remove_cooked_items_from_orders(&mut orders);
+    // get orders for the table. quotes work on a copy of the orders so that firing the due items
+    // doesn't affect the table
+    let mut queues = if is_quote { Queues::Copy(HashMap::new()) } else { Queues::Kitchen };
+    let copy;
+    let mut orders = if is_quote {
+      copy = Mutex::new(self.tables[table_id].lock().unwrap().copy());
+      copy.lock().unwrap()
+    } else {
+      self.tables[table_id].lock().unwrap()
+    };
+    self.remove_cooked_items_on(&mut queues, &mut orders);
 
-    // return error if # of items exceeds the limit
-    if orders.len() == self.max_table_items {
+    // return error if # of items exceeds the limit. combos are counted by their components
+    let cooked = lines.iter().filter(|x| !x.is_combo);
+    let (num_items, num_added) = match self.count_rule {
+      CountRule::Lines => (orders.len(), cooked.count()),
+      CountRule::Units => (orders.num_units(), cooked.map(|x| x.quantity).sum()),
+    };
+    if num_items + num_added > self.max_table_items {
       error!("Max # of items ({}) reached. Ignoring add request.", self.max_table_items);
       return Err(Error::MaxItemsExceeded)
     }
 
-    let mut items = vec![];
+    // combos are checked by their components
+    let restrictions = orders.get_restrictions();
+    let allergy_flags: Vec<Vec<String>> = lines.iter()
+      .map(|line| if line.is_combo { vec![] } else { self.allergy_flags_of(&line.name, line.seat, &restrictions) })
+      .collect();
+    if self.allergen_strictness == Strictness::Reject {
+      if let Some(i) = allergy_flags.iter().position(|x| !x.is_empty()) {
+        error!("{} conflicts w/ {:?}. Ignoring add request.", lines[i].name, allergy_flags[i]);
+        return Err(Error::AllergenConflict(lines[i].name.clone()))
+      }
+    }
+
+    // ingredients of all items are taken at once so that nothing is taken if any item is out of stock
+    {
+      let mut inventory = self.inventory.lock().unwrap();
+      let dishes: Vec<(String, usize)> = lines.iter()
+        .filter(|x| !x.is_combo)
+        .map(|x| (x.name.clone(), x.quantity))
+        .collect();
+      let res = if is_quote {
+        inventory.check(&self.menu, &dishes).map(|_| vec![])
+      } else {
+        inventory.take(&self.menu, &dishes)
+      };
+      match res {
+        Ok(became_low) => {
+          for x in became_low {
+            warn!("Stock of {} is low: {}", x.ingredient, x.quantity);
+          }
+        },
+        Err(name) => {
+          error!("{} is out of stock. Ignoring add request.", name);
+          return Err(Error::OutOfStock(name))
+        },
+      }
+    }
+
+    let mut items: Vec<Item> = vec![];
+    let sync = req.sync.unwrap_or_else(|| orders.is_sync_ready());
 
     // create items and add to orders
-    let mut rng = thread_rng();
     let created_at = now;
-    let time2cook: i64 = self.one_min_in_sec * rng.gen_range(5, 15);
-    let ready_at = created_at + time2cook;
 
-    for item_name in item_names {
+    for (i, line) in lines.into_iter().enumerate() {
+      let uuid = Uuid::new_v4().to_string();
+      let (station, time2cook) = if line.is_combo {
+        (None, 0)
+      } else {
+        (self.menu.station_of(&line.name), self.time2cook(&line.name))
+      };
+
+      // items of the courses not fired yet are not scheduled until fired. combos are not cooked by themselves.
+      // items to be served later are held w/ the tentative schedule until it's time to start cooking
+      let course = line.course;
+      let serve_at = if line.is_combo { None } else { req.serve_at };
+      let is_scheduled = serve_at.map(|x| x - time2cook > now).unwrap_or(false);
+      let is_held = is_scheduled || (!line.is_combo && course.map(|x| !orders.is_fired(x)).unwrap_or(false));
+      let (start_at, ready_at) = if is_scheduled {
+        (serve_at.unwrap() - time2cook, serve_at.unwrap())
+      } else if is_held || line.is_combo {
+        (0, 0)
+      } else {
+        self.schedule_on(&mut queues, station, table_id, &uuid, time2cook, now)
+      };
+
       let item = Item {
-        uuid: Uuid::new_v4().to_string(),
-        name: item_name.to_string(),
+        uuid,
+        name: line.name,
         table_id,
+        takeout_id: orders.get_takeout_id(),
+        seat: line.seat,
+        price: line.price,
+        quantity: line.quantity,
+        station,
         created_at,
+        start_at,
         ready_at,
+        cook_secs: time2cook,
+        started_at: None,
+        bumped_at: None,
+        served_at: None,
+        course,
+        is_held,
+        serve_at,
+        allergy_flags: allergy_flags[i].clone(),
+        modifiers: line.modifiers,
+        note: line.note,
+        parent_id: line.parent.map(|x| items[x].uuid.clone()),
+        is_combo: line.is_combo,
         is_removed: false,
+        removed_at: None,
+        is_voided: false,
+        remaining_secs: 0,
+        remaining_minutes: 0,
+        elapsed_secs: 0,
+        is_overdue: false,
       };
-      orders.add(item.clone());
-      items.push(item.clone());
-      info!("Added item {} to table {}", item_name, table_id);
+      items.push(item);
+    }
+
+    // scheduling an item can move the items of the request scheduled before it
+    for x in items.iter_mut().filter(|x| !x.is_held && !x.is_combo) {
+      if let Some(station) = x.station {
+        if let Some((start_at, ready_at)) = self.with_queue(&mut queues, station, |queue| queue.get(&x.uuid)) {
+          x.start_at = start_at;
+          x.ready_at = ready_at;
+        }
+      }
+    }
+
+    let rescheduled = if sync {
+      self.synchronize(&mut queues, &mut items, now)
+    } else {
+      vec![]
+    };
+    if is_quote {
+      return Ok(items)
+    }
+
+    for x in &items {
+      if x.is_combo {
+        orders.add_to_session(x.clone());
+      } else {
+        orders.add(x.clone());
+      }
+      info!("Added item {} to table {}", x.name, table_id);
+    }
+    self.apply_deferred_schedule(&mut orders);
+
+    // held items can be fired immediately if the previous courses have been served
+    if self.auto_fire {
+      self.remove_cooked_items_from_orders(&mut orders);
+      items = items.into_iter().map(|x| orders.get(&x.uuid).unwrap_or(x)).collect();
+    }
+    drop(orders);
+    for (station, refs) in rescheduled {
+      self.apply_schedule(station, refs);
     }
     Ok(items) // return generated items to user
   }
 
+  // validates modifiers and substitutions of the requested items and expands combos into their components
+  fn lines_of(&self, req: &AddItemsParam) -> Result<Vec<OrderLine>, Error> {
+    let mut lines = vec![];
+    for (i, item_name) in req.item_names.iter().enumerate() {
+      let dish = self.menu.get(item_name);
+      if dish.is_none() {
+        warn!("{} is not on the menu. It's free of charge and not routed to any station", item_name);
+      }
+      let quantity = req.quantities.get(i).cloned().unwrap_or(1);
+      let modifiers_req = req.modifiers.get(i).cloned().unwrap_or_default();
+      let modifiers = match modifier::resolve(dish, &modifiers_req, self.max_note_len) {
+        Ok(xs) => xs,
+        Err(reason) => {
+          error!("Bad modifiers of {}: {}", item_name, reason);
+          return Err(Error::BadParam("modifiers".to_string()))
+        },
+      };
+      let substitutions = req.substitutions.get(i).cloned().unwrap_or_default();
+      let components = match dish {
+        Some(x) if !x.components.is_empty() => combo::resolve(x, &substitutions),
+        _ if substitutions.is_empty() => Ok(vec![]),
+        _ => Err("not a combo".to_string()),
+      };
+      let components = match components {
+        Ok(xs) => xs,
+        Err(reason) => {
+          error!("Bad substitutions of {}: {}", item_name, reason);
+          return Err(Error::BadParam("substitutions".to_string()))
+        },
+      };
+
+      let unit_price = self.menu.price_of(item_name)
+        + modifiers.iter().chain(&components).map(|x| x.price).sum::<i64>();
+      let price = match unit_price.checked_mul(quantity as i64) {
+        Some(x) => x,
+        None => {
+          error!("Price of {} x {} overflows", item_name, quantity);
+          return Err(Error::BadParam("quantities".to_string()))
+        },
+      };
+      let parent = lines.len();
+      let line = OrderLine {
+        name: item_name.to_string(),
+        seat: req.seats.get(i).cloned().flatten(),
+        course: req.courses.get(i).cloned().flatten(),
+        quantity,
+        price,
+        modifiers,
+        note: modifiers_req.note.filter(|x| !x.is_empty()),
+        parent: None,
+        is_combo: !components.is_empty(),
+      };
+      let components: Vec<OrderLine> = components.into_iter()
+        .map(|x| OrderLine {
+          name: x.name,
+          seat: line.seat,
+          course: line.course,
+          quantity,
+          price: 0,
+          modifiers: vec![],
+          note: None,
+          parent: Some(parent),
+          is_combo: false,
+        })
+        .collect();
+      lines.push(line);
+      lines.extend(components);
+    }
+    Ok(lines)
+  }
+
+  // delays the starts of the items not held so that all of them are ready at the latest ready_at among them.
+  // a delayed item may push back the items queued behind it, so this repeats until the latest ready_at
+  // stops changing. returns the items of the stations rescheduled as a result
+  fn synchronize(&self, queues: &mut Queues, items: &mut [Item], now: i64) -> Vec<(Station, Vec<(usize, String)>)> {
+    let mut rescheduled = vec![];
+    let mut target = i64::MIN;
+
+    for _ in 0..=items.len() {
+      let latest = items.iter().filter(|x| !x.is_held && !x.is_combo).map(|x| x.ready_at).max().unwrap_or(target);
+      if latest == target {
+        break
+      }
+      target = latest;
+
+      for x in items.iter().filter(|x| !x.is_held && !x.is_combo) {
+        if let Some(station) = x.station {
+          let changed = self.with_queue(queues, station, |queue| queue.delay(&x.uuid, target - x.cook_secs, now));
+          rescheduled.push((station, changed));
+        }
+      }
+      // delaying an item can change the schedule of the others, so read them after all are delayed
+      for x in items.iter_mut().filter(|x| !x.is_held && !x.is_combo) {
+        let schedule = match x.station {
+          Some(station) => self.with_queue(queues, station, |queue| queue.get(&x.uuid)),
+          None => Some((target - x.cook_secs, target)),
+        };
+        if let Some((start_at, ready_at)) = schedule {
+          x.start_at = start_at;
+          x.ready_at = ready_at;
+        }
+      }
+    }
+    rescheduled
+  }
+
+  // items are checked against the guests at their seats and the whole table.
+  // items w/o seat are shared, so they are checked against all guests
+  fn allergy_flags_of(&self, item_name: &str, seat: Option<usize>, restrictions: &[Restriction]) -> Vec<String> {
+    let restrictions: Vec<&Restriction> = restrictions.iter()
+      .filter(|x| seat.is_none() || x.seat.is_none() || x.seat == seat)
+      .collect();
+    allergen::conflicts_of(self.menu.get(item_name), &restrictions)
+  }
+
+  // records allergies and diets of the guest at the seat or the whole table. the outstanding items
+  // served to the guest are flagged again, but never rejected since they have already been ordered
+  pub fn set_restriction(&self, table_id: usize, req: &Restriction) -> Result<(), Error> {
+    validate_table_id!(table_id, self.num_tables);
+    if let Some(seat) = req.seat {
+      validate_seat!(seat, self.seats_of(table_id));
+    }
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+    orders.set_restriction(req.clone());
+    info!("Set restriction {:?} of table {}", req, table_id);
+
+    let restrictions = orders.get_restrictions();
+    let items = orders.get_all().into_iter()
+      .filter(|x| req.seat.is_none() || x.seat.is_none() || x.seat == req.seat);
+    for x in items {
+      let allergy_flags = self.allergy_flags_of(&x.name, x.seat, &restrictions);
+      if allergy_flags == x.allergy_flags {
+        continue
+      }
+      if !allergy_flags.is_empty() {
+        warn!("Outstanding item {} of table {} conflicts w/ {:?}", x.uuid, table_id, allergy_flags);
+      }
+      orders.update(&x.uuid, |x| x.allergy_flags = allergy_flags);
+    }
+    Ok(())
+  }
+
+  pub fn get_restrictions(&self, table_id: usize) -> Result<Vec<Restriction>, Error> {
+    validate_table_id!(table_id, self.num_tables);
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let orders = orders_mut.lock().unwrap();
+    Ok(orders.get_restrictions())
+  }
+
+  // makes the items added to the table together ready at the same time by default
+  pub fn set_sync_ready(&self, table_id: usize, req: &SyncParam) -> Result<(), Error> {
+    validate_table_id!(table_id, self.num_tables);
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    orders.set_sync_ready(req.enabled);
+    info!("Set sync ready of table {} to {}", table_id, req.enabled);
+    Ok(())
+  }
+
   pub fn remove_item(&self, table_id: usize, item_uuid: &str) -> Result<(), Error> {
     validate_table_id!(table_id, self.num_tables);
 
@@ -119,8 +892,25 @@ impl OrderMgr {
     let mut orders = orders_mut.lock().unwrap();
     self.remove_cooked_items_from_orders(&mut orders);
 
-    if let Some(x) = orders.remove(item_uuid) {
+    // components are cancelled together w/ their combo
+    match orders.get_from_session(item_uuid) {
+      Some(x) if x.is_combo && !x.is_removed => return self.remove_combo(orders, table_id, item_uuid),
+      Some(x) if x.parent_id.is_some() => {
+        warn!("Item {} is a component of combo {}", item_uuid, x.parent_id.unwrap());
+        return Err(Error::BadItemState)
+      },
+      _ => (),
+    }
+    if let Some(x) = orders.remove(item_uuid, self.clock.now()) {
       info!("Removed item {:?} from table {}", x, table_id);
+      self.inventory.lock().unwrap().restore(&self.menu, &x.name, x.quantity);
+
+      // items queued behind the removed item can be cooked earlier
+      if let Some(station) = x.station {
+        let rescheduled = self.kitchen.station(station).lock().unwrap().remove(item_uuid, self.clock.now());
+        drop(orders);
+        self.apply_schedule(station, rescheduled);
+      }
       Ok(())
     } else {
       warn!("Item {} not found", item_uuid);
@@ -128,6 +918,175 @@ impl OrderMgr {
     }
   }
 
+  // cancels a combo and its components. all of the components need to be outstanding
+  fn remove_combo(&self, mut orders: MutexGuard<TableOrders>, table_id: usize, item_uuid: &str) -> Result<(), Error> {
+    let components = orders.get_components(item_uuid);
+    if components.iter().any(|x| orders.get(&x.uuid).is_none()) {
+      warn!("Some components of combo {} have already been cooked", item_uuid);
+      return Err(Error::BadItemState)
+    }
+
+    let now = self.clock.now();
+    let mut rescheduled = vec![];
+    for x in components {
+      orders.remove(&x.uuid, now);
+      self.inventory.lock().unwrap().restore(&self.menu, &x.name, x.quantity);
+      if let Some(station) = x.station {
+        rescheduled.push((station, self.kitchen.station(station).lock().unwrap().remove(&x.uuid, now)));
+      }
+    }
+    let x = orders.update_in_session(item_uuid, |x| {
+      x.is_removed = true;
+      x.removed_at = Some(now);
+    }).unwrap();
+    info!("Removed combo {:?} from table {}", x, table_id);
+    drop(orders);
+
+    for (station, refs) in rescheduled {
+      self.apply_schedule(station, refs);
+    }
+    Ok(())
+  }
+
+  // revives an item removed within the grace period w/ its original schedule. a combo is restored
+  // together w/ its components. items whose ready_at has passed are restored as cooked
+  pub fn restore_item(&self, table_id: usize, item_uuid: &str) -> Result<Item, Error> {
+    validate_table_id!(table_id, self.num_tables);
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+
+    let (is_combo, items) = match orders.get_from_session(item_uuid) {
+      Some(x) if x.parent_id.is_some() => {
+        warn!("Item {} is a component of combo {}", item_uuid, x.parent_id.unwrap());
+        return Err(Error::BadItemState)
+      },
+      Some(x) if x.is_combo && x.is_removed => (true, orders.get_session().into_iter()
+        .filter(|c| c.parent_id.as_deref() == Some(item_uuid) && c.is_removed)
+        .collect::<Vec<Item>>()),
+      Some(x) if x.is_removed => (false, vec![x]),
+      Some(_) => {
+        warn!("Item {} of table {} has not been removed", item_uuid, table_id);
+        return Err(Error::BadItemState)
+      },
+      None => {
+        warn!("Item {} not found or purged", item_uuid);
+        return Err(Error::ItemNotFound)
+      },
+    };
+
+    // return error if # of items exceeds the limit. a combo brings back all of its components
+    let (num_items, num_restored) = match self.count_rule {
+      CountRule::Lines => (orders.len(), items.len()),
+      CountRule::Units => (orders.num_units(), items.iter().map(|x| x.quantity).sum()),
+    };
+    if num_items + num_restored > self.max_table_items {
+      error!("Max # of items ({}) reached. Ignoring restore request.", self.max_table_items);
+      return Err(Error::MaxItemsExceeded)
+    }
+
+    // ingredients put back on removal are taken again
+    let dishes: Vec<(String, usize)> = items.iter().map(|x| (x.name.clone(), x.quantity)).collect();
+    match self.inventory.lock().unwrap().take(&self.menu, &dishes) {
+      Ok(became_low) => {
+        for x in became_low {
+          warn!("Stock of {} is low: {}", x.ingredient, x.quantity);
+        }
+      },
+      Err(name) => {
+        error!("{} is out of stock. Ignoring restore request.", name);
+        return Err(Error::OutOfStock(name))
+      },
+    }
+
+    let now = self.clock.now();
+    let mut rescheduled = vec![];
+    for x in items {
+      let x = orders.revive(&x.uuid).unwrap();
+      match (x.station, x.course) {
+        // the course has been fired while the item was removed
+        (_, Some(course)) if x.is_held && orders.is_fired(course) => {
+          self.fire(&mut orders, course, now);
+        },
+        (Some(station), _) if !x.is_held && x.ready_at > now => {
+          let mut queue = self.kitchen.station(station).lock().unwrap();
+          let mut refs = queue.restore(table_id, &x.uuid, x.start_at, x.ready_at, now);
+          // the slot of the item has been taken while it was removed
+          if let Some(pos) = refs.iter().position(|r| r.1 == x.uuid) {
+            refs.remove(pos);
+            let (start_at, ready_at) = queue.get(&x.uuid).unwrap();
+            warn!("Slot of item {} has been taken. Rescheduled to {}-{}", x.uuid, start_at, ready_at);
+            orders.reschedule(&x.uuid, start_at, ready_at);
+          }
+          rescheduled.push((station, refs));
+        },
+        _ => (),
+      }
+    }
+    // components are revived above and the combo itself is revived here
+    let x = if is_combo {
+      orders.revive(item_uuid).unwrap()
+    } else {
+      orders.get_from_session(item_uuid).unwrap()
+    };
+    info!("Restored item {:?} of table {}", x, table_id);
+    drop(orders);
+
+    for (station, refs) in rescheduled {
+      self.apply_schedule(station, refs);
+    }
+    Ok(self.timed(x))
+  }
+
+  // cancels a part of the quantity of an outstanding item. use remove_item to cancel the whole item
+  pub fn decrement_item(&self, table_id: usize, item_uuid: &str, req: &DecrementParam) -> Result<Item, Error> {
+    validate_table_id!(table_id, self.num_tables);
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+
+    // components of a combo are decremented together w/ the combo
+    let (quantity, components) = match orders.get_from_session(item_uuid) {
+      Some(x) if x.is_combo && !x.is_removed => (x.quantity, orders.get_components(item_uuid)),
+      Some(x) if x.parent_id.is_some() => {
+        warn!("Item {} is a component of combo {}", item_uuid, x.parent_id.unwrap());
+        return Err(Error::BadItemState)
+      },
+      _ => match orders.get(item_uuid) {
+        Some(x) => (x.quantity, vec![x]),
+        None => {
+          warn!("Item {} not found", item_uuid);
+          return Err(Error::ItemNotFound)
+        },
+      },
+    };
+    if req.by == 0 || req.by >= quantity {
+      error!("Cannot decrement quantity {} by {}", quantity, req.by);
+      return Err(Error::BadParam("by".to_string()))
+    }
+    if components.iter().any(|x| orders.get(&x.uuid).is_none()) {
+      warn!("Some components of combo {} have already been cooked", item_uuid);
+      return Err(Error::BadItemState)
+    }
+    let decrement = |x: &mut Item| {
+      x.price = x.price / quantity as i64 * (quantity - req.by) as i64;
+      x.quantity -= req.by;
+    };
+    for x in &components {
+      if x.uuid != item_uuid {
+        orders.update(&x.uuid, decrement);
+      }
+      self.inventory.lock().unwrap().restore(&self.menu, &x.name, req.by);
+    }
+    let item = orders.update_in_session(item_uuid, decrement).unwrap();
+    info!("Decremented quantity of item {} of table {} by {}", item_uuid, table_id, req.by);
+    Ok(self.timed(item))
+  }
+
   pub fn get_item(&self, table_id: usize, item_uuid: &str) -> Result<Item, Error> {
     validate_table_id!(table_id, self.num_tables);
 
@@ -138,7 +1097,7 @@ impl OrderMgr {
 
     if let Some(item) = orders.get(item_uuid) {
       info!("Got item {} from table {}", item_uuid, table_id);
-      Ok(item)
+      Ok(self.timed(item))
     } else {
       Err(Error::ItemNotFound)
     }
@@ -155,356 +1114,3113 @@ impl OrderMgr {
     let items = orders.get_all();
     info!("Got all {} items from table {}", items.len(), table_id);
 
-    Ok(items)
+    Ok(self.timed_all(items))
   }
-}
 
-#[cfg(test)]
-mod tests {
-  use super::*;
-  use crate::clock::arbitrary_clock::ArbitraryClock;
+  // returns a page of outstanding items of all tables and takeout orders in the order of (ready_at, uuid)
+  // after the cursor. tables are locked one at a time, so the page is not a snapshot of a single moment
+  pub fn get_items(&self, filter: &ItemFilter, cursor: Option<&str>, limit: Option<usize>) -> Result<ItemPage, Error> {
+    if let Some(table_id) = filter.table_id {
+      validate_table_id!(table_id, self.num_tables);
+    }
+    let cursor = match cursor.map(|x| x.parse::<Cursor>()) {
+      Some(Ok(x)) => Some(x),
+      Some(Err(e)) => {
+        error!("{}", e);
+        return Err(Error::BadParam("cursor".to_string()))
+      },
+      None => None,
+    };
+    let limit = limit.unwrap_or(query::DEFAULT_PAGE_LIMIT);
+    if limit == 0 || limit > query::MAX_PAGE_LIMIT {
+      error!("Limit must be 1-{}, but {} is specified", query::MAX_PAGE_LIMIT, limit);
+      return Err(Error::BadParam("limit".to_string()))
+    }
 
-  fn get_clock() -> Arc<dyn Clock> {
-    Arc::new(ArbitraryClock::new())
+    let now = self.clock.now();
+    let table_ids = match filter.table_id {
+      Some(x) => x..x + 1,
+      None => 0..self.tables.len(),
+    };
+    let mut items = vec![];
+    for table_id in table_ids {
+      let more: Vec<Item> = {
+        let mut orders = self.tables[table_id].lock().unwrap();
+        self.remove_cooked_items_from_orders(&mut orders);
+        orders.get_all().into_iter()
+          .filter(|x| filter.matches(x, now) && cursor.as_ref().map(|c| c.is_before(x)).unwrap_or(true))
+          .collect()
+      };
+      query::merge(&mut items, more, limit);
+    }
+    let page = query::page_of(items, limit);
+    info!("Got {} items across tables w/ {:?}", page.items.len(), filter);
+    Ok(ItemPage { items: self.timed_all(page.items), ..page })
   }
 
-  #[test]
-  fn test_new() {
-    let om = OrderMgr::new(1, 2, 1, get_clock());
-    assert_eq!(1, om.num_tables);
-    assert_eq!(2, om.max_table_items);
-  }
+  // returns outstanding items of the table that match the filter in the order of the sort key.
+  // the order is stable, so offset and limit page through the items unless they change
+  pub fn get_table_items(
+    &self,
+    table_id: usize,
+    filter: &ItemFilter,
+    sort: ItemSort,
+    offset: usize,
+    limit: Option<usize>,
+  ) -> Result<Vec<Item>, Error> {
+    validate_table_id!(table_id, self.num_tables);
+    if let Some(seat) = filter.seat {
+      validate_seat!(seat, self.seats_of(table_id));
+    }
+    if limit.map(|x| x == 0 || x > query::MAX_PAGE_LIMIT).unwrap_or(false) {
+      error!("Limit must be 1-{}, but {} is specified", query::MAX_PAGE_LIMIT, limit.unwrap());
+      return Err(Error::BadParam("limit".to_string()))
+    }
+
+    let mut items: Vec<Item> = {
+      let mut orders = self.tables[table_id].lock().unwrap();
+      self.remove_cooked_items_from_orders(&mut orders);
+      let now = self.clock.now();
+      orders.get_all().into_iter().filter(|x| filter.matches(x, now)).collect()
+    };
+    query::sort(&mut items, sort);
+    let items: Vec<Item> = items.into_iter()
+      .skip(offset)
+      .take(limit.unwrap_or(usize::MAX))
+      .collect();
+    info!("Got {} items from table {} w/ {:?}", items.len(), table_id, filter);
+    Ok(self.timed_all(items))
+  }
+
+  pub fn get_seat_items(&self, table_id: usize, seat: usize) -> Result<Vec<Item>, Error> {
+    validate_table_id!(table_id, self.num_tables);
+    validate_seat!(seat, self.seats_of(table_id));
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+
+    let items: Vec<Item> = orders.get_all().into_iter()
+      .filter(|x| x.seat == Some(seat))
+      .collect();
+    info!("Got {} items of seat {} from table {}", items.len(), seat, table_id);
+
+    Ok(self.timed_all(items))
+  }
+
+  // returns the items routed to the station across all tables and takeout orders in the order of ready_at.
+  // tables are locked one at a time after the station queue is released
+  // reads the station queue and looks up only the tables w/ items on it. the schedule is taken from the queue
+  // since the tables may not have caught up w/ it yet. nothing is fired, so this is cheap enough to poll
+  pub fn get_station_items(&self, station: Station) -> Result<Vec<Item>, Error> {
+    let jobs: Vec<(usize, String, (i64, i64))> = {
+      let mut queue = self.kitchen.station(station).lock().unwrap();
+      queue.remove_before_eq_threshold(self.clock.now());
+      queue.get_all().into_iter()
+        .filter_map(|(table_id, uuid)| queue.get(&uuid).map(|x| (table_id, uuid, x)))
+        .collect()
+    };
+
+    let items: Vec<Item> = jobs.into_iter()
+      .filter_map(|(table_id, uuid, (start_at, ready_at))| {
+        let orders = self.tables[table_id].lock().unwrap();
+        orders.get(&uuid).map(|x| Item { start_at, ready_at, ..x })
+      })
+      .collect();
+    info!("Got {} items of station {:?}", items.len(), station);
+
+    Ok(self.timed_all(items))
+  }
+
+  // chef started cooking the item. if the item was waiting to start, it starts now if a slot of its
+  // station is free and the items queued behind it are rescheduled
+  pub fn start_item(&self, table_id: usize, item_uuid: &str) -> Result<Item, Error> {
+    validate_table_id!(table_id, self.num_tables);
+    self.start_item_of(table_id, item_uuid)
+  }
+
+  pub fn start_takeout_item(&self, takeout_id: &str, item_uuid: &str) -> Result<Item, Error> {
+    let takeout = self.open_takeout_of(takeout_id)?;
+    self.start_item_of(takeout.slot, item_uuid)
+  }
+
+  fn start_item_of(&self, table_id: usize, item_uuid: &str) -> Result<Item, Error> {
+    let now = self.clock.now();
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+
+    let station = match orders.get(item_uuid) {
+      Some(x) if x.started_at.is_some() || x.is_held => {
+        warn!("Item {} of table {} has already been started or is held", item_uuid, table_id);
+        return Err(Error::BadItemState)
+      },
+      Some(x) => x.station,
+      None => {
+        warn!("Item {} not found", item_uuid);
+        return Err(Error::ItemNotFound)
+      },
+    };
+    // the station queue is kept locked from the check to the start so that no other item takes the slot
+    let started = match station {
+      Some(station) => {
+        let mut queue = self.kitchen.station(station).lock().unwrap();
+        if !queue.can_start(item_uuid, now) {
+          warn!("All slots of station {:?} are busy. Item {} of table {} cannot start", station, item_uuid, table_id);
+          return Err(Error::BadItemState)
+        }
+        Some((station, queue.start(item_uuid, now), queue.get(item_uuid)))
+      },
+      None => None,
+    };
+    let mut item = orders.update(item_uuid, |x| x.started_at = Some(now)).unwrap();
+
+    if let Some((station, rescheduled, schedule)) = started {
+      if let Some((start_at, ready_at)) = schedule {
+        item = orders.update(item_uuid, |x| {
+          x.start_at = start_at;
+          x.ready_at = ready_at;
+        }).unwrap();
+      }
+      drop(orders);
+      self.apply_schedule(station, rescheduled);
+    }
+    self.add_kds_event(&item, KdsAction::Start, now);
+    info!("Started item {} of table {}", item_uuid, table_id);
+    Ok(self.timed(item))
+  }
+
+  // chef marked the item as ready. the item is served immediately and frees its slot
+  pub fn bump_item(&self, table_id: usize, item_uuid: &str) -> Result<Item, Error> {
+    validate_table_id!(table_id, self.num_tables);
+    self.bump_item_of(table_id, item_uuid)
+  }
+
+  pub fn bump_takeout_item(&self, takeout_id: &str, item_uuid: &str) -> Result<Item, Error> {
+    let takeout = self.open_takeout_of(takeout_id)?;
+    self.bump_item_of(takeout.slot, item_uuid)
+  }
+
+  fn bump_item_of(&self, table_id: usize, item_uuid: &str) -> Result<Item, Error> {
+    let now = self.clock.now();
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+
+    if let Some(x) = orders.get(item_uuid) {
+      if x.is_held {
+        warn!("Item {} of table {} is held", item_uuid, table_id);
+        return Err(Error::BadItemState)
+      }
+    }
+    let item = match orders.update(item_uuid, |x| {
+      x.start_at = x.start_at.min(now);
+      x.ready_at = now;
+      x.bumped_at = Some(now);
+    }) {
+      Some(x) => x,
+      None => {
+        warn!("Item {} not found", item_uuid);
+        return Err(Error::ItemNotFound)
+      },
+    };
+    // item is ready now
+    self.remove_cooked_items_from_orders(&mut orders);
+
+    if let Some(station) = item.station {
+      let rescheduled = self.kitchen.station(station).lock().unwrap().remove(item_uuid, now);
+      drop(orders);
+      self.apply_schedule(station, rescheduled);
+    }
+    self.add_kds_event(&item, KdsAction::Bump, now);
+    info!("Bumped item {} of table {}", item_uuid, table_id);
+    Ok(self.timed(item))
+  }
+
+  // puts a bumped item back to cooking. the item is rescheduled on its station as if it was added now
+  pub fn recall_item(&self, table_id: usize, item_uuid: &str) -> Result<Item, Error> {
+    validate_table_id!(table_id, self.num_tables);
+    self.recall_item_of(table_id, item_uuid)
+  }
+
+  pub fn recall_takeout_item(&self, takeout_id: &str, item_uuid: &str) -> Result<Item, Error> {
+    let takeout = self.open_takeout_of(takeout_id)?;
+    self.recall_item_of(takeout.slot, item_uuid)
+  }
+
+  fn recall_item_of(&self, table_id: usize, item_uuid: &str) -> Result<Item, Error> {
+    let now = self.clock.now();
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+
+    let item = match orders.get_from_session(item_uuid) {
+      Some(x) if !x.is_removed => x,
+      _ => {
+        warn!("Item {} not found", item_uuid);
+        return Err(Error::ItemNotFound)
+      },
+    };
+    if item.bumped_at.is_none() || orders.get(item_uuid).is_some() {
+      warn!("Item {} of table {} has not been bumped", item_uuid, table_id);
+      return Err(Error::BadItemState)
+    }
+
+    let (start_at, ready_at) = self.schedule(item.station, table_id, item_uuid, item.cook_secs, now);
+    let item = orders.restore(item_uuid, |x| {
+      x.start_at = start_at;
+      x.ready_at = ready_at;
+      x.started_at = None;
+      x.bumped_at = None;
+      x.served_at = None;
+    }).unwrap();
+    self.apply_deferred_schedule(&mut orders);
+
+    self.add_kds_event(&item, KdsAction::Recall, now);
+    info!("Recalled item {} of table {}", item_uuid, table_id);
+    Ok(self.timed(item))
+  }
+
+  // chef actions in chronological order. all stations if station is not specified
+  pub fn get_kds_events(&self, station: Option<Station>) -> Result<Vec<KdsEvent>, Error> {
+    let events = self.kitchen.get_events(station);
+    info!("Got {} KDS events of station {:?}", events.len(), station);
+    Ok(events)
+  }
+
+  pub fn get_stocks(&self) -> Result<Vec<Stock>, Error> {
+    Ok(self.inventory.lock().unwrap().get_all())
+  }
+
+  // stocks whose quantity is less than or equal to their low stock threshold
+  pub fn get_low_stocks(&self) -> Result<Vec<Stock>, Error> {
+    let stocks = self.inventory.lock().unwrap().get_low();
+    info!("Got {} low stocks", stocks.len());
+    Ok(stocks)
+  }
+
+  pub fn set_stock(&self, ingredient: &str, req: &StockParam) -> Result<Stock, Error> {
+    if req.quantity < 0 {
+      return Err(Error::BadParam("quantity".to_string()))
+    }
+    let stock = Stock {
+      ingredient: ingredient.to_string(),
+      quantity: req.quantity,
+      low_stock: req.low_stock,
+    };
+    self.inventory.lock().unwrap().set(stock.clone());
+    info!("Set stock {:?}", stock);
+    Ok(stock)
+  }
+
+  pub fn get_86_board(&self) -> Result<EightySixBoard, Error> {
+    Ok(self.inventory.lock().unwrap().board(&self.menu))
+  }
+
+  // only dishes on the menu can be 86-ed since ingredients and unknown names are never checked on ordering
+  pub fn eighty_six(&self, dish_name: &str) -> Result<(), Error> {
+    if self.menu.get(dish_name).is_none() {
+      error!("{} is not on the menu", dish_name);
+      return Err(Error::BadParam("dish".to_string()))
+    }
+    self.inventory.lock().unwrap().eighty_six(dish_name);
+    info!("86-ed {}", dish_name);
+    Ok(())
+  }
+
+  pub fn un_eighty_six(&self, dish_name: &str) -> Result<(), Error> {
+    if self.inventory.lock().unwrap().un_eighty_six(dish_name) {
+      info!("Un-86-ed {}", dish_name);
+      Ok(())
+    } else {
+      warn!("{} is not 86-ed", dish_name);
+      Err(Error::ItemNotFound)
+    }
+  }
+
+  fn validate_reservation(&self, req: &ReservationParam) -> Result<(), Error> {
+    validate_table_id!(req.table_id, self.num_tables);
+    if req.party_size == 0 || req.party_size > self.seats_of(req.table_id) {
+      error!("Party of {} cannot be seated", req.party_size);
+      return Err(Error::BadParam("party_size".to_string()))
+    }
+    if req.at < self.clock.now() {
+      error!("Reservation time {} is in the past", req.at);
+      return Err(Error::BadParam("at".to_string()))
+    }
+    if req.serve_at.map(|x| x < req.at).unwrap_or(false) {
+      error!("Pre-order cannot be served before the party arrives");
+      return Err(Error::BadParam("serve_at".to_string()))
+    }
+    // serve time of the pre-order is given by the reservation
+    if req.pre_order.serve_at.is_some() || !req.pre_order.courses.is_empty() {
+      error!("Pre-order cannot have serve_at nor courses");
+      return Err(Error::BadParam("pre_order".to_string()))
+    }
+    Ok(())
+  }
+
+  // reservation exists, but is not in the expected status
+  fn reservation_error_of(book: &ReservationBook, id: &str) -> Error {
+    if book.get(id).is_some() {
+      warn!("Reservation {} is not booked", id);
+      Error::BadReservationState
+    } else {
+      warn!("Reservation {} not found", id);
+      Error::ReservationNotFound
+    }
+  }
+
+  pub fn create_reservation(&self, req: &ReservationParam) -> Result<Reservation, Error> {
+    self.validate_reservation(req)?;
+    let reservation = Reservation {
+      id: Uuid::new_v4().to_string(),
+      table_id: req.table_id,
+      name: req.name.clone(),
+      party_size: req.party_size,
+      at: req.at,
+      serve_at: req.serve_at.unwrap_or(req.at),
+      pre_order: req.pre_order.clone(),
+      status: ReservationStatus::Booked,
+      created_at: self.clock.now(),
+    };
+    self.reservations.lock().unwrap().add(reservation.clone());
+    info!("Created reservation {:?}", reservation);
+    Ok(reservation)
+  }
+
+  // only booked reservations can be modified
+  pub fn update_reservation(&self, id: &str, req: &ReservationParam) -> Result<Reservation, Error> {
+    self.validate_reservation(req)?;
+    let mut book = self.reservations.lock().unwrap();
+    let res = book.update(id, ReservationStatus::Booked, |x| {
+      x.table_id = req.table_id;
+      x.name = req.name.clone();
+      x.party_size = req.party_size;
+      x.at = req.at;
+      x.serve_at = req.serve_at.unwrap_or(req.at);
+      x.pre_order = req.pre_order.clone();
+    });
+    match res {
+      Some(x) => {
+        info!("Updated reservation {:?}", x);
+        Ok(x)
+      },
+      None => Err(OrderMgr::reservation_error_of(&book, id)),
+    }
+  }
+
+  pub fn cancel_reservation(&self, id: &str) -> Result<Reservation, Error> {
+    let mut book = self.reservations.lock().unwrap();
+    match book.update(id, ReservationStatus::Booked, |x| x.status = ReservationStatus::Cancelled) {
+      Some(x) => {
+        info!("Cancelled reservation {}", id);
+        Ok(x)
+      },
+      None => Err(OrderMgr::reservation_error_of(&book, id)),
+    }
+  }
+
+  pub fn get_reservation(&self, id: &str) -> Result<Reservation, Error> {
+    match self.reservations.lock().unwrap().get(id) {
+      Some(x) => Ok(x),
+      None => Err(Error::ReservationNotFound),
+    }
+  }
+
+  pub fn get_reservations(&self) -> Result<Vec<Reservation>, Error> {
+    Ok(self.reservations.lock().unwrap().get_all())
+  }
+
+  // starts the session of the party on the reserved table or on the specified one and adds the pre-order
+  // to be served at serve_at. the table needs to be free
+  pub fn seat_reservation(&self, id: &str, table_id: Option<usize>) -> Result<Vec<Item>, Error> {
+    let reservation = {
+      let mut book = self.reservations.lock().unwrap();
+      match book.update(id, ReservationStatus::Booked, |x| x.status = ReservationStatus::Seated) {
+        Some(x) => x,
+        None => return Err(OrderMgr::reservation_error_of(&book, id)),
+      }
+    };
+    let unseat = || {
+      self.reservations.lock().unwrap().update(id, ReservationStatus::Seated, |x| x.status = ReservationStatus::Booked);
+    };
+
+    let party = Party {
+      name: reservation.name.clone(),
+      party_size: reservation.party_size,
+      seated_at: self.clock.now(),
+    };
+    let table_id = match self.open_session(Some(table_id.unwrap_or(reservation.table_id)), party) {
+      Ok(x) => x,
+      Err(e) => {
+        unseat();
+        return Err(e)
+      },
+    };
+    self.reservations.lock().unwrap().update(id, ReservationStatus::Seated, |x| x.table_id = table_id);
+    if reservation.pre_order.item_names.is_empty() {
+      info!("Seated reservation {} at table {} w/o pre-order", id, table_id);
+      return Ok(vec![])
+    }
+
+    // the party can arrive after the pre-order was supposed to be served
+    let req = AddItemsParam {
+      serve_at: Some(reservation.serve_at.max(self.clock.now())),
+      ..reservation.pre_order.clone()
+    };
+    match self.add_items(table_id, &req) {
+      Ok(items) => {
+        info!("Seated reservation {} at table {} w/ {} pre-ordered items", id, table_id, items.len());
+        Ok(items)
+      },
+      Err(e) => {
+        self.tables[table_id].lock().unwrap().set_party(None);
+        self.reservations.lock().unwrap().update(id, ReservationStatus::Seated, |x| {
+          x.status = ReservationStatus::Booked;
+          x.table_id = reservation.table_id;
+        });
+        Err(e)
+      },
+    }
+  }
+
+  // seats the party at the table, or at the free table w/ the lowest id if no table is specified.
+  // tables are locked one at a time
+  fn open_session(&self, table_id: Option<usize>, party: Party) -> Result<usize, Error> {
+    if let Some(table_id) = table_id {
+      validate_table_id!(table_id, self.num_tables);
+    }
+    if party.party_size > table_id.map(|x| self.seats_of(x)).unwrap_or_else(|| self.max_seats()) {
+      error!("Party of {} cannot be seated", party.party_size);
+      return Err(Error::BadParam("party_size".to_string()))
+    }
+    // tables too small for the party are skipped
+    let table_ids: Vec<usize> = match table_id {
+      Some(x) => vec![x],
+      None => (0..self.num_tables).filter(|x| party.party_size <= self.seats_of(*x)).collect(),
+    };
+    for table_id in table_ids {
+      let mut orders = self.tables[table_id].lock().unwrap();
+      self.remove_cooked_items_from_orders(&mut orders);
+      if orders.is_free() {
+        info!("Seated {} (party of {}) at table {}", party.name, party.party_size, table_id);
+        orders.set_party(Some(party));
+        return Ok(table_id)
+      }
+    }
+    match table_id {
+      Some(x) => warn!("Table {} is occupied", x),
+      None => warn!("No table is free"),
+    }
+    Err(Error::TableOccupied)
+  }
+
+  // current party and estimated time to become free of each table
+  pub fn get_tables(&self) -> Result<Vec<TableStatus>, Error> {
+    let now = self.clock.now();
+    let dining_secs = self.one_min_in_sec * self.dining_mins;
+    let mut statuses = vec![];
+    for (table_id, orders_mut) in self.tables[..self.num_tables].iter().enumerate() {
+      let mut orders = orders_mut.lock().unwrap();
+      self.remove_cooked_items_from_orders(&mut orders);
+      let party = orders.get_party();
+      let is_free = orders.is_free();
+      let free_at = if is_free {
+        now
+      } else {
+        floor::free_at_of(&orders.get_session(), party.as_ref(), dining_secs, now)
+      };
+      statuses.push(TableStatus { table_id, party, is_free, free_at });
+    }
+    Ok(statuses)
+  }
+
+  // waitlist entry exists, but the party is not waiting
+  fn waitlist_error_of(waitlist: &Waitlist, id: &str) -> Error {
+    if waitlist.get(id).is_some() {
+      warn!("Party {} is not waiting", id);
+      Error::BadWaitlistState
+    } else {
+      warn!("Waitlist entry {} not found", id);
+      Error::WaitlistEntryNotFound
+    }
+  }
+
+  // adds a walk-in party to the waitlist w/ the wait quoted from the tables and the parties ahead
+  pub fn join_waitlist(&self, req: &WaitlistParam) -> Result<WaitlistEntry, Error> {
+    if req.party_size == 0 || req.party_size > self.max_seats() {
+      error!("Party of {} cannot be seated", req.party_size);
+      return Err(Error::BadParam("party_size".to_string()))
+    }
+    let free_ats: Vec<i64> = self.get_tables()?.into_iter().map(|x| x.free_at).collect();
+    let now = self.clock.now();
+
+    let mut waitlist = self.waitlist.lock().unwrap();
+    let position = waitlist.get_waiting().len();
+    let entry = WaitlistEntry {
+      id: Uuid::new_v4().to_string(),
+      name: req.name.clone(),
+      party_size: req.party_size,
+      quoted_wait: floor::quote_wait(&free_ats, position, self.one_min_in_sec * self.dining_mins, now),
+      status: WaitlistStatus::Waiting,
+      table_id: None,
+      created_at: now,
+    };
+    waitlist.add(entry.clone());
+    info!("Added {:?} to the waitlist", entry);
+    Ok(entry)
+  }
+
+  pub fn get_waitlist(&self) -> Result<Vec<WaitlistEntry>, Error> {
+    Ok(self.waitlist.lock().unwrap().get_waiting())
+  }
+
+  pub fn leave_waitlist(&self, id: &str) -> Result<WaitlistEntry, Error> {
+    let mut waitlist = self.waitlist.lock().unwrap();
+    match waitlist.update(id, WaitlistStatus::Waiting, |x| x.status = WaitlistStatus::Left) {
+      Some(x) => {
+        info!("{} left the waitlist", x.name);
+        Ok(x)
+      },
+      None => Err(OrderMgr::waitlist_error_of(&waitlist, id)),
+    }
+  }
+
+  // starts the session of the waiting party on the specified table or on any free table
+  pub fn seat_waitlist(&self, id: &str, table_id: Option<usize>) -> Result<WaitlistEntry, Error> {
+    let entry = {
+      let mut waitlist = self.waitlist.lock().unwrap();
+      match waitlist.update(id, WaitlistStatus::Waiting, |x| x.status = WaitlistStatus::Seated) {
+        Some(x) => x,
+        None => return Err(OrderMgr::waitlist_error_of(&waitlist, id)),
+      }
+    };
+    let party = Party {
+      name: entry.name.clone(),
+      party_size: entry.party_size,
+      seated_at: self.clock.now(),
+    };
+    match self.open_session(table_id, party) {
+      Ok(table_id) => {
+        let entry = self.waitlist.lock().unwrap().update(id, WaitlistStatus::Seated, |x| x.table_id = Some(table_id));
+        Ok(entry.unwrap())
+      },
+      Err(e) => {
+        self.waitlist.lock().unwrap().update(id, WaitlistStatus::Seated, |x| x.status = WaitlistStatus::Waiting);
+        Err(e)
+      },
+    }
+  }
+
+  fn validate_takeout(&self, req: &TakeoutParam) -> Result<(), Error> {
+    if req.phone.is_empty() {
+      error!("Phone # of the customer is required");
+      return Err(Error::BadParam("phone".to_string()))
+    }
+    if req.kind == TakeoutKind::Delivery && req.address.as_ref().map(|x| x.is_empty()).unwrap_or(true) {
+      error!("Address is required for delivery");
+      return Err(Error::BadParam("address".to_string()))
+    }
+    if req.due_at < self.clock.now() {
+      error!("Due time {} is in the past", req.due_at);
+      return Err(Error::BadParam("due_at".to_string()))
+    }
+    // items are made ready at due_at
+    if req.items.item_names.is_empty() || req.items.serve_at.is_some() || !req.items.courses.is_empty() {
+      error!("Takeout needs items w/o serve_at nor courses");
+      return Err(Error::BadParam("items".to_string()))
+    }
+    Ok(())
+  }
+
+  // takeout order exists, but is not open
+  fn takeout_error_of(book: &TakeoutBook, id: &str) -> Error {
+    if book.get(id).is_some() {
+      warn!("Takeout {} is not open", id);
+      Error::BadTakeoutState
+    } else {
+      warn!("Takeout {} not found", id);
+      Error::TakeoutNotFound
+    }
+  }
+
+  // open takeout order w/o readiness
+  fn open_takeout_of(&self, id: &str) -> Result<Takeout, Error> {
+    let book = self.takeouts.lock().unwrap();
+    match book.get(id) {
+      Some(x) if x.status == TakeoutStatus::Open => Ok(x),
+      _ => Err(OrderMgr::takeout_error_of(&book, id)),
+    }
+  }
+
+  // fills in the readiness of an open takeout order from its items
+  fn readiness_of(&self, mut takeout: Takeout) -> Takeout {
+    if takeout.status != TakeoutStatus::Open {
+      return takeout
+    }
+    let mut orders = self.tables[takeout.slot].lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+    let expo = ExpoTable::of(takeout.slot, &orders.get_session(), self.clock.now());
+    takeout.is_ready = expo.is_complete;
+    takeout.ready_at = expo.latest_ready_at;
+    takeout
+  }
+
+  // places a takeout order on a free slot. its items are made ready at due_at
+  pub fn create_takeout(&self, req: &TakeoutParam) -> Result<Takeout, Error> {
+    self.validate_takeout(req)?;
+    let id = Uuid::new_v4().to_string();
+
+    // slots are locked one at a time
+    let slot = (self.num_tables..self.tables.len()).find(|slot| {
+      let mut orders = self.tables[*slot].lock().unwrap();
+      self.remove_cooked_items_from_orders(&mut orders);
+      let is_free = orders.get_takeout_id().is_none() && orders.get_session().is_empty() && orders.len() == 0;
+      if is_free {
+        orders.set_takeout_id(Some(id.clone()));
+      }
+      is_free
+    });
+    let slot = match slot {
+      Some(x) => x,
+      None => {
+        error!("Max # of takeout orders ({}) reached", self.max_takeouts);
+        return Err(Error::MaxTakeoutsExceeded)
+      },
+    };
+
+    let now = self.clock.now();
+    let req_items = AddItemsParam {
+      serve_at: Some(req.due_at.max(now)),
+      ..req.items.clone()
+    };
+    if let Err(e) = self.place_items(slot, &req_items, false) {
+      self.tables[slot].lock().unwrap().close_session();
+      return Err(e)
+    }
+    let takeout = Takeout {
+      id,
+      kind: req.kind,
+      name: req.name.clone(),
+      phone: req.phone.clone(),
+      address: req.address.clone(),
+      due_at: req.due_at,
+      status: TakeoutStatus::Open,
+      is_ready: false,
+      ready_at: None,
+      created_at: now,
+      slot,
+    };
+    self.takeouts.lock().unwrap().add(takeout.clone());
+    info!("Created takeout {:?}", takeout);
+    Ok(self.readiness_of(takeout))
+  }
+
+  pub fn get_takeout(&self, id: &str) -> Result<Takeout, Error> {
+    let takeout = match self.takeouts.lock().unwrap().get(id) {
+      Some(x) => x,
+      None => return Err(Error::TakeoutNotFound),
+    };
+    Ok(self.readiness_of(takeout))
+  }
+
+  // open takeout orders in the order of due_at
+  pub fn get_takeouts(&self) -> Result<Vec<Takeout>, Error> {
+    let takeouts = self.takeouts.lock().unwrap().get_open();
+    Ok(takeouts.into_iter().map(|x| self.readiness_of(x)).collect())
+  }
+
+  // returns all items of an open takeout order including cooked and removed ones
+  pub fn get_takeout_items(&self, id: &str) -> Result<Vec<Item>, Error> {
+    let takeout = self.open_takeout_of(id)?;
+    let mut orders = self.tables[takeout.slot].lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+    let items = orders.get_session();
+    info!("Got {} items of takeout {}", items.len(), id);
+    Ok(self.timed_all(items))
+  }
+
+  // customer picked up the order or the driver left w/ it. all items need to be ready
+  pub fn complete_takeout(&self, id: &str) -> Result<Takeout, Error> {
+    let takeout = self.open_takeout_of(id)?;
+    let takeout = self.readiness_of(takeout);
+    if !takeout.is_ready {
+      warn!("Takeout {} is not ready", id);
+      return Err(Error::BadTakeoutState)
+    }
+    // the slot is released only by the request that closes the order
+    let takeout = {
+      let mut book = self.takeouts.lock().unwrap();
+      let res = book.update(id, TakeoutStatus::Open, |x| {
+        x.status = TakeoutStatus::Completed;
+        x.is_ready = true;
+        x.ready_at = takeout.ready_at;
+      });
+      match res {
+        Some(x) => x,
+        None => return Err(OrderMgr::takeout_error_of(&book, id)),
+      }
+    };
+    self.tables[takeout.slot].lock().unwrap().close_session();
+    info!("Completed takeout {}", id);
+    Ok(takeout)
+  }
+
+  // cancels a takeout order and its outstanding items
+  pub fn cancel_takeout(&self, id: &str) -> Result<Takeout, Error> {
+    let takeout = {
+      let mut book = self.takeouts.lock().unwrap();
+      match book.update(id, TakeoutStatus::Open, |x| x.status = TakeoutStatus::Cancelled) {
+        Some(x) => x,
+        None => return Err(OrderMgr::takeout_error_of(&book, id)),
+      }
+    };
+
+    let now = self.clock.now();
+    let mut orders = self.tables[takeout.slot].lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+    let mut rescheduled = vec![];
+    for x in orders.get_all() {
+      orders.remove(&x.uuid, now);
+      self.inventory.lock().unwrap().restore(&self.menu, &x.name, x.quantity);
+      if let Some(station) = x.station {
+        rescheduled.push((station, self.kitchen.station(station).lock().unwrap().remove(&x.uuid, now)));
+      }
+    }
+    orders.close_session();
+    drop(orders);
+
+    for (station, refs) in rescheduled {
+      self.apply_schedule(station, refs);
+    }
+    info!("Cancelled takeout {}", id);
+    Ok(takeout)
+  }
+
+  // expediter delivered a cooked item to the table
+  pub fn serve_item(&self, table_id: usize, item_uuid: &str) -> Result<Item, Error> {
+    validate_table_id!(table_id, self.num_tables);
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+
+    match orders.get_from_session(item_uuid) {
+      Some(x) if x.is_combo => {
+        warn!("Combo {} is served by its components", item_uuid);
+        return Err(Error::BadItemState)
+      },
+      Some(x) if !x.is_removed => (),
+      _ => {
+        warn!("Item {} not found", item_uuid);
+        return Err(Error::ItemNotFound)
+      },
+    }
+    if let Some(x) = orders.serve(item_uuid, self.clock.now()) {
+      info!("Served item {} of table {}", item_uuid, table_id);
+      Ok(self.timed(x))
+    } else {
+      warn!("Item {} of table {} is not ready or has already been served", item_uuid, table_id);
+      Err(Error::BadItemState)
+    }
+  }
+
+  // readiness of the tables w/ items in the current session. tables are locked one at a time
+  pub fn get_expo(&self, sort: ExpoSort) -> Result<Vec<ExpoTable>, Error> {
+    let now = self.clock.now();
+    let mut tables: Vec<ExpoTable> = self.tables[..self.num_tables].iter().enumerate()
+      .filter_map(|(table_id, orders_mut)| {
+        let mut orders = orders_mut.lock().unwrap();
+        self.remove_cooked_items_from_orders(&mut orders);
+        let items = orders.get_session();
+        if items.iter().all(|x| x.is_removed) {
+          return None
+        }
+        Some(ExpoTable::of(table_id, &items, now))
+      })
+      .collect();
+    expo::sort(&mut tables, sort);
+    info!("Got expo view of {} tables", tables.len());
+    Ok(tables)
+  }
+
+  // items not served yet beyond their SLA across all tables, the latest first. tables are locked one at a time
+  pub fn get_late_items(&self) -> Result<Vec<LateItem>, Error> {
+    let now = self.clock.now();
+    let mut items: Vec<LateItem> = vec![];
+    for orders_mut in &self.tables[..self.num_tables] {
+      let mut orders = orders_mut.lock().unwrap();
+      self.remove_cooked_items_from_orders(&mut orders);
+      for x in orders.get_session().into_iter().filter(|x| x.served_at.is_none()) {
+        let sla_secs = self.sla_secs_of(&x);
+        if let Some(late_secs) = sla::late_secs_of(&x, sla_secs, now) {
+          items.push(LateItem { item: self.timed(x), sla_secs, late_secs });
+        }
+      }
+    }
+    items.sort_by(|a, b| (b.late_secs, &a.item.uuid).cmp(&(a.late_secs, &b.item.uuid)));
+    info!("Got {} late items", items.len());
+    Ok(items)
+  }
+
+  // returns overdue events of the station or all stations if not specified
+  pub fn get_overdue_events(&self, station: Option<Station>) -> Result<Vec<OverdueEvent>, Error> {
+    let events = self.overdue_events.get(station);
+    info!("Got {} overdue events of station {:?}", events.len(), station);
+    Ok(events)
+  }
+
+  // starts cooking the held items of the course
+  pub fn fire_course(&self, table_id: usize, course: usize) -> Result<Vec<Item>, Error> {
+    validate_table_id!(table_id, self.num_tables);
+    if course == 0 {
+      error!("Course starts from 1");
+      return Err(Error::BadParam("course".to_string()))
+    }
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+
+    let items = self.fire(&mut orders, course, self.clock.now());
+    info!("Fired {} items of course {} of table {}", items.len(), course, table_id);
+    Ok(self.timed_all(items))
+  }
+
+  pub fn void_item(&self, table_id: usize, item_uuid: &str) -> Result<Item, Error> {
+    validate_table_id!(table_id, self.num_tables);
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+
+    // components are voided together w/ their combo
+    if let Some(parent_id) = orders.get_from_session(item_uuid).and_then(|x| x.parent_id) {
+      warn!("Item {} is a component of combo {}", item_uuid, parent_id);
+      return Err(Error::BadItemState)
+    }
+    let components = orders.get_components(item_uuid);
+    let uuids = std::iter::once(item_uuid.to_string()).chain(components.into_iter().map(|x| x.uuid));
+
+    // items not cooked yet are dropped from their stations and their ingredients are put back
+    let now = self.clock.now();
+    let mut voided = vec![];
+    let mut rescheduled = vec![];
+    for (i, uuid) in uuids.enumerate() {
+      let is_outstanding = orders.get(&uuid).is_some();
+      let x = match orders.void(&uuid) {
+        Some(x) => x,
+        None if i == 0 => {
+          warn!("Item {} not found", item_uuid);
+          return Err(Error::ItemNotFound)
+        },
+        None => continue,
+      };
+      if is_outstanding {
+        self.inventory.lock().unwrap().restore(&self.menu, &x.name, x.quantity);
+        if let Some(station) = x.station {
+          rescheduled.push((station, self.kitchen.station(station).lock().unwrap().remove(&uuid, now)));
+        }
+      }
+      info!("Voided item {:?} of table {}", x, table_id);
+      voided.push(x);
+    }
+    drop(orders);
+
+    for (station, refs) in rescheduled {
+      self.apply_schedule(station, refs);
+    }
+    Ok(self.timed(voided.remove(0)))
+  }
+
+  pub fn get_bill(&self, table_id: usize) -> Result<Bill, Error> {
+    validate_table_id!(table_id, self.num_tables);
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+
+    let bill = Bill::of(table_id, &orders.get_session(), &self.bill_config);
+    info!("Got bill of {} lines for table {}", bill.lines.len(), table_id);
+
+    Ok(bill)
+  }
+
+  pub fn split_bill(&self, table_id: usize, mode: &SplitMode) -> Result<Vec<SubBill>, Error> {
+    validate_table_id!(table_id, self.num_tables);
+    // a table is split among at most its guests
+    if let SplitMode::Even { num_payers } = mode {
+      if *num_payers == 0 || *num_payers > self.seats_of(table_id) {
+        error!("Bill of table {} w/ {} seats cannot be split among {} payers", table_id, self.seats_of(table_id), num_payers);
+        return Err(Error::BadParam("num_payers".to_string()))
+      }
+    }
+    let bill = self.get_bill(table_id)?;
+    match bill.split(mode) {
+      Ok(sub_bills) => {
+        info!("Split bill of table {} into {} sub-bills", table_id, sub_bills.len());
+        Ok(sub_bills)
+      },
+      Err(param) => {
+        error!("Failed to split bill of table {} by {:?}", table_id, mode);
+        Err(Error::BadParam(param))
+      },
+    }
+  }
+
+  pub fn pay(&self, table_id: usize, req: &PayParam) -> Result<Payment, Error> {
+    validate_table_id!(table_id, self.num_tables);
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+
+    // partial payment is fine, but overpayment is not. amounts being charged are not available
+    let summary = self.payment_summary_of(table_id, &orders);
+    let balance = summary.balance - orders.get_charging();
+    if req.amount <= 0 || req.amount > balance {
+      error!("Payment of {} is not acceptable for balance {} of table {}", req.amount, balance, table_id);
+      return Err(Error::BadParam("amount".to_string()))
+    }
+    if req.tip < 0 {
+      return Err(Error::BadParam("tip".to_string()))
+    }
+
+    // the table is not locked while the payment processor is called
+    orders.add_charging(req.amount);
+    drop(orders);
+    let res = self.payment_processor.charge(req.tender, req.amount + req.tip);
+    let mut orders = orders_mut.lock().unwrap();
+    orders.add_charging(-req.amount);
+
+    match res {
+      Ok(id) => {
+        let payment = Payment {
+          id,
+          tender: req.tender,
+          amount: req.amount,
+          tip: req.tip,
+          refunded: 0,
+          created_at: self.clock.now(),
+        };
+        orders.add_payment(payment.clone());
+        info!("Added payment {:?} to table {}", payment, table_id);
+        Ok(payment)
+      },
+      Err(err) => {
+        warn!("Payment of table {} failed: {:?}", table_id, err);
+        Err(Error::PaymentDeclined)
+      },
+    }
+  }
+
+  pub fn refund(&self, table_id: usize, payment_id: &str, req: &RefundParam) -> Result<Payment, Error> {
+    validate_table_id!(table_id, self.num_tables);
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+
+    let refundable = match orders.get_payment(payment_id) {
+      Some(x) => x.amount - x.refunded,
+      None => {
+        warn!("Payment {} not found", payment_id);
+        return Err(Error::PaymentNotFound)
+      },
+    };
+    if req.amount <= 0 || req.amount > refundable {
+      error!("Refund of {} is not acceptable for payment {}", req.amount, payment_id);
+      return Err(Error::BadParam("amount".to_string()))
+    }
+
+    // the refund is recorded before the payment processor is called w/o the table locked,
+    // and put back if declined
+    let payment = orders.refund_payment(payment_id, req.amount).unwrap();
+    drop(orders);
+    let res = self.payment_processor.refund(payment_id, req.amount);
+
+    match res {
+      Ok(()) => {
+        info!("Refunded {} of payment {} of table {}", req.amount, payment_id, table_id);
+        Ok(payment)
+      },
+      Err(err) => {
+        warn!("Refund of payment {} failed: {:?}", payment_id, err);
+        orders_mut.lock().unwrap().refund_payment(payment_id, -req.amount);
+        Err(Error::PaymentDeclined)
+      },
+    }
+  }
+
+  pub fn get_payments(&self, table_id: usize) -> Result<PaymentSummary, Error> {
+    validate_table_id!(table_id, self.num_tables);
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+
+    Ok(self.payment_summary_of(table_id, &orders))
+  }
+
+  // closes the current session of the table if its bill is fully paid and no item is outstanding
+  pub fn close_session(&self, table_id: usize) -> Result<(), Error> {
+    validate_table_id!(table_id, self.num_tables);
+
+    // get orders for the table
+    let orders_mut = &self.tables[table_id];
+    let mut orders = orders_mut.lock().unwrap();
+    self.remove_cooked_items_from_orders(&mut orders);
+
+    let summary = self.payment_summary_of(table_id, &orders);
+    if !summary.is_paid {
+      warn!("Balance {} of table {} is not settled", summary.balance, table_id);
+      return Err(Error::BillNotSettled)
+    }
+    // items still being cooked would be left out of the new session
+    if orders.len() > 0 {
+      warn!("{} items of table {} are outstanding", orders.len(), table_id);
+      return Err(Error::BadItemState)
+    }
+    orders.close_session();
+    info!("Closed session of table {}", table_id);
+    Ok(())
+  }
+
+  pub fn get_receipt(&self, table_id: usize, format: PrintFormat) -> Result<Vec<u8>, Error> {
+    let bill = self.get_bill(table_id)?;
+    let lines = printout::receipt_of(&bill);
+    Ok(printout::render(&lines, format, self.print_width))
+  }
+
+  // kitchen ticket of the items being cooked in the order of ready_at followed by held items
+  pub fn get_ticket(&self, table_id: usize, format: PrintFormat) -> Result<Vec<u8>, Error> {
+    let mut items = self.get_all_items(table_id)?;
+    items.sort_by(|a, b| {
+      (a.is_held, a.ready_at, a.created_at, &a.name).cmp(&(b.is_held, b.ready_at, b.created_at, &b.name))
+    });
+    let lines = printout::ticket_of(table_id, &items);
+    Ok(printout::render(&lines, format, self.print_width))
+  }
+
+  // times relative to now are computed by the server so that clients don't depend on their own clocks
+  fn timed(&self, item: Item) -> Item {
+    item.with_times(self.clock.now(), self.one_min_in_sec)
+  }
+
+  fn timed_all(&self, items: Vec<Item>) -> Vec<Item> {
+    let now = self.clock.now();
+    items.into_iter().map(|x| x.with_times(now, self.one_min_in_sec)).collect()
+  }
+
+  fn add_kds_event(&self, item: &Item, action: KdsAction, at: i64) {
+    self.kitchen.add_event(KdsEvent {
+      uuid: item.uuid.clone(),
+      table_id: item.table_id,
+      takeout_id: item.takeout_id.clone(),
+      station: item.station,
+      action,
+      at,
+    });
+  }
+
+  fn payment_summary_of(&self, table_id: usize, orders: &TableOrders) -> PaymentSummary {
+    let bill = Bill::of(table_id, &orders.get_session(), &self.bill_config);
+    PaymentSummary::of(table_id, bill.total, orders.get_payments())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    clock::arbitrary_clock::ArbitraryClock,
+    menu::Dish,
+    modifier::{ChoiceGroup, Modifier},
+    combo::ComboSlot,
+    api::ModifiersParam,
+    query::ItemState,
+    bill::Rounding,
+    payment::{
+      payment::Tender,
+      payment_processor::PaymentError,
+    },
+  };
+  use std::{
+    thread,
+    sync::{mpsc, atomic::Ordering},
+  };
+
+  fn get_clock() -> Arc<dyn Clock> {
+    Arc::new(ArbitraryClock::new())
+  }
+
+  fn dish_of(name: &str, price: i64) -> Dish {
+    Dish {
+      name: name.to_string(),
+      price,
+      ..Default::default()
+    }
+  }
+
+  fn items_of(item_names: Vec<&str>) -> AddItemsParam {
+    AddItemsParam {
+      item_names: item_names.into_iter().map(|x| x.to_string()).collect(),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_new() {
+    let om = OrderMgr::new(1, 2, 1, get_clock());
+    assert_eq!(1, om.num_tables);
+    assert_eq!(2, om.max_table_items);
+  }
+
+  #[test]
+  fn test_add_items() {
+    let items_1 = items_of(vec!["ramen"]);
+    let items_2 = items_of(vec!["steak", "pizza"]);
+
+    let om1 = OrderMgr::new(1, 3, 1, get_clock());
+
+    // only valid table id should be 0, and 1 is a bad table id
+    assert_eq!(Err(Error::BadTableId(1)), om1.add_items(1, &items_1));
+
+    // add a single item
+    if let Ok(xs) = om1.add_items(0, &items_1) {
+      assert_eq!(xs.len(), 1);
+      let x = xs[0].clone();
+      assert_eq!(x.name, "ramen");
+      assert_eq!(x.table_id, 0);
+      assert_eq!(x.is_removed, false);
+      assert!(x.created_at < x.ready_at);
+      assert_ne!(x.uuid, "");
+
+    } else {
+      assert!(false);
+    }
+
+    // adding 2 more items should be fine work
+    if let Ok(xs) = om1.add_items(0, &items_2) {
+      assert_eq!(xs.len(), 2);
+      let x0 = xs[0].clone();
+      assert_eq!(x0.name, "steak");
+      assert_eq!(x0.table_id, 0);
+      assert_eq!(x0.is_removed, false);
+      assert!(x0.created_at < x0.ready_at);
+      assert_ne!(x0.uuid, "");
+
+      let x1 = xs[1].clone();
+      assert_eq!(x1.name, "pizza");
+      assert_eq!(x1.table_id, 0);
+      assert_eq!(x1.is_removed, false);
+      assert!(x1.created_at < x1.ready_at);
+      assert_ne!(x1.uuid, "");
+    } else {
+      assert!(false);
+    }
+
+    // table is full. adding another item should get MaxItemExceeded
+    assert_eq!(Err(Error::MaxItemsExceeded), om1.add_items(0, &items_1));
+
+    let om2 = OrderMgr::new(2, 2, 1, get_clock());
+
+    // valid table ids are 0 and 1. 2 shoulf be a bad table id
+    assert_eq!(Err(Error::BadTableId(2)), om1.add_items(2, &items_1));
+
+    // add a single item to table 0
+    if let Ok(xs) = om2.add_items(0, &items_1) {
+      assert_eq!(xs.len(), 1);
+      let x = xs[0].clone();
+      assert_eq!(x.name, "ramen");
+      assert_eq!(x.table_id, 0);
+      assert_eq!(x.is_removed, false);
+      assert!(x.created_at < x.ready_at);
+      assert_ne!(x.uuid, "");
+
+    } else {
+      assert!(false);
+    }
+
+    // add 2 items to table 1
+    if let Ok(xs) = om2.add_items(1, &items_2) {
+      assert_eq!(xs.len(), 2);
+      let x0 = xs[0].clone();
+      assert_eq!(x0.name, "steak");
+      assert_eq!(x0.table_id, 1);
+      assert_eq!(x0.is_removed, false);
+      assert!(x0.created_at < x0.ready_at);
+      assert_ne!(x0.uuid, "");
+
+      let x1 = xs[1].clone();
+      assert_eq!(x1.name, "pizza");
+      assert_eq!(x1.table_id, 1);
+      assert_eq!(x1.is_removed, false);
+      assert!(x1.created_at < x1.ready_at);
+      assert_ne!(x1.uuid, "");
+    } else {
+      assert!(false);
+    }
+  }
+
+  #[test]
+  fn test_get_all_items() {
+    let items_1 = items_of(vec!["ramen"]);
+    let items_2 = items_of(vec!["steak", "pizza"]);
+    let items_3 = items_of(vec!["apple", "cake", "bbq"]);
+
+    let om = OrderMgr::new(2, 3, 1, get_clock());
+
+    // add 1 items to table 0 and check that the 1 item is returned
+    if let Err(_) = om.add_items(0, &items_1) {
+      assert!(false);
+    }
+    match om.get_all_items(0) {
+      Err(_) => assert!(false),
+      Ok(xs) => {
+        assert_eq!(xs.len(), 1);
+        let x0 = xs[0].clone();
+        assert_eq!(x0.name, "ramen");
+        assert_eq!(x0.table_id, 0);
+        assert_eq!(x0.is_removed, false);
+        assert!(x0.created_at < x0.ready_at);
+        assert_ne!(x0.uuid, "");
+      },
+    };
+
+    // add 2 more items to table 0 and check that all items for table 0 are returned
+    if let Err(_) = om.add_items(0, &items_2) {
+      assert!(false);
+    }
+
+    match om.get_all_items(0) {
+      Err(_) => assert!(false),
+      Ok(mut xs) => {
+        assert_eq!(xs.len(), 3);
+        xs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let x0 = xs[0].clone();
+        assert_eq!(x0.name, "pizza");
+        assert_eq!(x0.table_id, 0);
+        assert_eq!(x0.is_removed, false);
+        assert!(x0.created_at < x0.ready_at);
+        assert_ne!(x0.uuid, "");
+
+        let x1 = xs[1].clone();
+        assert_eq!(x1.name, "ramen");
+        assert_eq!(x1.table_id, 0);
+        assert_eq!(x1.is_removed, false);
+        assert!(x1.created_at < x1.ready_at);
+        assert_ne!(x1.uuid, "");
+
+        let x2 = xs[2].clone();
+        assert_eq!(x2.name, "steak");
+        assert_eq!(x2.table_id, 0);
+        assert_eq!(x2.is_removed, false);
+        assert!(x2.created_at < x2.ready_at);
+        assert_ne!(x2.uuid, "");
+      },
+    };
+
+    // add items to table 1 and check that all items for table 1 are returned
+    if let Err(_) = om.add_items(1, &items_3) {
+      assert!(false);
+    }
+    match om.get_all_items(1) {
+      Err(_) => assert!(false),
+      Ok(mut xs) => {
+        assert_eq!(xs.len(), 3);
+        xs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let x0 = xs[0].clone();
+        assert_eq!(x0.name, "apple");
+        assert_eq!(x0.table_id, 1);
+        assert_eq!(x0.is_removed, false);
+        assert!(x0.created_at < x0.ready_at);
+        assert_ne!(x0.uuid, "");
+
+        let x1 = xs[1].clone();
+        assert_eq!(x1.name, "bbq");
+        assert_eq!(x1.table_id, 1);
+        assert_eq!(x1.is_removed, false);
+        assert!(x1.created_at < x1.ready_at);
+        assert_ne!(x1.uuid, "");
+
+        let x2 = xs[2].clone();
+        assert_eq!(x2.name, "cake");
+        assert_eq!(x2.table_id, 1);
+        assert_eq!(x2.is_removed, false);
+        assert!(x2.created_at < x2.ready_at);
+        assert_ne!(x2.uuid, "");
+      },
+    };
+
+    // adding items to table 1 should not affect table 0
+    match om.get_all_items(0) {
+      Err(_) => assert!(false),
+      Ok(mut xs) => {
+        assert_eq!(xs.len(), 3);
+        xs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let x0 = xs[0].clone();
+        assert_eq!(x0.name, "pizza");
+        assert_eq!(x0.table_id, 0);
+        assert_eq!(x0.is_removed, false);
+        assert!(x0.created_at < x0.ready_at);
+        assert_ne!(x0.uuid, "");
+
+        let x1 = xs[1].clone();
+        assert_eq!(x1.name, "ramen");
+        assert_eq!(x1.table_id, 0);
+        assert_eq!(x1.is_removed, false);
+        assert!(x1.created_at < x1.ready_at);
+        assert_ne!(x1.uuid, "");
+
+        let x2 = xs[2].clone();
+        assert_eq!(x2.name, "steak");
+        assert_eq!(x2.table_id, 0);
+        assert_eq!(x2.is_removed, false);
+        assert!(x2.created_at < x2.ready_at);
+        assert_ne!(x2.uuid, "");
+      },
+    };
+  }
+
+  #[test]
+  fn test_get_item() {
+    let items_2 = items_of(vec!["steak", "pizza"]);
+    let items_3 = items_of(vec!["apple", "cake", "bbq"]);
+
+    let om = OrderMgr::new(2, 3, 1, get_clock());
+
+    // no item has been added to table 0. should be ItemNotFound
+    assert_eq!(Err(Error::ItemNotFound), om.get_item(0, "bad uuid"));
+
+    let uuids3 = match om.add_items(0, &items_3) {
+      Err(_) => { assert!(false); vec![] },
+      Ok(mut xs) => { xs.sort_by(|a, b| a.name.cmp(&b.name)); xs },
+    };
+
+    // sandwich has not been added to table 0
+    assert_eq!(Err(Error::ItemNotFound), om.get_item(0, "bad uuid"));
+
+    // not in table 1 either
+    assert_eq!(Err(Error::ItemNotFound), om.get_item(1, "bad uuid"));
+
+    if let Ok(x) = om.get_item(0, &uuids3[0].uuid) {
+      assert_eq!(x.name, "apple");
+    } else {
+      assert!(false);
+    }
+    if let Ok(x) = om.get_item(0, &uuids3[1].uuid) {
+      assert_eq!(x.name, "bbq");
+    } else {
+      assert!(false);
+    }
+    if let Ok(x) = om.get_item(0, &uuids3[2].uuid) {
+      assert_eq!(x.name, "cake");
+    } else {
+      assert!(false);
+    }
+
+    let uuids2 = match om.add_items(1, &items_2) {
+      Err(_) => { assert!(false); vec![] },
+      Ok(mut xs) => { xs.sort_by(|a, b| a.name.cmp(&b.name)); xs },
+    };
+
+    if let Ok(x) = om.get_item(1, &uuids2[0].uuid) {
+      assert_eq!(x.name, "pizza");
+    } else {
+      assert!(false);
+    }
+    if let Ok(x) = om.get_item(1, &uuids2[1].uuid) {
+      assert_eq!(x.name, "steak");
+    } else {
+      assert!(false);
+    }
+  }
+
+  #[test]
+  fn test_remove_item() {
+    let items_2 = items_of(vec!["pizza", "steak"]);
+    let items_3 = items_of(vec!["apple", "cake", "bbq"]);
+
+    let om = OrderMgr::new(2, 3, 1, get_clock());
+
+    let uuids3 = match om.add_items(0, &items_3) {
+      Err(_) => { assert!(false); vec![] },
+      Ok(mut xs) => { xs.sort_by(|a, b| a.name.cmp(&b.name)); xs },
+    };
+    let uuids2 = match om.add_items(1, &items_2) {
+      Err(_) => { assert!(false); vec![] },
+      Ok(mut xs) => { xs.sort_by(|a, b| a.name.cmp(&b.name)); xs },
+    };
+
+    // cannot remove non-existing item from table 0
+    assert_eq!(Err(Error::ItemNotFound), om.remove_item(0, "bad uuid"));
+
+    // should be able to remove exsiting item from table 0
+    if let Err(_) = om.remove_item(0, &uuids3[0].uuid) {
+      assert!(false);
+    }
+    // cannot remove removed item from table 0
+    assert_eq!(Err(Error::ItemNotFound), om.remove_item(0, &uuids3[0].uuid));
+
+    // items can be removed from table 0 and 1 in arbitrary order
+    if let Err(_) = om.remove_item(0, &uuids3[2].uuid) {
+      assert!(false);
+    }
+    if let Err(_) = om.remove_item(1, &uuids2[1].uuid) {
+      assert!(false);
+    }
+    if let Err(_) = om.remove_item(1, &uuids2[0].uuid) {
+      assert!(false);
+    }
+    if let Err(_) = om.remove_item(0, &uuids3[1].uuid) {
+      assert!(false);
+    }
+  }
+
+  #[test]
+  fn test_add_items_with_seats() {
+    let om = OrderMgr::new(1, 5, 1, get_clock()).with_num_seats(2);
+
+    // seat is stored on each item
+    let mut req = items_of(vec!["ramen", "soba", "gyoza"]);
+    req.seats = vec![Some(0), Some(1), None];
+    match om.add_items(0, &req) {
+      Err(_) => assert!(false),
+      Ok(xs) => {
+        assert_eq!(Some(0), xs[0].seat);
+        assert_eq!(Some(1), xs[1].seat);
+        assert_eq!(None, xs[2].seat);
+      },
+    };
+
+    // seat out of the table's seat range should be rejected
+    req.seats = vec![Some(0), Some(2), None];
+    assert_eq!(Err(Error::BadSeat(2)), om.add_items(0, &req));
+
+    // seats must be specified for all items
+    req.seats = vec![Some(0)];
+    assert_eq!(Err(Error::BadParam("seats".to_string())), om.add_items(0, &req));
+
+    // rejected requests should not add any item
+    assert_eq!(3, om.get_all_items(0).unwrap().len());
+  }
+
+  #[test]
+  fn test_table_seats() {
+    let om = OrderMgr::new(3, 5, 1, get_clock())
+      .with_num_seats(2)
+      .with_table_seats(vec![(1, 6)].into_iter().collect());
+
+    // seats are validated against the seat count of each table
+    let mut req = items_of(vec!["ramen"]);
+    req.seats = vec![Some(5)];
+    assert!(om.add_items(1, &req).is_ok());
+    assert_eq!(Err(Error::BadSeat(5)), om.add_items(0, &req));
+    assert!(om.get_seat_items(1, 5).is_ok());
+    assert_eq!(Err(Error::BadSeat(5)), om.get_seat_items(2, 5));
+  }
+
+  #[test]
+  fn test_seat_large_party() {
+    let om = OrderMgr::new(3, 5, 1, get_clock())
+      .with_num_seats(2)
+      .with_table_seats(vec![(1, 6)].into_iter().collect());
+    if let Err(_) = om.add_items(1, &items_of(vec!["ramen"])) {
+      assert!(false);
+    }
+
+    // large party is seated only at the table that fits
+    let party = Party { name: "Sato".to_string(), party_size: 4, seated_at: 0 };
+    assert_eq!(Err(Error::TableOccupied), om.open_session(None, party.clone()));
+    assert_eq!(Err(Error::BadParam("party_size".to_string())), om.open_session(Some(0), party.clone()));
+    let party = Party { party_size: 7, ..party };
+    assert_eq!(Err(Error::BadParam("party_size".to_string())), om.open_session(None, party));
+  }
+
+  #[test]
+  fn test_get_seat_items() {
+    let om = OrderMgr::new(1, 5, 1, get_clock()).with_num_seats(2);
+
+    let mut req = items_of(vec!["ramen", "soba", "gyoza"]);
+    req.seats = vec![Some(1), None, Some(1)];
+    if let Err(_) = om.add_items(0, &req) {
+      assert!(false);
+    }
+
+    // only items of seat 1 should be returned
+    match om.get_seat_items(0, 1) {
+      Err(_) => assert!(false),
+      Ok(mut xs) => {
+        assert_eq!(2, xs.len());
+        xs.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!("gyoza", xs[0].name);
+        assert_eq!("ramen", xs[1].name);
+      },
+    };
+
+    // no item is assigned to seat 0
+    assert_eq!(Ok(vec![]), om.get_seat_items(0, 0));
+
+    // bad seat and table should be rejected
+    assert_eq!(Err(Error::BadSeat(2)), om.get_seat_items(0, 2));
+    assert_eq!(Err(Error::BadTableId(1)), om.get_seat_items(1, 0));
+  }
+
+  #[test]
+  fn test_get_bill() {
+    let menu = Menu::new(vec![
+      dish_of("ramen", 900),
+      dish_of("gyoza", 450),
+    ]);
+    let bill_config = BillConfig {
+      tax_rate_bp: 1000,
+      service_charge_bp: 0,
+      rounding: Rounding::HalfUp,
+    };
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(2, 5, 1, clock.clone())
+      .with_menu(menu)
+      .with_bill_config(bill_config);
+
+    // empty table has an empty bill
+    match om.get_bill(0) {
+      Err(_) => assert!(false),
+      Ok(bill) => assert_eq!(0, bill.total),
+    };
+
+    let xs = om.add_items(0, &items_of(vec!["ramen", "gyoza", "gyoza", "water"])).unwrap();
+    assert_eq!(900, xs[0].price);
+    assert_eq!(0, xs[3].price); // not on the menu
+
+    // cancel one gyoza and void the other after it's served
+    om.remove_item(0, &xs[1].uuid).unwrap();
+    clock.now.store(xs[2].ready_at, std::sync::atomic::Ordering::Relaxed);
+    if let Err(_) = om.void_item(0, &xs[2].uuid) {
+      assert!(false);
+    }
+    assert_eq!(Err(Error::ItemNotFound), om.void_item(0, &xs[1].uuid));
+
+    // served items stay on the bill
+    match om.get_bill(0) {
+      Err(_) => assert!(false),
+      Ok(bill) => {
+        assert_eq!(2, bill.lines.len());
+        assert_eq!("ramen", bill.lines[0].name);
+        assert_eq!("water", bill.lines[1].name);
+        assert_eq!(900, bill.subtotal);
+        assert_eq!(90, bill.tax);
+        assert_eq!(990, bill.total);
+      },
+    };
+
+    // other tables are not affected
+    assert_eq!(0, om.get_bill(1).unwrap().total);
+    assert_eq!(Err(Error::BadTableId(2)), om.get_bill(2));
+  }
+
+  #[test]
+  fn test_void_item() {
+    let option_of = |name: &str, price: i64| Modifier { name: name.to_string(), price };
+    let menu = Menu::new(vec![
+      Dish {
+        name: "steak".to_string(),
+        price: 2500,
+        station: Some(Station::Grill),
+        cook_mins: Some(15),
+        ingredients: vec![("beef".to_string(), 1)].into_iter().collect(),
+        ..Default::default()
+      },
+      Dish {
+        name: "lunch set".to_string(),
+        price: 1200,
+        components: vec![ComboSlot { name: "main".to_string(), options: vec![option_of("steak", 0)] }],
+        ..Default::default()
+      },
+    ]);
+    let stock = Stock { ingredient: "beef".to_string(), quantity: 5, low_stock: 0 };
+    let capacities = vec![(Station::Grill, 1)].into_iter().collect();
+    let om = OrderMgr::new(1, 10, 60, get_clock())
+      .with_menu(menu)
+      .with_inventory(Inventory::new(vec![stock]))
+      .with_station_capacities(capacities);
+    let xs = om.add_items(0, &items_of(vec!["steak", "steak"])).unwrap();
+    assert_eq!((900, 1800), (xs[1].start_at, xs[1].ready_at));
+
+    // steak being cooked is dropped from the station and the other one moves up
+    match om.void_item(0, &xs[0].uuid) {
+      Ok(x) => assert!(x.is_voided),
+      Err(_) => assert!(false),
+    }
+    assert_eq!(Err(Error::ItemNotFound), om.get_item(0, &xs[0].uuid));
+    let x = om.get_item(0, &xs[1].uuid).unwrap();
+    assert_eq!((0, 900), (x.start_at, x.ready_at));
+    assert_eq!(1, om.get_station_items(Station::Grill).unwrap().len());
+    assert_eq!(4, om.get_stocks().unwrap()[0].quantity);
+    assert_eq!(2500, om.get_bill(0).unwrap().subtotal);
+
+    // components cannot be voided by themselves, but are voided together w/ their combo
+    let xs = om.add_items(0, &items_of(vec!["lunch set"])).unwrap();
+    assert_eq!(Err(Error::BadItemState), om.void_item(0, &xs[1].uuid));
+    if let Err(_) = om.void_item(0, &xs[0].uuid) {
+      assert!(false);
+    }
+    assert_eq!(Err(Error::ItemNotFound), om.get_item(0, &xs[1].uuid));
+    assert_eq!(1, om.get_station_items(Station::Grill).unwrap().len());
+    assert_eq!(4, om.get_stocks().unwrap()[0].quantity);
+    assert_eq!(2500, om.get_bill(0).unwrap().subtotal);
+  }
 
   #[test]
-  fn test_add_items() {
-    let items_1 = vec![
-      "ramen".to_string(),
-    ];
-    let items_2 = vec![
-      "steak".to_string(),
-      "pizza".to_string(),
-    ];
+  fn test_split_bill() {
+    let menu = Menu::new(vec![
+      dish_of("ramen", 900),
+      dish_of("gyoza", 450),
+    ]);
+    let bill_config = BillConfig {
+      tax_rate_bp: 800,
+      service_charge_bp: 1000,
+      rounding: Rounding::HalfUp,
+    };
+    let om = OrderMgr::new(1, 5, 1, get_clock())
+      .with_num_seats(8)
+      .with_menu(menu)
+      .with_bill_config(bill_config);
 
-    let om1 = OrderMgr::new(1, 3, 1, get_clock());
+    let mut req = items_of(vec!["ramen", "gyoza", "gyoza"]);
+    req.seats = vec![Some(0), Some(1), None];
+    if let Err(_) = om.add_items(0, &req) {
+      assert!(false);
+    }
+    let total = om.get_bill(0).unwrap().total;
 
-    // only valid table id should be 0, and 1 is a bad table id
-    assert_eq!(Err(Error::BadTableId(1)), om1.add_items(1, &items_1));
+    // sub-bills should add up to the bill total
+    match om.split_bill(0, &SplitMode::Seat) {
+      Err(_) => assert!(false),
+      Ok(xs) => {
+        assert_eq!(3, xs.len());
+        assert_eq!(total, xs.iter().map(|x| x.total).sum::<i64>());
+      },
+    };
+    match om.split_bill(0, &SplitMode::Even { num_payers: 7 }) {
+      Err(_) => assert!(false),
+      Ok(xs) => {
+        assert_eq!(7, xs.len());
+        assert_eq!(total, xs.iter().map(|x| x.total).sum::<i64>());
+      },
+    };
 
-    // add a single item
-    if let Ok(xs) = om1.add_items(0, &items_1) {
-      assert_eq!(xs.len(), 1);
-      let x = xs[0].clone();
-      assert_eq!(x.name, "ramen");
-      assert_eq!(x.table_id, 0);
-      assert_eq!(x.is_removed, false);
-      assert!(x.created_at < x.ready_at);
-      assert_ne!(x.uuid, "");
+    // bad split parameter should be rejected
+    assert_eq!(
+      Err(Error::BadParam("num_payers".to_string())),
+      om.split_bill(0, &SplitMode::Even { num_payers: 0 }),
+    );
+    assert_eq!(
+      Err(Error::BadParam("num_payers".to_string())),
+      om.split_bill(0, &SplitMode::Even { num_payers: 9 }),
+    );
+    assert_eq!(Err(Error::BadTableId(1)), om.split_bill(1, &SplitMode::Seat));
+  }
 
-    } else {
+  fn pay_of(tender: Tender, amount: i64, tip: i64) -> PayParam {
+    PayParam {
+      tender,
+      amount,
+      tip,
+    }
+  }
+
+  #[test]
+  fn test_pay() {
+    let menu = Menu::new(vec![
+      dish_of("ramen", 900),
+    ]);
+    let pp = Arc::new(MockPaymentProcessor::new());
+    let om = OrderMgr::new(1, 5, 1, get_clock())
+      .with_menu(menu)
+      .with_payment_processor(pp.clone());
+
+    if let Err(_) = om.add_items(0, &items_of(vec!["ramen", "ramen"])) {
       assert!(false);
     }
 
-    // adding 2 more items should be fine work
-    if let Ok(xs) = om1.add_items(0, &items_2) {
-      assert_eq!(xs.len(), 2);
-      let x0 = xs[0].clone();
-      assert_eq!(x0.name, "steak");
-      assert_eq!(x0.table_id, 0);
-      assert_eq!(x0.is_removed, false);
-      assert!(x0.created_at < x0.ready_at);
-      assert_ne!(x0.uuid, "");
+    // partial payment w/ tip
+    if let Err(_) = om.pay(0, &pay_of(Tender::Voucher, 500, 0)) {
+      assert!(false);
+    }
+    match om.pay(0, &pay_of(Tender::Card, 1000, 200)) {
+      Err(_) => assert!(false),
+      Ok(x) => {
+        assert_eq!(1000, x.amount);
+        assert_eq!(200, x.tip);
+      },
+    };
+    match om.get_payments(0) {
+      Err(_) => assert!(false),
+      Ok(x) => {
+        assert_eq!(2, x.payments.len());
+        assert_eq!(1500, x.paid);
+        assert_eq!(200, x.tip);
+        assert_eq!(300, x.balance);
+        assert_eq!(false, x.is_paid);
+      },
+    };
 
-      let x1 = xs[1].clone();
-      assert_eq!(x1.name, "pizza");
-      assert_eq!(x1.table_id, 0);
-      assert_eq!(x1.is_removed, false);
-      assert!(x1.created_at < x1.ready_at);
-      assert_ne!(x1.uuid, "");
-    } else {
+    // overpayment and non-positive amount are not acceptable
+    assert_eq!(Err(Error::BadParam("amount".to_string())), om.pay(0, &pay_of(Tender::Cash, 301, 0)));
+    assert_eq!(Err(Error::BadParam("amount".to_string())), om.pay(0, &pay_of(Tender::Cash, 0, 0)));
+
+    // declined payment is not recorded
+    pp.declining.store(true, Ordering::Relaxed);
+    assert_eq!(Err(Error::PaymentDeclined), om.pay(0, &pay_of(Tender::Card, 300, 0)));
+    pp.declining.store(false, Ordering::Relaxed);
+
+    if let Err(_) = om.pay(0, &pay_of(Tender::Cash, 300, 0)) {
       assert!(false);
     }
+    assert_eq!(true, om.get_payments(0).unwrap().is_paid);
+  }
 
-    // table is full. adding another item should get MaxItemExceeded
-    assert_eq!(Err(Error::MaxItemsExceeded), om1.add_items(0, &items_1));
+  // blocks in charge until released so that requests can be made while charging
+  struct SlowPaymentProcessor {
+    entered: Mutex<mpsc::Sender<()>>,
+    release: Mutex<mpsc::Receiver<()>>,
+  }
 
-    let om2 = OrderMgr::new(2, 2, 1, get_clock());
+  impl PaymentProcessor for SlowPaymentProcessor {
+    fn charge(&self, _tender: Tender, _amount: i64) -> Result<String, PaymentError> {
+      self.entered.lock().unwrap().send(()).unwrap();
+      self.release.lock().unwrap().recv().unwrap();
+      Ok(Uuid::new_v4().to_string())
+    }
 
-    // valid table ids are 0 and 1. 2 shoulf be a bad table id
-    assert_eq!(Err(Error::BadTableId(2)), om1.add_items(2, &items_1));
+    fn refund(&self, _transaction_id: &str, _amount: i64) -> Result<(), PaymentError> {
+      Ok(())
+    }
+  }
 
-    // add a single item to table 0
-    if let Ok(xs) = om2.add_items(0, &items_1) {
-      assert_eq!(xs.len(), 1);
-      let x = xs[0].clone();
-      assert_eq!(x.name, "ramen");
-      assert_eq!(x.table_id, 0);
-      assert_eq!(x.is_removed, false);
-      assert!(x.created_at < x.ready_at);
-      assert_ne!(x.uuid, "");
+  #[test]
+  fn test_pay_while_charging() {
+    let (entered_tx, entered_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel();
+    let pp = Arc::new(SlowPaymentProcessor { entered: Mutex::new(entered_tx), release: Mutex::new(release_rx) });
+    let om = Arc::new(OrderMgr::new(1, 5, 1, get_clock())
+      .with_menu(Menu::new(vec![dish_of("ramen", 900)]))
+      .with_payment_processor(pp));
+    om.add_items(0, &items_of(vec!["ramen"])).unwrap();
 
-    } else {
+    let om2 = om.clone();
+    let handle = thread::spawn(move || om2.pay(0, &pay_of(Tender::Card, 600, 0)));
+    entered_rx.recv().unwrap();
+
+    // table is not locked while charging, but the amount being charged cannot be paid twice
+    assert_eq!(1, om.get_all_items(0).unwrap().len());
+    assert_eq!(Err(Error::BadParam("amount".to_string())), om.pay(0, &pay_of(Tender::Cash, 301, 0)));
+    release_tx.send(()).unwrap();
+    assert!(handle.join().unwrap().is_ok());
+    assert_eq!(300, om.get_payments(0).unwrap().balance);
+  }
+
+  #[test]
+  fn test_refund() {
+    let menu = Menu::new(vec![
+      dish_of("ramen", 900),
+    ]);
+    let om = OrderMgr::new(1, 5, 1, get_clock()).with_menu(menu);
+
+    if let Err(_) = om.add_items(0, &items_of(vec!["ramen"])) {
       assert!(false);
     }
+    let payment = om.pay(0, &pay_of(Tender::Card, 900, 100)).unwrap();
 
-    // add 2 items to table 1
-    if let Ok(xs) = om2.add_items(1, &items_2) {
-      assert_eq!(xs.len(), 2);
-      let x0 = xs[0].clone();
-      assert_eq!(x0.name, "steak");
-      assert_eq!(x0.table_id, 1);
-      assert_eq!(x0.is_removed, false);
-      assert!(x0.created_at < x0.ready_at);
-      assert_ne!(x0.uuid, "");
+    match om.refund(0, &payment.id, &RefundParam { amount: 400 }) {
+      Err(_) => assert!(false),
+      Ok(x) => assert_eq!(400, x.refunded),
+    };
+    assert_eq!(400, om.get_payments(0).unwrap().balance);
 
-      let x1 = xs[1].clone();
-      assert_eq!(x1.name, "pizza");
-      assert_eq!(x1.table_id, 1);
-      assert_eq!(x1.is_removed, false);
-      assert!(x1.created_at < x1.ready_at);
-      assert_ne!(x1.uuid, "");
-    } else {
+    // cannot refund more than paid
+    assert_eq!(Err(Error::BadParam("amount".to_string())), om.refund(0, &payment.id, &RefundParam { amount: 501 }));
+    assert_eq!(Err(Error::PaymentNotFound), om.refund(0, "foo", &RefundParam { amount: 1 }));
+  }
+
+  #[test]
+  fn test_close_session() {
+    let menu = Menu::new(vec![
+      dish_of("ramen", 900),
+    ]);
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(1, 5, 1, clock.clone()).with_menu(menu);
+
+    // table w/o items can be closed
+    assert_eq!(Ok(()), om.close_session(0));
+
+    let xs = om.add_items(0, &items_of(vec!["ramen"])).unwrap();
+
+    // cannot close until the bill is settled
+    assert_eq!(Err(Error::BillNotSettled), om.close_session(0));
+    if let Err(_) = om.pay(0, &pay_of(Tender::Cash, 900, 0)) {
+      assert!(false);
+    }
+
+    // nor while the item is being cooked
+    assert_eq!(Err(Error::BadItemState), om.close_session(0));
+    clock.now.store(xs[0].ready_at, Ordering::Relaxed);
+    assert_eq!(Ok(()), om.close_session(0));
+
+    // new session starts w/ an empty bill
+    assert_eq!(0, om.get_bill(0).unwrap().total);
+    assert_eq!(0, om.get_payments(0).unwrap().payments.len());
+  }
+
+  #[test]
+  fn test_get_receipt_and_ticket() {
+    let menu = Menu::new(vec![
+      dish_of("ramen", 900),
+    ]);
+    let om = OrderMgr::new(1, 5, 1, get_clock())
+      .with_menu(menu)
+      .with_print_width(20);
+
+    let mut req = items_of(vec!["ramen", "gyoza"]);
+    req.seats = vec![Some(2), None];
+    if let Err(_) = om.add_items(0, &req) {
+      assert!(false);
+    }
+
+    match om.get_receipt(0, PrintFormat::Text) {
+      Err(_) => assert!(false),
+      Ok(x) => {
+        let text = String::from_utf8(x).unwrap();
+        assert!(text.contains("ramen           9.00\n"));
+        assert!(text.contains("TOTAL           9.00\n"));
+      },
+    };
+
+    match om.get_ticket(0, PrintFormat::Text) {
+      Err(_) => assert!(false),
+      Ok(x) => {
+        let text = String::from_utf8(x).unwrap();
+        assert!(text.starts_with("      TABLE 0\n"));
+        assert!(text.contains("  seat 2\n"));
+        assert!(text.contains("gyoza"));
+      },
+    };
+
+    assert_eq!(Err(Error::BadTableId(1)), om.get_receipt(1, PrintFormat::EscPos));
+    assert_eq!(Err(Error::BadTableId(1)), om.get_ticket(1, PrintFormat::Html));
+  }
+
+  #[test]
+  fn test_get_station_items() {
+    let menu = Menu::new(vec![
+      Dish { name: "ramen".to_string(), price: 900, station: Some(Station::Grill), cook_mins: Some(10), ..Default::default() },
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+      Dish { name: "beer".to_string(), price: 500, station: Some(Station::Bar), cook_mins: Some(5), ..Default::default() },
+    ]);
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(2, 5, 1, clock.clone()).with_menu(menu);
+
+    let xs0 = om.add_items(0, &items_of(vec!["ramen", "beer", "water"])).unwrap();
+    assert_eq!(Some(Station::Grill), xs0[0].station);
+    assert_eq!(None, xs0[2].station);
+
+    clock.now.store(1, Ordering::Relaxed);
+    let xs1 = om.add_items(1, &items_of(vec!["steak"])).unwrap();
+
+    // grill items of both tables in the order of ready_at
+    match om.get_station_items(Station::Grill) {
+      Err(_) => assert!(false),
+      Ok(xs) => {
+        assert_eq!(2, xs.len());
+        assert!(xs[0].ready_at <= xs[1].ready_at);
+        assert!(xs.iter().all(|x| x.station == Some(Station::Grill)));
+      },
+    };
+    assert_eq!(1, om.get_station_items(Station::Bar).unwrap().len());
+    assert_eq!(0, om.get_station_items(Station::Fry).unwrap().len());
+
+    // removed item should leave the station queue
+    om.remove_item(1, &xs1[0].uuid).unwrap();
+    match om.get_station_items(Station::Grill) {
+      Err(_) => assert!(false),
+      Ok(xs) => {
+        assert_eq!(1, xs.len());
+        assert_eq!(xs0[0].uuid, xs[0].uuid);
+      },
+    };
+
+    // cooked items should leave the station queue
+    clock.now.store(xs0[0].ready_at, Ordering::Relaxed);
+    assert_eq!(0, om.get_station_items(Station::Grill).unwrap().len());
+    assert_eq!(0, om.get_station_items(Station::Bar).unwrap().len());
+  }
+
+  #[test]
+  fn test_schedule_by_station_capacity() {
+    let menu = Menu::new(vec![
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(10), ..Default::default() },
+      Dish { name: "salad".to_string(), price: 800, station: Some(Station::Cold), cook_mins: Some(5), ..Default::default() },
+      Dish { name: "water".to_string(), price: 0, station: None, cook_mins: Some(1), ..Default::default() },
+    ]);
+    let capacities = vec![(Station::Grill, 2)].into_iter().collect();
+    let om = OrderMgr::new(2, 10, 60, get_clock())
+      .with_menu(menu)
+      .with_station_capacities(capacities);
+
+    // 2 steaks are cooked in parallel and the 3rd one waits
+    let xs0 = om.add_items(0, &items_of(vec!["steak", "steak", "salad", "water"])).unwrap();
+    assert_eq!((0, 600), (xs0[0].start_at, xs0[0].ready_at));
+    assert_eq!((0, 600), (xs0[1].start_at, xs0[1].ready_at));
+    assert_eq!((0, 300), (xs0[2].start_at, xs0[2].ready_at));
+    assert_eq!((0, 60), (xs0[3].start_at, xs0[3].ready_at));
+
+    let xs1 = om.add_items(1, &items_of(vec!["steak", "steak"])).unwrap();
+    assert_eq!((600, 1200), (xs1[0].start_at, xs1[0].ready_at));
+    assert_eq!((600, 1200), (xs1[1].start_at, xs1[1].ready_at));
+
+    // cancelling a steak of table 0 lets a steak of table 1 start immediately
+    om.remove_item(0, &xs0[0].uuid).unwrap();
+    let x = om.get_item(1, &xs1[0].uuid).unwrap();
+    assert_eq!((0, 600), (x.start_at, x.ready_at));
+    let x = om.get_item(1, &xs1[1].uuid).unwrap();
+    assert_eq!((600, 1200), (x.start_at, x.ready_at));
+
+    // station queue should follow the new schedule
+    match om.get_station_items(Station::Grill) {
+      Err(_) => assert!(false),
+      Ok(xs) => {
+        assert_eq!(3, xs.len());
+        assert_eq!(xs1[1].uuid, xs[2].uuid);
+      },
+    };
+  }
+
+  #[test]
+  fn test_kds() {
+    let menu = Menu::new(vec![
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(10), ..Default::default() },
+    ]);
+    let capacities = vec![(Station::Grill, 2)].into_iter().collect();
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(1, 10, 60, clock.clone())
+      .with_menu(menu)
+      .with_station_capacities(capacities);
+
+    let xs = om.add_items(0, &items_of(vec!["steak", "steak", "steak"])).unwrap();
+    assert_eq!((600, 1200), (xs[2].start_at, xs[2].ready_at));
+
+    // chef starts the 2nd steak. the 3rd one cannot start while both burners are busy
+    clock.now.store(60, Ordering::Relaxed);
+    match om.start_item(0, &xs[1].uuid) {
+      Err(_) => assert!(false),
+      Ok(x) => {
+        assert_eq!(Some(60), x.started_at);
+        assert_eq!((0, 600), (x.start_at, x.ready_at));
+      },
+    }
+    assert_eq!(Err(Error::BadItemState), om.start_item(0, &xs[1].uuid));
+    assert_eq!(Err(Error::BadItemState), om.start_item(0, &xs[2].uuid));
+    assert_eq!(None, om.get_item(0, &xs[2].uuid).unwrap().started_at);
+
+    // bumping the 2nd steak serves it immediately and frees the burner for the 3rd one
+    clock.now.store(300, Ordering::Relaxed);
+    match om.bump_item(0, &xs[1].uuid) {
+      Err(_) => assert!(false),
+      Ok(x) => {
+        assert_eq!(Some(300), x.bumped_at);
+        assert_eq!(300, x.ready_at);
+      },
+    }
+    assert_eq!(Err(Error::ItemNotFound), om.get_item(0, &xs[1].uuid));
+    assert_eq!((300, 900), {
+      let x = om.get_item(0, &xs[2].uuid).unwrap();
+      (x.start_at, x.ready_at)
+    });
+
+    // recalling the 2nd steak puts it back to the end of the queue
+    assert_eq!(Err(Error::BadItemState), om.recall_item(0, &xs[0].uuid));
+    clock.now.store(360, Ordering::Relaxed);
+    match om.recall_item(0, &xs[1].uuid) {
+      Err(_) => assert!(false),
+      Ok(x) => {
+        assert_eq!(None, x.bumped_at);
+        assert_eq!((600, 1200), (x.start_at, x.ready_at));
+      },
+    }
+    assert_eq!(3, om.get_station_items(Station::Grill).unwrap().len());
+    assert_eq!(Err(Error::BadItemState), om.recall_item(0, &xs[1].uuid));
+    assert_eq!(Err(Error::ItemNotFound), om.bump_item(0, "foo"));
+
+    // every action is recorded w/ its time
+    let events = om.get_kds_events(Some(Station::Grill)).unwrap();
+    let actions: Vec<(KdsAction, i64)> = events.iter().map(|x| (x.action, x.at)).collect();
+    assert_eq!(vec![(KdsAction::Start, 60), (KdsAction::Bump, 300), (KdsAction::Recall, 360)], actions);
+    assert_eq!(0, om.get_kds_events(Some(Station::Bar)).unwrap().len());
+  }
+
+  #[test]
+  fn test_fire_course() {
+    let menu = Menu::new(vec![
+      Dish { name: "salad".to_string(), price: 800, station: Some(Station::Cold), cook_mins: Some(5), ..Default::default() },
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+    ]);
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(1, 10, 60, clock.clone()).with_menu(menu);
+
+    let req = AddItemsParam {
+      item_names: vec!["salad".to_string(), "steak".to_string()],
+      courses: vec![Some(1), Some(2)],
+      ..Default::default()
+    };
+    let xs = om.add_items(0, &req).unwrap();
+    assert!(!xs[0].is_held);
+    assert_eq!(300, xs[0].ready_at);
+    assert!(xs[1].is_held);
+
+    // held steak is not on the grill and cannot be started on KDS
+    assert_eq!(0, om.get_station_items(Station::Grill).unwrap().len());
+    assert_eq!(Err(Error::BadItemState), om.start_item(0, &xs[1].uuid));
+
+    // held steak stays even after salad is served
+    clock.now.store(600, Ordering::Relaxed);
+    assert!(om.get_item(0, &xs[1].uuid).unwrap().is_held);
+
+    match om.fire_course(0, 2) {
+      Err(_) => assert!(false),
+      Ok(xs) => {
+        assert_eq!(1, xs.len());
+        assert_eq!((600, 1500), (xs[0].start_at, xs[0].ready_at));
+      },
+    }
+    assert_eq!(1, om.get_station_items(Station::Grill).unwrap().len());
+
+    // items of a fired course start immediately
+    let req = AddItemsParam {
+      item_names: vec!["steak".to_string()],
+      courses: vec![Some(2)],
+      ..Default::default()
+    };
+    assert!(!om.add_items(0, &req).unwrap()[0].is_held);
+
+    assert_eq!(Err(Error::BadParam("course".to_string())), om.fire_course(0, 0));
+    let req = AddItemsParam {
+      item_names: vec!["steak".to_string()],
+      courses: vec![Some(0)],
+      ..Default::default()
+    };
+    assert_eq!(Err(Error::BadParam("courses".to_string())), om.add_items(0, &req));
+  }
+
+  #[test]
+  fn test_auto_fire() {
+    let menu = Menu::new(vec![
+      Dish { name: "salad".to_string(), price: 800, station: Some(Station::Cold), cook_mins: Some(5), ..Default::default() },
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+      Dish { name: "cake".to_string(), price: 600, station: Some(Station::Cold), cook_mins: Some(2), ..Default::default() },
+    ]);
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(1, 10, 60, clock.clone())
+      .with_menu(menu)
+      .with_auto_fire(true);
+
+    let req = AddItemsParam {
+      item_names: vec!["salad".to_string(), "steak".to_string(), "cake".to_string()],
+      courses: vec![Some(1), Some(2), Some(3)],
+      ..Default::default()
+    };
+    let xs = om.add_items(0, &req).unwrap();
+    assert!(xs[1].is_held);
+    assert!(xs[2].is_held);
+
+    // steak is fired when salad is served
+    clock.now.store(300, Ordering::Relaxed);
+    let x = om.get_item(0, &xs[1].uuid).unwrap();
+    assert_eq!((300, 1200), (x.start_at, x.ready_at));
+
+    // cake is fired when steak is found served, not back at the time steak was served
+    clock.now.store(1250, Ordering::Relaxed);
+    assert_eq!(Err(Error::ItemNotFound), om.get_item(0, &xs[1].uuid));
+    let x = om.get_item(0, &xs[2].uuid).unwrap();
+    assert_eq!((1250, 1370), (x.start_at, x.ready_at));
+
+    // course w/o preceding courses outstanding is fired immediately
+    let req = AddItemsParam {
+      item_names: vec!["cake".to_string()],
+      courses: vec![Some(4)],
+      ..Default::default()
+    };
+    clock.now.store(1400, Ordering::Relaxed);
+    let x = om.add_items(0, &req).unwrap().remove(0);
+    assert!(!x.is_held);
+    assert_eq!((1400, 1520), (x.start_at, x.ready_at));
+  }
+
+  #[test]
+  fn test_sync_ready() {
+    let menu = Menu::new(vec![
+      Dish { name: "salad".to_string(), price: 800, station: Some(Station::Cold), cook_mins: Some(5), ..Default::default() },
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+      Dish { name: "tea".to_string(), price: 200, station: None, cook_mins: Some(1), ..Default::default() },
+    ]);
+    let capacities = vec![(Station::Cold, 1)].into_iter().collect();
+    let om = OrderMgr::new(2, 10, 60, get_clock())
+      .with_menu(menu)
+      .with_station_capacities(capacities);
+
+    // table 1 orders salad first
+    let x1 = om.add_items(1, &items_of(vec!["salad"])).unwrap().remove(0);
+    assert_eq!((0, 300), (x1.start_at, x1.ready_at));
+
+    // salad and tea of table 0 are delayed to be ready w/ steak
+    let req = AddItemsParam {
+      item_names: vec!["steak".to_string(), "salad".to_string(), "tea".to_string()],
+      sync: Some(true),
+      ..Default::default()
+    };
+    let xs = om.add_items(0, &req).unwrap();
+    assert_eq!((0, 900), (xs[0].start_at, xs[0].ready_at));
+    assert_eq!((600, 900), (xs[1].start_at, xs[1].ready_at));
+    assert_eq!((840, 900), (xs[2].start_at, xs[2].ready_at));
+
+    // salad of the other table is cooked before the delayed salad starts
+    let x2 = om.add_items(1, &items_of(vec!["salad"])).unwrap().remove(0);
+    assert_eq!((300, 600), (x2.start_at, x2.ready_at));
+
+    // table setting applies when the request doesn't specify
+    if let Err(_) = om.set_sync_ready(0, &SyncParam { enabled: true }) {
+      assert!(false);
+    }
+    let xs = om.add_items(0, &items_of(vec!["steak", "tea"])).unwrap();
+    assert_eq!(xs[0].ready_at, xs[1].ready_at);
+    let req = AddItemsParam {
+      item_names: vec!["steak".to_string(), "tea".to_string()],
+      sync: Some(false),
+      ..Default::default()
+    };
+    let xs = om.add_items(0, &req).unwrap();
+    assert_ne!(xs[0].ready_at, xs[1].ready_at);
+    assert_eq!(Err(Error::BadTableId(2)), om.set_sync_ready(2, &SyncParam { enabled: true }));
+  }
+
+  #[test]
+  fn test_expo() {
+    let menu = Menu::new(vec![
+      Dish { name: "salad".to_string(), price: 800, station: Some(Station::Cold), cook_mins: Some(5), ..Default::default() },
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+    ]);
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(3, 10, 60, clock.clone()).with_menu(menu);
+
+    let xs0 = om.add_items(0, &items_of(vec!["salad", "steak"])).unwrap();
+    om.add_items(1, &items_of(vec!["salad"])).unwrap();
+
+    // cooking item cannot be served
+    assert_eq!(Err(Error::BadItemState), om.serve_item(0, &xs0[0].uuid));
+
+    clock.now.store(300, Ordering::Relaxed);
+    match om.serve_item(0, &xs0[0].uuid) {
+      Err(_) => assert!(false),
+      Ok(x) => assert_eq!(Some(300), x.served_at),
+    }
+    assert_eq!(Err(Error::BadItemState), om.serve_item(0, &xs0[0].uuid));
+    assert_eq!(Err(Error::ItemNotFound), om.serve_item(0, "foo"));
+
+    // table 1 is complete before table 0 and table 2 w/o items is not listed
+    match om.get_expo(ExpoSort::ReadyAt) {
+      Err(_) => assert!(false),
+      Ok(xs) => {
+        assert_eq!(vec![1, 0], xs.iter().map(|x| x.table_id).collect::<Vec<usize>>());
+        assert!(xs[0].is_complete);
+        assert_eq!((1, 0, 1), (xs[1].num_cooking, xs[1].num_ready, xs[1].num_served));
+        assert_eq!(Some(900), xs[1].latest_ready_at);
+        assert!(!xs[1].is_complete);
+      },
+    }
+    assert_eq!(0, om.get_expo(ExpoSort::TableId).unwrap()[0].table_id);
+  }
+
+  #[test]
+  fn test_quote_items() {
+    let menu = Menu::new(vec![
+      Dish { name: "salad".to_string(), price: 800, station: Some(Station::Cold), cook_mins: Some(5), ..Default::default() },
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+    ]);
+    let capacities = vec![(Station::Grill, 1)].into_iter().collect();
+    let om = OrderMgr::new(2, 2, 60, get_clock())
+      .with_menu(menu)
+      .with_station_capacities(capacities);
+    om.add_items(1, &items_of(vec!["steak"])).unwrap();
+
+    // steak waits for the steak of table 1
+    let req = AddItemsParam {
+      item_names: vec!["steak".to_string(), "salad".to_string()],
+      sync: Some(true),
+      ..Default::default()
+    };
+    let quotes = match om.quote_items(0, &req) {
+      Err(_) => { assert!(false); vec![] },
+      Ok(xs) => xs,
+    };
+    assert_eq!((900, 1800), (quotes[0].start_at, quotes[0].ready_at));
+    assert_eq!((1500, 1800), (quotes[1].start_at, quotes[1].ready_at));
+
+    // quoting doesn't change the table nor the kitchen
+    assert_eq!(0, om.get_all_items(0).unwrap().len());
+    assert_eq!(1, om.get_station_items(Station::Grill).unwrap().len());
+    assert_eq!(quotes, om.quote_items(0, &req).unwrap());
+
+    // items are added as quoted
+    let xs = om.add_items(0, &req).unwrap();
+    assert_eq!(
+      quotes.iter().map(|x| (x.start_at, x.ready_at)).collect::<Vec<(i64, i64)>>(),
+      xs.iter().map(|x| (x.start_at, x.ready_at)).collect::<Vec<(i64, i64)>>(),
+    );
+
+    // quote fails in the same way as add
+    assert_eq!(Err(Error::MaxItemsExceeded), om.quote_items(0, &req));
+    assert_eq!(Err(Error::BadTableId(2)), om.quote_items(2, &req));
+
+    // dishes w/o cook time take the default time
+    let om = OrderMgr::new(1, 10, 60, get_clock()).with_default_cook_mins(7);
+    let quote = om.quote_items(0, &items_of(vec!["soup"])).unwrap().remove(0);
+    assert_eq!((0, 420), (quote.start_at, quote.ready_at));
+    assert_eq!(420, om.add_items(0, &items_of(vec!["soup"])).unwrap()[0].ready_at);
+  }
+
+  #[test]
+  fn test_quote_due_items() {
+    let menu = Menu::new(vec![
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+    ]);
+    let capacities = vec![(Station::Grill, 1)].into_iter().collect();
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(1, 10, 60, clock.clone())
+      .with_menu(menu)
+      .with_station_capacities(capacities);
+    let req = AddItemsParam {
+      item_names: vec!["steak".to_string()],
+      serve_at: Some(3600),
+      ..Default::default()
+    };
+    let held = om.add_items(0, &req).unwrap().remove(0);
+
+    // quote takes the steak due to start into account w/o firing it
+    clock.now.store(2700, Ordering::Relaxed);
+    let quote = om.quote_items(0, &items_of(vec!["steak"])).unwrap().remove(0);
+    assert_eq!((3600, 4500), (quote.start_at, quote.ready_at));
+    assert!(om.tables[0].lock().unwrap().get(&held.uuid).unwrap().is_held);
+    assert_eq!(0, om.kitchen.station(Station::Grill).lock().unwrap().get_all().len());
+
+    // steak is fired by the next request and the item is added as quoted
+    let items = om.get_table_items(0, &ItemFilter::default(), ItemSort::ReadyAt, 0, None).unwrap();
+    assert_eq!(1, items.len());
+    assert!(!items[0].is_held);
+    assert_eq!((2700, 3600), (items[0].start_at, items[0].ready_at));
+    let x = om.add_items(0, &items_of(vec!["steak"])).unwrap().remove(0);
+    assert_eq!((quote.start_at, quote.ready_at), (x.start_at, x.ready_at));
+  }
+
+  #[test]
+  fn test_inventory() {
+    let menu = Menu::new(vec![
+      Dish {
+        name: "ramen".to_string(),
+        price: 900,
+        ingredients: vec![("noodle".to_string(), 1)].into_iter().collect(),
+        ..Default::default()
+      },
+    ]);
+    let stock = Stock { ingredient: "noodle".to_string(), quantity: 2, low_stock: 1 };
+    let om = OrderMgr::new(1, 10, 1, get_clock())
+      .with_menu(menu)
+      .with_inventory(Inventory::new(vec![stock]));
+
+    // 3 bowls cannot be cooked w/ 2 packs of noodle. quote follows the stock as well
+    let req = items_of(vec!["ramen", "ramen", "ramen"]);
+    assert_eq!(Err(Error::OutOfStock("ramen".to_string())), om.quote_items(0, &req));
+    assert_eq!(Err(Error::OutOfStock("ramen".to_string())), om.add_items(0, &req));
+
+    let xs = om.add_items(0, &items_of(vec!["ramen", "ramen"])).unwrap();
+    assert_eq!(0, om.get_stocks().unwrap()[0].quantity);
+    assert_eq!(1, om.get_low_stocks().unwrap().len());
+    assert_eq!(vec!["ramen".to_string()], om.get_86_board().unwrap().out_of_stock);
+
+    // cancelling restores the stock
+    om.remove_item(0, &xs[0].uuid).unwrap();
+    assert_eq!(1, om.get_stocks().unwrap()[0].quantity);
+
+    // manual 86 overrides the stock
+    om.eighty_six("ramen").unwrap();
+    assert_eq!(Err(Error::OutOfStock("ramen".to_string())), om.add_items(0, &items_of(vec!["ramen"])));
+    om.un_eighty_six("ramen").unwrap();
+    assert_eq!(Err(Error::ItemNotFound), om.un_eighty_six("ramen"));
+    assert_eq!(Err(Error::BadParam("dish".to_string())), om.eighty_six("noodle"));
+    assert_eq!(Err(Error::BadParam("dish".to_string())), om.eighty_six("foo"));
+    assert!(om.get_86_board().unwrap().manual.is_empty());
+    assert!(om.add_items(0, &items_of(vec!["ramen"])).is_ok());
+
+    // restocking
+    let req = StockParam { quantity: 10, low_stock: 2 };
+    assert_eq!(10, om.set_stock("noodle", &req).unwrap().quantity);
+    assert_eq!(0, om.get_low_stocks().unwrap().len());
+    assert_eq!(Err(Error::BadParam("quantity".to_string())), om.set_stock("noodle", &StockParam { quantity: -1, low_stock: 0 }));
+  }
+
+  #[test]
+  fn test_allergens() {
+    let menu = Menu::new(vec![
+      Dish {
+        name: "gyoza".to_string(),
+        price: 450,
+        allergens: vec!["gluten".to_string()],
+        ..Default::default()
+      },
+      Dish {
+        name: "edamame".to_string(),
+        price: 350,
+        diets: vec!["vegan".to_string()],
+        ..Default::default()
+      },
+    ]);
+    let om = OrderMgr::new(1, 10, 1, get_clock()).with_menu(menu);
+
+    let r = Restriction { seat: Some(1), allergies: vec!["gluten".to_string()], ..Default::default() };
+    if let Err(_) = om.set_restriction(0, &r) {
+      assert!(false);
+    }
+    assert_eq!(Err(Error::BadSeat(4)), om.set_restriction(0, &Restriction { seat: Some(4), ..Default::default() }));
+    assert_eq!(vec![r.clone()], om.get_restrictions(0).unwrap());
+
+    // gyoza for seat 0 is fine, but shared gyoza is flagged
+    let req = AddItemsParam {
+      item_names: vec!["gyoza".to_string(), "gyoza".to_string(), "edamame".to_string()],
+      seats: vec![Some(0), None, Some(1)],
+      ..Default::default()
+    };
+    let xs = om.add_items(0, &req).unwrap();
+    assert_eq!(Vec::<String>::new(), xs[0].allergy_flags);
+    assert_eq!(vec!["gluten".to_string()], xs[1].allergy_flags);
+    assert_eq!(Vec::<String>::new(), xs[2].allergy_flags);
+
+    // outstanding items are flagged again when a restriction is recorded afterwards
+    let r0 = Restriction { seat: Some(0), allergies: vec!["gluten".to_string()], ..Default::default() };
+    if let Err(_) = om.set_restriction(0, &r0) {
+      assert!(false);
+    }
+    assert_eq!(vec!["gluten".to_string()], om.get_item(0, &xs[0].uuid).unwrap().allergy_flags);
+    if let Err(_) = om.set_restriction(0, &Restriction { seat: Some(1), ..Default::default() }) {
+      assert!(false);
+    }
+    assert_eq!(vec!["gluten".to_string()], om.get_item(0, &xs[1].uuid).unwrap().allergy_flags);
+    if let Err(_) = om.set_restriction(0, &Restriction { seat: Some(0), ..Default::default() }) {
+      assert!(false);
+    }
+    assert_eq!(Vec::<String>::new(), om.get_item(0, &xs[0].uuid).unwrap().allergy_flags);
+    assert_eq!(Vec::<String>::new(), om.get_item(0, &xs[1].uuid).unwrap().allergy_flags);
+    if let Err(_) = om.set_restriction(0, &r) {
       assert!(false);
     }
+
+    // strict mode rejects the whole request
+    let om = om.with_allergen_strictness(Strictness::Reject);
+    assert_eq!(Err(Error::AllergenConflict("gyoza".to_string())), om.add_items(0, &req));
+    assert_eq!(Err(Error::AllergenConflict("gyoza".to_string())), om.quote_items(0, &req));
   }
 
   #[test]
-  fn test_get_all_items() {
-    let items_1 = vec![
-      "ramen".to_string(),
-    ];
-    let items_2 = vec![
-      "steak".to_string(),
-      "pizza".to_string(),
-    ];
-    let items_3 = vec![
-      "apple".to_string(),
-      "cake".to_string(),
-      "bbq".to_string(),
-    ];
-
-    let om = OrderMgr::new(2, 3, 1, get_clock());
+  fn test_modifiers() {
+    let menu = Menu::new(vec![
+      Dish {
+        name: "ramen".to_string(),
+        price: 900,
+        choices: vec![ChoiceGroup {
+          name: "soup".to_string(),
+          options: vec![
+            Modifier { name: "shoyu".to_string(), price: 0 },
+            Modifier { name: "miso".to_string(), price: 50 },
+          ],
+        }],
+        add_ons: vec![Modifier { name: "egg".to_string(), price: 100 }],
+        ..Default::default()
+      },
+    ]);
+    let om = OrderMgr::new(1, 10, 1, get_clock()).with_menu(menu).with_max_note_len(10);
+    let modifiers_of = |soup: &str, add_ons: Vec<&str>, note: Option<&str>| ModifiersParam {
+      choices: vec![("soup".to_string(), soup.to_string())].into_iter().collect(),
+      add_ons: add_ons.into_iter().map(|x| x.to_string()).collect(),
+      note: note.map(|x| x.to_string()),
+    };
 
-    // add 1 items to table 0 and check that the 1 item is returned
-    if let Err(_) = om.add_items(0, &items_1) {
-      assert!(false);
-    }
-    match om.get_all_items(0) {
+    let req = AddItemsParam {
+      item_names: vec!["ramen".to_string(), "ramen".to_string()],
+      modifiers: vec![modifiers_of("miso", vec!["egg"], Some("no onions")), modifiers_of("shoyu", vec![], None)],
+      ..Default::default()
+    };
+    match om.add_items(0, &req) {
       Err(_) => assert!(false),
       Ok(xs) => {
-        assert_eq!(xs.len(), 1);
-        let x0 = xs[0].clone();
-        assert_eq!(x0.name, "ramen");
-        assert_eq!(x0.table_id, 0);
-        assert_eq!(x0.is_removed, false);
-        assert!(x0.created_at < x0.ready_at);
-        assert_ne!(x0.uuid, "");
+        assert_eq!(1050, xs[0].price);
+        assert_eq!(vec!["miso", "egg"], xs[0].modifiers.iter().map(|x| x.name.as_str()).collect::<Vec<&str>>());
+        assert_eq!(Some("no onions".to_string()), xs[0].note);
+        assert_eq!(900, xs[1].price);
+        assert_eq!(None, xs[1].note);
       },
-    };
-
-    // add 2 more items to table 0 and check that all items for table 0 are returned
-    if let Err(_) = om.add_items(0, &items_2) {
-      assert!(false);
     }
+    assert_eq!(1950, om.get_bill(0).unwrap().subtotal);
 
-    match om.get_all_items(0) {
-      Err(_) => assert!(false),
-      Ok(mut xs) => {
-        assert_eq!(xs.len(), 3);
-        xs.sort_by(|a, b| a.name.cmp(&b.name));
+    // soup is required
+    let req = AddItemsParam {
+      item_names: vec!["ramen".to_string()],
+      ..Default::default()
+    };
+    assert_eq!(Err(Error::BadParam("modifiers".to_string())), om.add_items(0, &req));
 
-        let x0 = xs[0].clone();
-        assert_eq!(x0.name, "pizza");
-        assert_eq!(x0.table_id, 0);
-        assert_eq!(x0.is_removed, false);
-        assert!(x0.created_at < x0.ready_at);
-        assert_ne!(x0.uuid, "");
+    // note is too long
+    let req = AddItemsParam {
+      item_names: vec!["ramen".to_string()],
+      modifiers: vec![modifiers_of("miso", vec![], Some("no onions please"))],
+      ..Default::default()
+    };
+    assert_eq!(Err(Error::BadParam("modifiers".to_string())), om.add_items(0, &req));
 
-        let x1 = xs[1].clone();
-        assert_eq!(x1.name, "ramen");
-        assert_eq!(x1.table_id, 0);
-        assert_eq!(x1.is_removed, false);
-        assert!(x1.created_at < x1.ready_at);
-        assert_ne!(x1.uuid, "");
+    // # of modifiers differs from # of items
+    let req = AddItemsParam {
+      item_names: vec!["ramen".to_string(), "beer".to_string()],
+      modifiers: vec![modifiers_of("miso", vec![], None)],
+      ..Default::default()
+    };
+    assert_eq!(Err(Error::BadParam("modifiers".to_string())), om.add_items(0, &req));
+  }
 
-        let x2 = xs[2].clone();
-        assert_eq!(x2.name, "steak");
-        assert_eq!(x2.table_id, 0);
-        assert_eq!(x2.is_removed, false);
-        assert!(x2.created_at < x2.ready_at);
-        assert_ne!(x2.uuid, "");
+  #[test]
+  fn test_quantities() {
+    let menu = Menu::new(vec![
+      Dish {
+        name: "gyoza".to_string(),
+        price: 450,
+        ingredients: vec![("dumpling".to_string(), 6)].into_iter().collect(),
+        ..Default::default()
       },
+    ]);
+    let inventory = Inventory::new(vec![Stock { ingredient: "dumpling".to_string(), quantity: 30, low_stock: 0 }]);
+    let om = OrderMgr::new(1, 5, 1, get_clock()).with_menu(menu).with_inventory(inventory);
+
+    let req = AddItemsParam {
+      item_names: vec!["gyoza".to_string(), "beer".to_string()],
+      quantities: vec![3, 2],
+      ..Default::default()
     };
+    let xs = om.add_items(0, &req).unwrap();
+    assert_eq!((3, 1350), (xs[0].quantity, xs[0].price));
+    assert_eq!(2, xs[1].quantity);
+    assert_eq!(12, om.get_stocks().unwrap()[0].quantity);
 
-    // add items to table 1 and check that all items for table 1 are returned
-    if let Err(_) = om.add_items(1, &items_3) {
+    // 2 lines are counted as 2 items
+    let req = AddItemsParam {
+      item_names: vec!["beer".to_string()],
+      ..Default::default()
+    };
+    if let Err(_) = om.add_items(0, &req) {
       assert!(false);
     }
-    match om.get_all_items(1) {
+
+    // partial cancel puts back the ingredients
+    let param = DecrementParam { by: 2 };
+    match om.decrement_item(0, &xs[0].uuid, &param) {
       Err(_) => assert!(false),
-      Ok(mut xs) => {
-        assert_eq!(xs.len(), 3);
-        xs.sort_by(|a, b| a.name.cmp(&b.name));
+      Ok(x) => assert_eq!((1, 450), (x.quantity, x.price)),
+    }
+    assert_eq!(24, om.get_stocks().unwrap()[0].quantity);
+    assert_eq!(Err(Error::BadParam("by".to_string())), om.decrement_item(0, &xs[0].uuid, &DecrementParam { by: 1 }));
+    assert_eq!(Err(Error::ItemNotFound), om.decrement_item(0, "foo", &param));
 
-        let x0 = xs[0].clone();
-        assert_eq!(x0.name, "apple");
-        assert_eq!(x0.table_id, 1);
-        assert_eq!(x0.is_removed, false);
-        assert!(x0.created_at < x0.ready_at);
-        assert_ne!(x0.uuid, "");
+    // 4 units are outstanding
+    let om = om.with_count_rule(CountRule::Units);
+    if let Err(_) = om.add_items(0, &req) {
+      assert!(false);
+    }
+    assert_eq!(Err(Error::MaxItemsExceeded), om.add_items(0, &req));
 
-        let x1 = xs[1].clone();
-        assert_eq!(x1.name, "bbq");
-        assert_eq!(x1.table_id, 1);
-        assert_eq!(x1.is_removed, false);
-        assert!(x1.created_at < x1.ready_at);
-        assert_ne!(x1.uuid, "");
+    // units of the request are counted before adding
+    let om = OrderMgr::new(1, 5, 1, get_clock()).with_count_rule(CountRule::Units);
+    let req = AddItemsParam {
+      item_names: vec!["beer".to_string()],
+      quantities: vec![6],
+      ..Default::default()
+    };
+    assert_eq!(Err(Error::MaxItemsExceeded), om.add_items(0, &req));
+    assert_eq!(0, om.get_all_items(0).unwrap().len());
+    let req = AddItemsParam { quantities: vec![5], ..req };
+    if let Err(_) = om.add_items(0, &req) {
+      assert!(false);
+    }
 
-        let x2 = xs[2].clone();
-        assert_eq!(x2.name, "cake");
-        assert_eq!(x2.table_id, 1);
-        assert_eq!(x2.is_removed, false);
-        assert!(x2.created_at < x2.ready_at);
-        assert_ne!(x2.uuid, "");
+    let req = AddItemsParam {
+      item_names: vec!["beer".to_string()],
+      quantities: vec![0],
+      ..Default::default()
+    };
+    assert_eq!(Err(Error::BadParam("quantities".to_string())), om.add_items(0, &req));
+    let req = AddItemsParam { quantities: vec![100], ..req };
+    assert_eq!(Err(Error::BadParam("quantities".to_string())), om.add_items(0, &req));
+    let req = AddItemsParam { quantities: vec![4000000000], ..req };
+    assert_eq!(Err(Error::BadParam("quantities".to_string())), om.quote_items(0, &req));
+  }
+
+  #[test]
+  fn test_combos() {
+    let option_of = |name: &str, price: i64| Modifier { name: name.to_string(), price };
+    let menu = Menu::new(vec![
+      Dish {
+        name: "lunch set".to_string(),
+        price: 1200,
+        components: vec![
+          ComboSlot { name: "main".to_string(), options: vec![option_of("steak", 0), option_of("fish", 100)] },
+          ComboSlot { name: "drink".to_string(), options: vec![option_of("tea", 0)] },
+        ],
+        ..Default::default()
       },
+      Dish { name: "steak".to_string(), price: 1500, station: Some(Station::Grill), cook_mins: Some(10), ..Default::default() },
+      Dish { name: "fish".to_string(), price: 1300, station: Some(Station::Grill), cook_mins: Some(8), ..Default::default() },
+      Dish { name: "tea".to_string(), price: 200, station: Some(Station::Bar), cook_mins: Some(1), ..Default::default() },
+    ]);
+    let om = OrderMgr::new(1, 10, 1, get_clock()).with_menu(menu);
+
+    let req = AddItemsParam {
+      item_names: vec!["lunch set".to_string()],
+      quantities: vec![2],
+      substitutions: vec![vec![("main".to_string(), "fish".to_string())].into_iter().collect()],
+      ..Default::default()
     };
+    let xs = om.add_items(0, &req).unwrap();
+    assert_eq!(vec!["lunch set", "fish", "tea"], xs.iter().map(|x| x.name.as_str()).collect::<Vec<&str>>());
+    assert!(xs[0].is_combo);
+    assert_eq!((2600, None), (xs[0].price, xs[0].station));
+    assert_eq!((0, 8, 2), (xs[1].price, xs[1].ready_at, xs[1].quantity));
+    assert_eq!(Some(xs[0].uuid.clone()), xs[2].parent_id);
 
-    // adding items to table 1 should not affect table 0
-    match om.get_all_items(0) {
+    // combo is billed and its components are cooked
+    let bill = om.get_bill(0).unwrap();
+    assert_eq!(1, bill.lines.len());
+    assert_eq!(vec!["fish".to_string(), "tea".to_string()], bill.lines[0].components);
+    assert_eq!(2, om.get_all_items(0).unwrap().len());
+
+    // components cannot be cancelled nor served by themselves
+    assert_eq!(Err(Error::BadItemState), om.remove_item(0, &xs[1].uuid));
+    assert_eq!(Err(Error::BadItemState), om.serve_item(0, &xs[0].uuid));
+
+    match om.decrement_item(0, &xs[0].uuid, &DecrementParam { by: 1 }) {
       Err(_) => assert!(false),
-      Ok(mut xs) => {
-        assert_eq!(xs.len(), 3);
-        xs.sort_by(|a, b| a.name.cmp(&b.name));
+      Ok(x) => assert_eq!((1, 1300), (x.quantity, x.price)),
+    }
+    assert_eq!(1, om.get_item(0, &xs[1].uuid).unwrap().quantity);
 
-        let x0 = xs[0].clone();
-        assert_eq!(x0.name, "pizza");
-        assert_eq!(x0.table_id, 0);
-        assert_eq!(x0.is_removed, false);
-        assert!(x0.created_at < x0.ready_at);
-        assert_ne!(x0.uuid, "");
+    // cancelling the combo cascades to its components
+    if let Err(_) = om.remove_item(0, &xs[0].uuid) {
+      assert!(false);
+    }
+    assert_eq!(0, om.get_all_items(0).unwrap().len());
+    assert_eq!(0, om.get_bill(0).unwrap().lines.len());
 
-        let x1 = xs[1].clone();
-        assert_eq!(x1.name, "ramen");
-        assert_eq!(x1.table_id, 0);
-        assert_eq!(x1.is_removed, false);
-        assert!(x1.created_at < x1.ready_at);
-        assert_ne!(x1.uuid, "");
+    // substitutions must be allowed in the slot
+    let req = AddItemsParam {
+      item_names: vec!["lunch set".to_string()],
+      substitutions: vec![vec![("main".to_string(), "tea".to_string())].into_iter().collect()],
+      ..Default::default()
+    };
+    assert_eq!(Err(Error::BadParam("substitutions".to_string())), om.add_items(0, &req));
+    let req = AddItemsParam {
+      item_names: vec!["steak".to_string()],
+      substitutions: vec![vec![("main".to_string(), "fish".to_string())].into_iter().collect()],
+      ..Default::default()
+    };
+    assert_eq!(Err(Error::BadParam("substitutions".to_string())), om.add_items(0, &req));
+  }
 
-        let x2 = xs[2].clone();
-        assert_eq!(x2.name, "steak");
-        assert_eq!(x2.table_id, 0);
-        assert_eq!(x2.is_removed, false);
-        assert!(x2.created_at < x2.ready_at);
-        assert_ne!(x2.uuid, "");
+  #[test]
+  fn test_restore_combo() {
+    let option_of = |name: &str, price: i64| Modifier { name: name.to_string(), price };
+    let menu = Menu::new(vec![
+      Dish {
+        name: "lunch set".to_string(),
+        price: 1200,
+        components: vec![
+          ComboSlot { name: "main".to_string(), options: vec![option_of("steak", 0)] },
+          ComboSlot { name: "drink".to_string(), options: vec![option_of("tea", 0)] },
+        ],
+        ..Default::default()
       },
-    };
+      Dish { name: "steak".to_string(), price: 1500, station: Some(Station::Grill), cook_mins: Some(10), ..Default::default() },
+      Dish { name: "tea".to_string(), price: 200, station: Some(Station::Bar), cook_mins: Some(1), ..Default::default() },
+    ]);
+    let om = OrderMgr::new(1, 3, 1, get_clock()).with_menu(menu);
+    let combo = om.add_items(0, &items_of(vec!["lunch set"])).unwrap().remove(0);
+    om.remove_item(0, &combo.uuid).unwrap();
+    let xs = om.add_items(0, &items_of(vec!["steak", "tea"])).unwrap();
+
+    // both components count against the limit
+    assert_eq!(Err(Error::MaxItemsExceeded), om.restore_item(0, &combo.uuid).map(|_| ()));
+
+    om.remove_item(0, &xs[1].uuid).unwrap();
+    match om.restore_item(0, &combo.uuid) {
+      Ok(x) => assert_eq!((true, false), (x.is_combo, x.is_removed)),
+      Err(_) => assert!(false),
+    }
+    assert_eq!(3, om.get_all_items(0).unwrap().len());
+    assert_eq!(2, om.get_bill(0).unwrap().lines.len());
   }
 
   #[test]
-  fn test_get_item() {
-    let items_2 = vec![
-      "steak".to_string(),
-      "pizza".to_string(),
-    ];
-    let items_3 = vec![
-      "apple".to_string(),
-      "cake".to_string(),
-      "bbq".to_string(),
-    ];
+  fn test_serve_at() {
+    let menu = Menu::new(vec![
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+      Dish { name: "salad".to_string(), price: 800, station: Some(Station::Cold), cook_mins: Some(5), ..Default::default() },
+    ]);
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(1, 10, 60, clock.clone()).with_menu(menu);
 
-    let om = OrderMgr::new(2, 3, 1, get_clock());
+    let req = AddItemsParam {
+      item_names: vec!["steak".to_string(), "salad".to_string()],
+      serve_at: Some(3600),
+      ..Default::default()
+    };
+    let xs = om.add_items(0, &req).unwrap();
+    assert!(xs[0].is_held && xs[1].is_held);
+    assert_eq!((2700, 3600), (xs[0].start_at, xs[0].ready_at));
+    assert_eq!(0, om.get_station_items(Station::Grill).unwrap().len());
 
-    // no item has been added to table 0. should be ItemNotFound
-    assert_eq!(Err(Error::ItemNotFound), om.get_item(0, "bad uuid"));
+    // steak is fired at the time to start cooking
+    clock.now.store(2700, Ordering::Relaxed);
+    let x = om.get_item(0, &xs[0].uuid).unwrap();
+    assert!(!x.is_held);
+    assert_eq!((2700, 3600), (x.start_at, x.ready_at));
+    assert!(om.get_item(0, &xs[1].uuid).unwrap().is_held);
 
-    let uuids3 = match om.add_items(0, &items_3) {
-      Err(_) => { assert!(false); vec![] },
-      Ok(mut xs) => { xs.sort_by(|a, b| a.name.cmp(&b.name)); xs },
+    // salad is fired when a request finds the time has passed, not back at that time
+    clock.now.store(3400, Ordering::Relaxed);
+    let x = om.get_item(0, &xs[1].uuid).unwrap();
+    assert!(!x.is_held);
+    assert_eq!((3400, 3700), (x.start_at, x.ready_at));
+
+    // items that cannot be ready in time are cooked immediately
+    let req = AddItemsParam {
+      item_names: vec!["steak".to_string()],
+      serve_at: Some(3500),
+      ..Default::default()
     };
+    let xs = om.add_items(0, &req).unwrap();
+    assert!(!xs[0].is_held);
+    assert_eq!(4300, xs[0].ready_at);
 
-    // sandwich has not been added to table 0
-    assert_eq!(Err(Error::ItemNotFound), om.get_item(0, "bad uuid"));
+    let req = AddItemsParam {
+      item_names: vec!["steak".to_string()],
+      serve_at: Some(2000),
+      ..Default::default()
+    };
+    assert_eq!(Err(Error::BadParam("serve_at".to_string())), om.add_items(0, &req));
+    let req = AddItemsParam {
+      item_names: vec!["steak".to_string()],
+      courses: vec![Some(2)],
+      serve_at: Some(4000),
+      ..Default::default()
+    };
+    assert_eq!(Err(Error::BadParam("serve_at".to_string())), om.add_items(0, &req));
+  }
 
-    // not in table 1 either
-    assert_eq!(Err(Error::ItemNotFound), om.get_item(1, "bad uuid"));
+  #[test]
+  fn test_reservations() {
+    let menu = Menu::new(vec![
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+    ]);
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(2, 10, 60, clock.clone()).with_menu(menu);
 
-    if let Ok(x) = om.get_item(0, &uuids3[0].uuid) {
-      assert_eq!(x.name, "apple");
-    } else {
-      assert!(false);
+    let mut req = ReservationParam {
+      table_id: 0,
+      name: "Sato".to_string(),
+      party_size: 4,
+      at: 3600,
+      serve_at: Some(4200),
+      pre_order: AddItemsParam {
+        item_names: vec!["steak".to_string()],
+        ..Default::default()
+      },
+    };
+    let r = om.create_reservation(&req).unwrap();
+    assert_eq!((ReservationStatus::Booked, 4200), (r.status, r.serve_at));
+
+    req.party_size = 5;
+    assert_eq!(Err(Error::BadParam("party_size".to_string())), om.create_reservation(&req).map(|x| x.id));
+    req.party_size = 2;
+    req.table_id = 1;
+    match om.update_reservation(&r.id, &req) {
+      Ok(x) => assert_eq!((1, 2), (x.table_id, x.party_size)),
+      Err(_) => assert!(false),
     }
-    if let Ok(x) = om.get_item(0, &uuids3[1].uuid) {
-      assert_eq!(x.name, "bbq");
-    } else {
+    assert_eq!(Err(Error::ReservationNotFound), om.update_reservation("foo", &req).map(|x| x.id));
+
+    // table is occupied by another party
+    let r2 = om.create_reservation(&req).unwrap();
+    if let Err(_) = om.add_items(1, &items_of(vec!["tea"])) {
       assert!(false);
     }
-    if let Ok(x) = om.get_item(0, &uuids3[2].uuid) {
-      assert_eq!(x.name, "cake");
-    } else {
-      assert!(false);
+    assert_eq!(Err(Error::TableOccupied), om.seat_reservation(&r.id, None));
+    assert_eq!(ReservationStatus::Booked, om.get_reservation(&r.id).unwrap().status);
+    om.void_item(1, &om.get_all_items(1).unwrap()[0].uuid).unwrap();
+    om.close_session(1).unwrap();
+
+    // pre-order is held to be served at serve_at
+    clock.now.store(3200, Ordering::Relaxed);
+    let xs = om.seat_reservation(&r.id, None).unwrap();
+    assert!(xs[0].is_held);
+    assert_eq!((3300, 4200), (xs[0].start_at, xs[0].ready_at));
+    assert_eq!(Err(Error::BadReservationState), om.seat_reservation(&r.id, None));
+    assert_eq!(Err(Error::BadReservationState), om.cancel_reservation(&r.id).map(|x| x.id));
+
+    match om.cancel_reservation(&r2.id) {
+      Ok(x) => assert_eq!(ReservationStatus::Cancelled, x.status),
+      Err(_) => assert!(false),
     }
+    let statuses: Vec<ReservationStatus> = om.get_reservations().unwrap().into_iter().map(|x| x.status).collect();
+    assert_eq!(vec![ReservationStatus::Seated, ReservationStatus::Cancelled], statuses);
+  }
 
-    let uuids2 = match om.add_items(1, &items_2) {
-      Err(_) => { assert!(false); vec![] },
-      Ok(mut xs) => { xs.sort_by(|a, b| a.name.cmp(&b.name)); xs },
-    };
+  #[test]
+  fn test_floor() {
+    let menu = Menu::new(vec![
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+    ]);
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(2, 10, 60, clock.clone()).with_menu(menu).with_dining_mins(30);
 
-    if let Ok(x) = om.get_item(1, &uuids2[0].uuid) {
-      assert_eq!(x.name, "pizza");
-    } else {
+    // party waits for nothing while a table is free
+    let w1 = om.join_waitlist(&WaitlistParam { name: "Sato".to_string(), party_size: 2 }).unwrap();
+    assert_eq!(0, w1.quoted_wait);
+    assert_eq!(Err(Error::BadParam("party_size".to_string())),
+      om.join_waitlist(&WaitlistParam { name: "Ito".to_string(), party_size: 5 }).map(|x| x.id));
+
+    // table 0 becomes free 30 mins after the steak is ready
+    if let Err(_) = om.add_items(0, &items_of(vec!["steak"])) {
       assert!(false);
     }
-    if let Ok(x) = om.get_item(1, &uuids2[1].uuid) {
-      assert_eq!(x.name, "steak");
-    } else {
+    match om.seat_waitlist(&w1.id, None) {
+      Ok(x) => assert_eq!((WaitlistStatus::Seated, Some(1)), (x.status, x.table_id)),
+      Err(_) => assert!(false),
+    }
+    assert_eq!(Err(Error::BadWaitlistState), om.seat_waitlist(&w1.id, None).map(|x| x.id));
+    assert_eq!(Err(Error::WaitlistEntryNotFound), om.leave_waitlist("foo").map(|x| x.id));
+
+    let tables = om.get_tables().unwrap();
+    assert_eq!((false, 2700), (tables[0].is_free, tables[0].free_at));
+    assert_eq!((false, 1800), (tables[1].is_free, tables[1].free_at));
+    assert_eq!(Some("Sato".to_string()), tables[1].party.as_ref().map(|x| x.name.clone()));
+
+    // each waiting party is quoted the next table to become free
+    clock.now.store(600, Ordering::Relaxed);
+    let w2 = om.join_waitlist(&WaitlistParam { name: "Ito".to_string(), party_size: 4 }).unwrap();
+    let w3 = om.join_waitlist(&WaitlistParam { name: "Kato".to_string(), party_size: 3 }).unwrap();
+    let w4 = om.join_waitlist(&WaitlistParam { name: "Abe".to_string(), party_size: 1 }).unwrap();
+    assert_eq!((1200, 2100, 3000), (w2.quoted_wait, w3.quoted_wait, w4.quoted_wait));
+
+    if let Err(_) = om.leave_waitlist(&w4.id) {
       assert!(false);
     }
+    let ids: Vec<String> = om.get_waitlist().unwrap().into_iter().map(|x| x.id).collect();
+    assert_eq!(vec![w2.id.clone(), w3.id.clone()], ids);
+
+    // no table is free until the party leaves
+    assert_eq!(Err(Error::TableOccupied), om.seat_waitlist(&w2.id, None).map(|x| x.id));
+    assert_eq!(WaitlistStatus::Waiting, om.get_waitlist().unwrap()[0].status);
+    om.close_session(1).unwrap();
+    assert_eq!(Err(Error::TableOccupied), om.seat_waitlist(&w2.id, Some(0)).map(|x| x.id));
+    match om.seat_waitlist(&w2.id, Some(1)) {
+      Ok(x) => assert_eq!(Some(1), x.table_id),
+      Err(_) => assert!(false),
+    }
   }
 
   #[test]
-  fn test_remove_item() {
-    let items_2 = vec![
-      "pizza".to_string(),
-      "steak".to_string(),
-    ];
-    let items_3 = vec![
-      "apple".to_string(),
-      "cake".to_string(),
-      "bbq".to_string(),
-    ];
+  fn test_takeouts() {
+    let menu = Menu::new(vec![
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+    ]);
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(2, 10, 60, clock.clone()).with_menu(menu).with_max_takeouts(1);
 
-    let om = OrderMgr::new(2, 3, 1, get_clock());
-
-    let uuids3 = match om.add_items(0, &items_3) {
-      Err(_) => { assert!(false); vec![] },
-      Ok(mut xs) => { xs.sort_by(|a, b| a.name.cmp(&b.name)); xs },
-    };
-    let uuids2 = match om.add_items(1, &items_2) {
-      Err(_) => { assert!(false); vec![] },
-      Ok(mut xs) => { xs.sort_by(|a, b| a.name.cmp(&b.name)); xs },
+    let mut req = TakeoutParam {
+      kind: TakeoutKind::Delivery,
+      name: "Sato".to_string(),
+      phone: "090-0000-0000".to_string(),
+      address: None,
+      due_at: 3600,
+      items: items_of(vec!["steak"]),
     };
+    assert_eq!(Err(Error::BadParam("address".to_string())), om.create_takeout(&req).map(|x| x.id));
+    req.kind = TakeoutKind::Pickup;
 
-    // cannot remove non-existing item from table 0
-    assert_eq!(Err(Error::ItemNotFound), om.remove_item(0, "bad uuid"));
+    // steak is held to be ready at due_at, so its ready_at is not fixed yet
+    let t = om.create_takeout(&req).unwrap();
+    assert_eq!((TakeoutStatus::Open, false, None), (t.status, t.is_ready, t.ready_at));
+    let items = om.get_takeout_items(&t.id).unwrap();
+    assert_eq!((Some(t.id.clone()), true, 2700), (items[0].takeout_id.clone(), items[0].is_held, items[0].start_at));
+    assert_eq!(Err(Error::MaxTakeoutsExceeded), om.create_takeout(&req).map(|x| x.id));
+    assert_eq!(Err(Error::BadTakeoutState), om.complete_takeout(&t.id).map(|x| x.id));
 
-    // should be able to remove exsiting item from table 0
-    if let Err(_) = om.remove_item(0, &uuids3[0].uuid) {
+    // takeout doesn't take a table
+    assert_eq!(2, om.get_tables().unwrap().len());
+    assert!(om.get_tables().unwrap().iter().all(|x| x.is_free));
+    assert_eq!(Err(Error::BadTableId(2)), om.add_items(2, &items_of(vec!["steak"])).map(|x| x.len()));
+
+    // steak is fired by a request at 2700 and cooked on the grill shared w/ tables
+    clock.now.store(2700, Ordering::Relaxed);
+    assert_eq!(0, om.get_station_items(Station::Grill).unwrap().len());
+    assert!(!om.get_takeout_items(&t.id).unwrap()[0].is_held);
+    let xs = om.get_station_items(Station::Grill).unwrap();
+    assert_eq!(Some(t.id.clone()), xs[0].takeout_id);
+    if let Err(_) = om.bump_takeout_item(&t.id, &xs[0].uuid) {
       assert!(false);
     }
-    // cannot remove removed item from table 0
-    assert_eq!(Err(Error::ItemNotFound), om.remove_item(0, &uuids3[0].uuid));
+    assert!(om.get_takeout(&t.id).unwrap().is_ready);
+    match om.complete_takeout(&t.id) {
+      Ok(x) => assert_eq!(TakeoutStatus::Completed, x.status),
+      Err(_) => assert!(false),
+    }
+    assert_eq!(Err(Error::BadTakeoutState), om.get_takeout_items(&t.id).map(|x| x.len()));
 
-    // items can be removed from table 0 and 1 in arbitrary order
-    if let Err(_) = om.remove_item(0, &uuids3[2].uuid) {
-      assert!(false);
+    // slot is released by completion and cancellation
+    let t2 = om.create_takeout(&req).unwrap();
+    match om.cancel_takeout(&t2.id) {
+      Ok(x) => assert_eq!(TakeoutStatus::Cancelled, x.status),
+      Err(_) => assert!(false),
     }
-    if let Err(_) = om.remove_item(1, &uuids2[1].uuid) {
+    assert_eq!(Err(Error::BadTakeoutState), om.cancel_takeout(&t2.id).map(|x| x.id));
+    assert_eq!(Err(Error::BadTakeoutState), om.cancel_takeout(&t.id).map(|x| x.id));
+    assert_eq!(Err(Error::TakeoutNotFound), om.cancel_takeout("foo").map(|x| x.id));
+    assert_eq!(Err(Error::TakeoutNotFound), om.complete_takeout("foo").map(|x| x.id));
+    assert_eq!(Err(Error::TakeoutNotFound), om.get_takeout("foo").map(|x| x.id));
+    assert_eq!(0, om.get_takeouts().unwrap().len());
+    assert!(om.create_takeout(&req).is_ok());
+  }
+
+  #[test]
+  fn test_get_items() {
+    let menu = Menu::new(vec![
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+      Dish { name: "salad".to_string(), price: 800, station: Some(Station::Cold), cook_mins: Some(5), ..Default::default() },
+    ]);
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(3, 10, 60, clock.clone()).with_menu(menu);
+    for table_id in 0..3 {
+      if let Err(_) = om.add_items(table_id, &items_of(vec!["steak", "salad"])) {
+        assert!(false);
+      }
+    }
+    let req = AddItemsParam { courses: vec![Some(2)], ..items_of(vec!["salad"]) };
+    if let Err(_) = om.add_items(1, &req) {
       assert!(false);
     }
-    if let Err(_) = om.remove_item(1, &uuids2[0].uuid) {
+
+    // pages are merged across tables by ready_at. held salad comes last and the cursor moves past it
+    let mut cursor = None;
+    let mut ready_ats = vec![];
+    loop {
+      let page = om.get_items(&ItemFilter::default(), cursor.as_deref(), Some(3)).unwrap();
+      ready_ats.extend(page.items.iter().map(|x| x.ready_at));
+      cursor = page.next_cursor;
+      if cursor.is_none() {
+        break
+      }
+    }
+    assert_eq!(vec![300, 300, 300, 900, 900, 900, 0], ready_ats);
+
+    let filter = ItemFilter { name: Some("salad".to_string()), state: Some(ItemState::Cooking), ..Default::default() };
+    assert_eq!(3, om.get_items(&filter, None, None).unwrap().items.len());
+    let filter = ItemFilter { table_id: Some(1), from: Some(1), to: Some(900), ..Default::default() };
+    let page = om.get_items(&filter, None, None).unwrap();
+    assert_eq!((1, 300), (page.items.len(), page.items[0].ready_at));
+
+    // held salad has no ready_at to be in a time window
+    let filter = ItemFilter { table_id: Some(1), from: Some(0), ..Default::default() };
+    let page = om.get_items(&filter, None, None).unwrap();
+    assert!(page.items.iter().all(|x| !x.is_held));
+    assert_eq!(2, page.items.len());
+    let filter = ItemFilter { table_id: Some(1), state: Some(ItemState::Held), ..Default::default() };
+    assert_eq!(1, om.get_items(&filter, None, None).unwrap().items.len());
+
+    // cooked items are not outstanding
+    clock.now.store(300, Ordering::Relaxed);
+    assert_eq!(4, om.get_items(&ItemFilter::default(), None, None).unwrap().items.len());
+
+    assert_eq!(Err(Error::BadParam("cursor".to_string())), om.get_items(&ItemFilter::default(), Some("foo"), None).map(|x| x.items.len()));
+    assert_eq!(Err(Error::BadParam("limit".to_string())), om.get_items(&ItemFilter::default(), None, Some(0)).map(|x| x.items.len()));
+    let filter = ItemFilter { table_id: Some(3), ..Default::default() };
+    assert_eq!(Err(Error::BadTableId(3)), om.get_items(&filter, None, None).map(|x| x.items.len()));
+  }
+
+  #[test]
+  fn test_get_table_items() {
+    let menu = Menu::new(vec![
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+      Dish { name: "salad".to_string(), price: 800, station: Some(Station::Cold), cook_mins: Some(5), ..Default::default() },
+      Dish { name: "sake".to_string(), price: 900, station: Some(Station::Bar), cook_mins: Some(1), ..Default::default() },
+    ]);
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(2, 10, 60, clock.clone()).with_menu(menu);
+    let req = AddItemsParam { seats: vec![Some(0), Some(1), Some(0)], ..items_of(vec!["steak", "salad", "sake"]) };
+    if let Err(_) = om.add_items(0, &req) {
       assert!(false);
     }
-    if let Err(_) = om.remove_item(0, &uuids3[1].uuid) {
+    clock.now.store(10, Ordering::Relaxed);
+    let req = AddItemsParam { courses: vec![Some(2)], ..items_of(vec!["salad"]) };
+    if let Err(_) = om.add_items(0, &req) {
       assert!(false);
     }
+
+    let names_of = |xs: Vec<Item>| -> Vec<String> { xs.into_iter().map(|x| x.name).collect() };
+    let all = ItemFilter::default();
+    let items = om.get_table_items(0, &all, ItemSort::ReadyAt, 0, None).unwrap();
+    assert_eq!((890, 10), (items[2].remaining_secs, items[2].elapsed_secs));
+    assert_eq!(vec!["sake", "salad", "steak", "salad"], names_of(items));
+    let items = om.get_table_items(0, &all, ItemSort::Name, 1, Some(2)).unwrap();
+    assert_eq!(vec!["salad", "salad"], names_of(items));
+    let items = om.get_table_items(0, &all, ItemSort::CreatedAt, 3, Some(2)).unwrap();
+    assert_eq!(vec![10], items.iter().map(|x| x.created_at).collect::<Vec<i64>>());
+
+    let filter = ItemFilter { name_prefix: Some("sa".to_string()), state: Some(ItemState::Cooking), ..Default::default() };
+    assert_eq!(vec!["sake", "salad"], names_of(om.get_table_items(0, &filter, ItemSort::ReadyAt, 0, None).unwrap()));
+    let filter = ItemFilter { seat: Some(0), ..Default::default() };
+    assert_eq!(vec!["sake", "steak"], names_of(om.get_table_items(0, &filter, ItemSort::ReadyAt, 0, None).unwrap()));
+
+    let filter = ItemFilter { seat: Some(8), ..Default::default() };
+    assert_eq!(Err(Error::BadSeat(8)), om.get_table_items(0, &filter, ItemSort::ReadyAt, 0, None).map(|x| x.len()));
+    assert_eq!(Err(Error::BadParam("limit".to_string())), om.get_table_items(0, &all, ItemSort::ReadyAt, 0, Some(0)).map(|x| x.len()));
+  }
+
+  #[test]
+  fn test_times() {
+    let menu = Menu::new(vec![
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+    ]);
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(2, 10, 60, clock.clone()).with_menu(menu);
+    let item = om.add_items(0, &items_of(vec!["steak"])).unwrap().remove(0);
+    assert_eq!((900, 15, 0, false), (item.remaining_secs, item.remaining_minutes, item.elapsed_secs, item.is_overdue));
+
+    // remaining minutes are rounded up
+    clock.now.store(301, Ordering::Relaxed);
+    let item = om.get_item(0, &item.uuid).unwrap();
+    assert_eq!((599, 10, 301), (item.remaining_secs, item.remaining_minutes, item.elapsed_secs));
+
+    // cooked, but not served yet
+    clock.now.store(1000, Ordering::Relaxed);
+    let item = om.serve_item(0, &item.uuid).unwrap();
+    assert_eq!((0, 0, 1000, true), (item.remaining_secs, item.remaining_minutes, item.elapsed_secs, item.is_overdue));
+  }
+
+  #[test]
+  fn test_late_items() {
+    let menu = Menu::new(vec![
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+      Dish { name: "beer".to_string(), price: 500, station: Some(Station::Bar), cook_mins: Some(1), sla_mins: Some(2), ..Default::default() },
+    ]);
+    let sla_config = SlaConfig {
+      sla_mins: 10,
+      station_sla_mins: vec![(Station::Grill, 5)].into_iter().collect(),
+    };
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(2, 10, 60, clock.clone()).with_menu(menu).with_sla_config(sla_config);
+    let steak = om.add_items(0, &items_of(vec!["steak"])).unwrap().remove(0);
+    let beer = om.add_items(1, &items_of(vec!["beer"])).unwrap().remove(0);
+
+    // within SLA
+    clock.now.store(beer.ready_at + 120, Ordering::Relaxed);
+    assert_eq!(0, om.get_late_items().unwrap().len());
+
+    // beer exceeds the SLA of the dish, steak the SLA of its station
+    clock.now.store(steak.ready_at + 301, Ordering::Relaxed);
+    let items = om.get_late_items().unwrap();
+    assert_eq!(vec![beer.uuid.clone(), steak.uuid.clone()], items.iter().map(|x| x.item.uuid.clone()).collect::<Vec<String>>());
+    assert_eq!((300, 1), (items[1].sla_secs, items[1].late_secs));
+    assert_eq!(120, items[0].sla_secs);
+
+    // served items are not late anymore, but overdue events are raised only once
+    om.serve_item(1, &beer.uuid).unwrap();
+    assert_eq!(vec![steak.uuid.clone()], om.get_late_items().unwrap().into_iter().map(|x| x.item.uuid).collect::<Vec<String>>());
+    let events = om.get_overdue_events(None).unwrap();
+    assert_eq!(2, events.len());
+    assert_eq!(steak.ready_at + 300, om.get_overdue_events(Some(Station::Grill)).unwrap()[0].at);
+  }
+
+  #[test]
+  fn test_restore_item() {
+    let menu = Menu::new(vec![
+      Dish { name: "ramen".to_string(), price: 900, station: Some(Station::Grill), cook_mins: Some(10), ..Default::default() },
+      Dish {
+        name: "udon".to_string(),
+        price: 800,
+        station: Some(Station::Grill),
+        cook_mins: Some(10),
+        ingredients: vec![("noodle".to_string(), 1)].into_iter().collect(),
+        ..Default::default()
+      },
+      Dish { name: "steak".to_string(), price: 2500, station: Some(Station::Grill), cook_mins: Some(15), ..Default::default() },
+    ]);
+    let stock = Stock { ingredient: "noodle".to_string(), quantity: 1, low_stock: 0 };
+    let clock = Arc::new(ArbitraryClock::new());
+    let om = OrderMgr::new(1, 10, 1, clock.clone())
+      .with_menu(menu)
+      .with_station_capacities(vec![(Station::Grill, 1)].into_iter().collect())
+      .with_restore_grace_mins(5)
+      .with_inventory(Inventory::new(vec![stock]));
+    let xs = om.add_items(0, &items_of(vec!["ramen", "udon", "steak"])).unwrap();
+    assert_eq!((20, 35), (xs[2].start_at, xs[2].ready_at));
+
+    // steak moves up when udon is removed and back when udon is restored
+    clock.now.store(1, Ordering::Relaxed);
+    om.remove_item(0, &xs[1].uuid).unwrap();
+    assert_eq!(25, om.get_item(0, &xs[2].uuid).unwrap().ready_at);
+    assert_eq!(1, om.get_stocks().unwrap()[0].quantity);
+
+    clock.now.store(2, Ordering::Relaxed);
+    match om.restore_item(0, &xs[1].uuid) {
+      Ok(x) => assert_eq!((false, None, 10, 20), (x.is_removed, x.removed_at, x.start_at, x.ready_at)),
+      Err(_) => assert!(false),
+    }
+    assert_eq!(35, om.get_item(0, &xs[2].uuid).unwrap().ready_at);
+    assert_eq!(0, om.get_stocks().unwrap()[0].quantity);
+    assert_eq!(Err(Error::BadItemState), om.restore_item(0, &xs[1].uuid).map(|_| ()));
+    assert_eq!(Err(Error::ItemNotFound), om.restore_item(0, "foo").map(|_| ()));
+
+    // noodle is used by another udon in the meantime
+    clock.now.store(3, Ordering::Relaxed);
+    om.remove_item(0, &xs[1].uuid).unwrap();
+    let udon = om.add_items(0, &items_of(vec!["udon"])).unwrap().remove(0);
+    assert_eq!(Err(Error::OutOfStock("udon".to_string())), om.restore_item(0, &xs[1].uuid).map(|_| ()));
+
+    // purged after the grace period
+    clock.now.store(5, Ordering::Relaxed);
+    om.remove_item(0, &udon.uuid).unwrap();
+    clock.now.store(8, Ordering::Relaxed);
+    assert_eq!(Err(Error::ItemNotFound), om.restore_item(0, &xs[1].uuid).map(|_| ()));
+    assert!(om.restore_item(0, &udon.uuid).is_ok());
   }
-}
\ No newline at end of file
+}
@@ -0,0 +1,85 @@
+use crate::payment::{
+  payment::Tender,
+  payment_processor::{PaymentProcessor, PaymentError},
+};
+use std::{
+  sync::{
+    Mutex,
+    atomic::{AtomicBool, Ordering},
+  },
+  collections::hash_map::HashMap,
+};
+use uuid::Uuid;
+
+// accepts all payments unless declining is set. keeps charged amounts in memory
+pub struct MockPaymentProcessor {
+  pub declining: AtomicBool,
+  charged: Mutex<HashMap<String, i64>>,
+}
+
+impl PaymentProcessor for MockPaymentProcessor {
+  fn charge(&self, tender: Tender, amount: i64) -> Result<String, PaymentError> {
+    if self.declining.load(Ordering::Relaxed) {
+      return Err(PaymentError::Declined)
+    }
+    let transaction_id = Uuid::new_v4().to_string();
+    self.charged.lock().unwrap().insert(transaction_id.clone(), amount);
+    info!("Charged {} by {:?} w/ transaction {}", amount, tender, transaction_id);
+    Ok(transaction_id)
+  }
+
+  fn refund(&self, transaction_id: &str, amount: i64) -> Result<(), PaymentError> {
+    if self.declining.load(Ordering::Relaxed) {
+      return Err(PaymentError::Declined)
+    }
+    let mut charged = self.charged.lock().unwrap();
+    match charged.get_mut(transaction_id) {
+      None => Err(PaymentError::TransactionNotFound),
+      Some(x) if *x < amount => Err(PaymentError::Declined),
+      Some(x) => {
+        *x -= amount;
+        info!("Refunded {} of transaction {}", amount, transaction_id);
+        Ok(())
+      },
+    }
+  }
+}
+
+impl MockPaymentProcessor {
+  pub fn new() -> MockPaymentProcessor {
+    MockPaymentProcessor {
+      declining: AtomicBool::new(false),
+      charged: Mutex::new(HashMap::new()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_charge_and_refund() {
+    let pp = MockPaymentProcessor::new();
+
+    let tx = pp.charge(Tender::Card, 1000).unwrap();
+    assert_eq!(Ok(()), pp.refund(&tx, 400));
+    assert_eq!(Ok(()), pp.refund(&tx, 600));
+
+    // cannot refund more than charged
+    assert_eq!(Err(PaymentError::Declined), pp.refund(&tx, 1));
+
+    // unknown transaction
+    assert_eq!(Err(PaymentError::TransactionNotFound), pp.refund("foo", 1));
+  }
+
+  #[test]
+  fn test_declining() {
+    let pp = MockPaymentProcessor::new();
+    let tx = pp.charge(Tender::Cash, 1000).unwrap();
+
+    pp.declining.store(true, Ordering::Relaxed);
+    assert_eq!(Err(PaymentError::Declined), pp.charge(Tender::Voucher, 1000));
+    assert_eq!(Err(PaymentError::Declined), pp.refund(&tx, 1000));
+  }
+}
@@ -0,0 +1,3 @@
+pub mod payment;
+pub mod payment_processor;
+pub mod mock_payment_processor;
@@ -0,0 +1,88 @@
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Tender {
+  Cash,
+  Card,
+  Voucher,
+}
+
+// all amounts are in minor currency unit. tip is not counted toward the bill
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Payment {
+  pub id: String,
+  pub tender: Tender,
+  pub amount: i64,
+  pub tip: i64,
+  pub refunded: i64,
+  pub created_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct PaymentSummary {
+  pub table_id: usize,
+  pub total: i64,
+  pub paid: i64,
+  pub tip: i64,
+  pub balance: i64,
+  pub is_paid: bool,
+  pub payments: Vec<Payment>,
+}
+
+impl PaymentSummary {
+  pub fn of(table_id: usize, total: i64, payments: Vec<Payment>) -> PaymentSummary {
+    let paid = payments.iter().map(|x| x.amount - x.refunded).sum();
+    let tip = payments.iter().map(|x| x.tip).sum();
+    PaymentSummary {
+      table_id,
+      total,
+      paid,
+      tip,
+      balance: total - paid,
+      is_paid: paid >= total,
+      payments,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn payment_of(amount: i64, tip: i64, refunded: i64) -> Payment {
+    Payment {
+      id: "p".to_string(),
+      tender: Tender::Card,
+      amount,
+      tip,
+      refunded,
+      created_at: 0,
+    }
+  }
+
+  #[test]
+  fn test_payment_summary_of() {
+    // no payment
+    let s = PaymentSummary::of(0, 1000, vec![]);
+    assert_eq!(0, s.paid);
+    assert_eq!(1000, s.balance);
+    assert_eq!(false, s.is_paid);
+
+    // partial payments w/ refund
+    let s = PaymentSummary::of(0, 1000, vec![payment_of(600, 100, 0), payment_of(300, 0, 100)]);
+    assert_eq!(800, s.paid);
+    assert_eq!(100, s.tip);
+    assert_eq!(200, s.balance);
+    assert_eq!(false, s.is_paid);
+
+    // fully paid
+    let s = PaymentSummary::of(0, 1000, vec![payment_of(600, 100, 0), payment_of(400, 50, 0)]);
+    assert_eq!(0, s.balance);
+    assert_eq!(150, s.tip);
+    assert_eq!(true, s.is_paid);
+
+    // nothing to pay
+    assert_eq!(true, PaymentSummary::of(0, 0, vec![]).is_paid);
+  }
+}
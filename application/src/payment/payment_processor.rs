@@ -0,0 +1,15 @@
+use crate::payment::payment::Tender;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PaymentError {
+  Declined,
+  TransactionNotFound,
+}
+
+pub trait PaymentProcessor: Send + Sync {
+  // charges amount w/ tender and returns the transaction id
+  fn charge(&self, tender: Tender, amount: i64) -> Result<String, PaymentError>;
+
+  // refunds amount of the transaction
+  fn refund(&self, transaction_id: &str, amount: i64) -> Result<(), PaymentError>;
+}
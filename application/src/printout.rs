@@ -0,0 +1,365 @@
+use crate::{
+  item::Item,
+  bill::Bill,
+};
+use std::str::FromStr;
+
+const ESC: u8 = 0x1b;
+const GS: u8 = 0x1d;
+const LF: u8 = 0x0a;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PrintFormat {
+  Text,
+  Html,
+  EscPos,
+}
+
+impl FromStr for PrintFormat {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<PrintFormat, String> {
+    match s {
+      "text" => Ok(PrintFormat::Text),
+      "html" => Ok(PrintFormat::Html),
+      "escpos" => Ok(PrintFormat::EscPos),
+      _ => Err(format!("Unknown print format {}", s)),
+    }
+  }
+}
+
+// format independent representation of a printout
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Line {
+  Title(String),        // centered and emphasized
+  Text(String),         // left aligned
+  Row(String, String),  // left and right aligned
+  Separator,
+}
+
+pub fn receipt_of(bill: &Bill) -> Vec<Line> {
+  let mut lines = vec![
+    Line::Title("RECEIPT".to_string()),
+    Line::Text(format!("Table {}", bill.table_id)),
+    Line::Separator,
+  ];
+  for x in &bill.lines {
+    lines.push(Line::Row(name_of(&x.name, x.quantity), money_of(x.price)));
+    for modifier in &x.modifiers {
+      lines.push(Line::Text(format!("  + {}", modifier)));
+    }
+    for component in &x.components {
+      lines.push(Line::Text(format!("  - {}", component)));
+    }
+  }
+  lines.append(&mut vec![
+    Line::Separator,
+    Line::Row("Subtotal".to_string(), money_of(bill.subtotal)),
+    Line::Row("Service charge".to_string(), money_of(bill.service_charge)),
+    Line::Row("Tax".to_string(), money_of(bill.tax)),
+    Line::Separator,
+    Line::Row("TOTAL".to_string(), money_of(bill.total)),
+  ]);
+  lines
+}
+
+// items are expected to be sorted in the order of cooking. held items are printed w/o time
+pub fn ticket_of(table_id: usize, items: &[Item]) -> Vec<Line> {
+  let mut lines = vec![
+    Line::Title(format!("TABLE {}", table_id)),
+    Line::Separator,
+  ];
+  for x in items {
+    let time = if x.is_held { "HOLD".to_string() } else { time_of(x.ready_at) };
+    lines.push(Line::Row(name_of(&x.name, x.quantity), time));
+    if let Some(seat) = x.seat {
+      lines.push(Line::Text(format!("  seat {}", seat)));
+    }
+    if let Some(course) = x.course {
+      lines.push(Line::Text(format!("  course {}", course)));
+    }
+    if let (true, Some(serve_at)) = (x.is_held, x.serve_at) {
+      lines.push(Line::Text(format!("  serve at {}", time_of(serve_at))));
+    }
+    if !x.allergy_flags.is_empty() {
+      lines.push(Line::Text(format!("  !! ALLERGY: {}", x.allergy_flags.join(", "))));
+    }
+    for modifier in &x.modifiers {
+      lines.push(Line::Text(format!("  + {}", modifier.name)));
+    }
+    if let Some(note) = &x.note {
+      lines.push(Line::Text(format!("  \"{}\"", note)));
+    }
+  }
+  lines
+}
+
+// quantity is shown only if more than 1
+fn name_of(name: &str, quantity: usize) -> String {
+  if quantity > 1 {
+    format!("{}x {}", quantity, name)
+  } else {
+    name.to_string()
+  }
+}
+
+pub fn render(lines: &[Line], format: PrintFormat, width: usize) -> Vec<u8> {
+  match format {
+    PrintFormat::Text => to_text(lines, width).into_bytes(),
+    PrintFormat::Html => to_html(lines).into_bytes(),
+    PrintFormat::EscPos => to_escpos(lines, width),
+  }
+}
+
+pub fn to_text(lines: &[Line], width: usize) -> String {
+  lines.iter().map(|x| {
+    let line = match x {
+      Line::Title(s) => format!("{:^width$}", truncate(s, width), width = width),
+      Line::Text(s) => truncate(s, width),
+      Line::Row(l, r) => row_of(l, r, width),
+      Line::Separator => "-".repeat(width),
+    };
+    line.trim_end().to_string() + "\n"
+  }).collect()
+}
+
+pub fn to_html(lines: &[Line]) -> String {
+  let body: String = lines.iter().map(|x| match x {
+    Line::Title(s) => format!("<h1>{}</h1>\n", escape_html(s)),
+    Line::Text(s) => format!("<p>{}</p>\n", escape_html(s)),
+    Line::Row(l, r) => format!("<p class=\"row\"><span>{}</span><span>{}</span></p>\n", escape_html(l), escape_html(r)),
+    Line::Separator => "<hr>\n".to_string(),
+  }).collect();
+
+  format!(
+    "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+    "body { font-family: monospace; width: 40ch; } h1 { text-align: center; } .row { display: flex; justify-content: space-between; }",
+    body,
+  )
+}
+
+// ESC/POS byte stream for thermal printers. non-ASCII characters are replaced w/ '?'
+pub fn to_escpos(lines: &[Line], width: usize) -> Vec<u8> {
+  let mut bytes = vec![ESC, b'@']; // initialize printer
+  for x in lines {
+    match x {
+      Line::Title(s) => {
+        bytes.extend_from_slice(&[ESC, b'a', 1, ESC, b'E', 1, GS, b'!', 0x11]); // center, bold, double size
+        bytes.extend(ascii_of(&truncate(s, width / 2)));
+        bytes.push(LF);
+        bytes.extend_from_slice(&[GS, b'!', 0, ESC, b'E', 0, ESC, b'a', 0]);
+      },
+      Line::Text(s) => {
+        bytes.extend(ascii_of(&truncate(s, width)));
+        bytes.push(LF);
+      },
+      Line::Row(l, r) => {
+        bytes.extend(ascii_of(&row_of(l, r, width)));
+        bytes.push(LF);
+      },
+      Line::Separator => {
+        bytes.extend("-".repeat(width).into_bytes());
+        bytes.push(LF);
+      },
+    }
+  }
+  bytes.extend_from_slice(&[ESC, b'd', 3, GS, b'V', 0]); // feed 3 lines and cut
+  bytes
+}
+
+// amount in minor currency unit w/ 2 decimal places
+fn money_of(amount: i64) -> String {
+  let sign = if amount < 0 { "-" } else { "" };
+  format!("{}{}.{:02}", sign, amount.abs() / 100, amount.abs() % 100)
+}
+
+// HH:MM:SS of the timestamp in UTC
+fn time_of(ts: i64) -> String {
+  let secs = ts.rem_euclid(24 * 60 * 60);
+  format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn truncate(s: &str, width: usize) -> String {
+  s.chars().take(width).collect()
+}
+
+// left part is truncated so that right part always fits in the row
+fn row_of(l: &str, r: &str, width: usize) -> String {
+  let r = truncate(r, width);
+  let l_width = width - r.chars().count();
+  let l = truncate(l, l_width.saturating_sub(1));
+  format!("{:<l_width$}{}", l, r, l_width = l_width)
+}
+
+fn ascii_of(s: &str) -> Vec<u8> {
+  s.chars().map(|c| if c.is_ascii() && !c.is_ascii_control() { c as u8 } else { b'?' }).collect()
+}
+
+fn escape_html(s: &str) -> String {
+  s.replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    bill::BillLine,
+    modifier::Modifier,
+  };
+
+  fn bill_of() -> Bill {
+    let line_of = |uuid: &str, name: &str, price: i64| BillLine {
+      uuid: uuid.to_string(),
+      name: name.to_string(),
+      seat: None,
+      price,
+      quantity: 1,
+      modifiers: vec![],
+      components: vec![],
+    };
+    Bill {
+      table_id: 3,
+      lines: vec![
+        line_of("i1", "ramen", 900),
+        line_of("i2", "gyoza", 450),
+        line_of("i3", "extra spicy tantanmen w/ double chashu", 1250),
+      ],
+      subtotal: 2600,
+      service_charge: 260,
+      tax: 286,
+      total: 3146,
+    }
+  }
+
+  fn items_of() -> Vec<Item> {
+    let item_of = |uuid: &str, name: &str, seat: Option<usize>, ready_at: i64| Item {
+      uuid: uuid.to_string(),
+      name: name.to_string(),
+      table_id: 3,
+      seat,
+      ready_at,
+      ..Default::default()
+    };
+    vec![
+      item_of("i1", "ramen", Some(1), 45300),
+      item_of("i2", "gyoza", None, 45600),
+    ]
+  }
+
+  #[test]
+  fn test_print_format_from_str() {
+    assert_eq!(Ok(PrintFormat::Text), "text".parse());
+    assert_eq!(Ok(PrintFormat::Html), "html".parse());
+    assert_eq!(Ok(PrintFormat::EscPos), "escpos".parse());
+    assert!("pdf".parse::<PrintFormat>().is_err());
+  }
+
+  #[test]
+  fn test_receipt_text() {
+    let text = to_text(&receipt_of(&bill_of()), 32);
+    assert_eq!(include_str!("../testdata/receipt.txt"), text);
+
+    // every line fits in the width
+    assert!(text.lines().all(|x| x.chars().count() <= 32));
+  }
+
+  #[test]
+  fn test_receipt_escpos() {
+    let bytes = to_escpos(&receipt_of(&bill_of()), 32);
+    assert_eq!(include_bytes!("../testdata/receipt.escpos").to_vec(), bytes);
+  }
+
+  #[test]
+  fn test_ticket_escpos() {
+    let bytes = to_escpos(&ticket_of(3, &items_of()), 32);
+    assert_eq!(include_bytes!("../testdata/ticket.escpos").to_vec(), bytes);
+  }
+
+  #[test]
+  fn test_ticket_held() {
+    let mut items = items_of();
+    items[1].course = Some(2);
+    items[1].is_held = true;
+    let text = to_text(&ticket_of(3, &items), 32);
+    assert!(text.contains("ramen                   12:35:00\n"));
+    assert!(text.contains("gyoza                       HOLD\n  course 2\n"));
+
+    items[1].course = None;
+    items[1].serve_at = Some(46800);
+    let text = to_text(&ticket_of(3, &items), 32);
+    assert!(text.contains("gyoza                       HOLD\n  serve at 13:00:00\n"));
+  }
+
+  #[test]
+  fn test_ticket_allergy() {
+    let mut items = items_of();
+    items[0].allergy_flags = vec!["gluten".to_string(), "vegan".to_string()];
+    let text = to_text(&ticket_of(3, &items), 32);
+    assert!(text.contains("  seat 1\n  !! ALLERGY: gluten, vegan\n"));
+  }
+
+  #[test]
+  fn test_modifiers() {
+    let mut items = items_of();
+    items[0].modifiers = vec![Modifier { name: "miso".to_string(), price: 50 }];
+    items[0].note = Some("no onions".to_string());
+    let text = to_text(&ticket_of(3, &items), 32);
+    assert!(text.contains("  seat 1\n  + miso\n  \"no onions\"\n"));
+
+    let mut bill = bill_of();
+    bill.lines[0].modifiers = vec!["miso".to_string()];
+    let text = to_text(&receipt_of(&bill), 32);
+    assert!(text.contains("ramen                       9.00\n  + miso\ngyoza"));
+  }
+
+  #[test]
+  fn test_quantity() {
+    let mut items = items_of();
+    items[1].quantity = 3;
+    let text = to_text(&ticket_of(3, &items), 32);
+    assert!(text.contains("\n3x gyoza"));
+
+    let mut bill = bill_of();
+    bill.lines[1].quantity = 3;
+    let text = to_text(&receipt_of(&bill), 32);
+    assert!(text.contains("\n3x gyoza"));
+  }
+
+  #[test]
+  fn test_receipt_combo() {
+    let mut bill = bill_of();
+    bill.lines[0].name = "lunch set".to_string();
+    bill.lines[0].components = vec!["ramen".to_string(), "green tea".to_string()];
+    let text = to_text(&receipt_of(&bill), 32);
+    assert!(text.contains("lunch set                   9.00\n  - ramen\n  - green tea\ngyoza"));
+  }
+
+  #[test]
+  fn test_html() {
+    let lines = vec![
+      Line::Title("TABLE 1".to_string()),
+      Line::Row("fish & chips".to_string(), "<b>".to_string()),
+    ];
+    let html = to_html(&lines);
+    assert!(html.contains("<h1>TABLE 1</h1>"));
+    assert!(html.contains("<span>fish &amp; chips</span><span>&lt;b&gt;</span>"));
+  }
+
+  #[test]
+  fn test_row_of() {
+    assert_eq!("ramen      9.00", row_of("ramen", "9.00", 15));
+    // left part is truncated and separated from right part by a space
+    assert_eq!("tantanm 12.50", row_of("tantanmen", "12.50", 13));
+    assert_eq!("-12.34", money_of(-1234));
+    assert_eq!("0.05", money_of(5));
+    assert_eq!("12:35:00", time_of(45300));
+  }
+
+  #[test]
+  fn test_ascii_of() {
+    assert_eq!(b"caf? ?".to_vec(), ascii_of("café \n"));
+  }
+}
@@ -0,0 +1,295 @@
+use crate::item::Item;
+use serde::{Serialize, Serializer, Deserialize, ser::Error};
+use serde_json::Value;
+use std::{
+  fmt,
+  str::FromStr,
+};
+
+pub const DEFAULT_PAGE_LIMIT: usize = 100;
+pub const MAX_PAGE_LIMIT: usize = 1000;
+
+// state of an outstanding item
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemState {
+  Held,    // waiting for its course or serve_at
+  Waiting, // waiting for a slot of its station
+  Cooking,
+}
+
+impl ItemState {
+  pub fn of(item: &Item, now: i64) -> ItemState {
+    if item.is_held {
+      ItemState::Held
+    } else if item.start_at > now {
+      ItemState::Waiting
+    } else {
+      ItemState::Cooking
+    }
+  }
+}
+
+impl FromStr for ItemState {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<ItemState, String> {
+    match s {
+      "held" => Ok(ItemState::Held),
+      "waiting" => Ok(ItemState::Waiting),
+      "cooking" => Ok(ItemState::Cooking),
+      _ => Err(format!("Unknown item state {}", s)),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ItemSort {
+  ReadyAt,
+  CreatedAt,
+  Name,
+}
+
+impl FromStr for ItemSort {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<ItemSort, String> {
+    match s {
+      "ready_at" => Ok(ItemSort::ReadyAt),
+      "created_at" => Ok(ItemSort::CreatedAt),
+      "name" => Ok(ItemSort::Name),
+      _ => Err(format!("Unknown sort key {}", s)),
+    }
+  }
+}
+
+// conditions on outstanding items. all specified conditions need to be met. held items have no fixed
+// ready_at, so they never match a time window
+#[derive(Debug, Clone, Default)]
+pub struct ItemFilter {
+  pub table_id: Option<usize>,
+  pub seat: Option<usize>,
+  pub name: Option<String>,
+  pub name_prefix: Option<String>,
+  pub state: Option<ItemState>,
+  pub from: Option<i64>, // ready_at is at or after this time
+  pub to: Option<i64>,   // ready_at is before this time
+}
+
+impl ItemFilter {
+  pub fn matches(&self, item: &Item, now: i64) -> bool {
+    self.table_id.map(|x| x == item.table_id).unwrap_or(true)
+      && self.seat.map(|x| Some(x) == item.seat).unwrap_or(true)
+      && self.name.as_ref().map(|x| x == &item.name).unwrap_or(true)
+      && self.name_prefix.as_ref().map(|x| item.name.starts_with(x.as_str())).unwrap_or(true)
+      && self.state.map(|x| x == ItemState::of(item, now)).unwrap_or(true)
+      && (self.from.is_none() && self.to.is_none() || !item.is_held)
+      && self.from.map(|x| item.ready_at >= x).unwrap_or(true)
+      && self.to.map(|x| item.ready_at < x).unwrap_or(true)
+  }
+}
+
+// position right after the last item of a page in the order of (is_held, ready_at, uuid). given to clients as
+// "[ready_at]_[uuid]", or "held_[ready_at]_[uuid]" if the item is held
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Cursor {
+  pub is_held: bool,
+  pub ready_at: i64,
+  pub uuid: String,
+}
+
+impl Cursor {
+  pub fn of(item: &Item) -> Cursor {
+    Cursor {
+      is_held: item.is_held,
+      ready_at: item.ready_at,
+      uuid: item.uuid.clone(),
+    }
+  }
+
+  pub fn is_before(&self, item: &Item) -> bool {
+    (self.is_held, self.ready_at, &self.uuid) < (item.is_held, item.ready_at, &item.uuid)
+  }
+}
+
+impl FromStr for Cursor {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Cursor, String> {
+    let (is_held, rest) = match s.strip_prefix("held_") {
+      Some(rest) => (true, rest),
+      None => (false, s),
+    };
+    let mut parts = rest.splitn(2, '_');
+    let ready_at = parts.next().and_then(|x| x.parse::<i64>().ok());
+    match (ready_at, parts.next()) {
+      (Some(ready_at), Some(uuid)) if !uuid.is_empty() => Ok(Cursor { is_held, ready_at, uuid: uuid.to_string() }),
+      _ => Err(format!("Bad cursor {}", s)),
+    }
+  }
+}
+
+impl fmt::Display for Cursor {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.is_held {
+      write!(f, "held_")?;
+    }
+    write!(f, "{}_{}", self.ready_at, self.uuid)
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ItemPage {
+  #[serde(serialize_with = "serialize_rows")]
+  pub items: Vec<Item>,
+  pub next_cursor: Option<String>, // none if there are no more items
+}
+
+// items of takeout orders are listed w/o table_id since it's the slot of the order rather than a table.
+// they are identified by takeout_id instead
+fn serialize_rows<S: Serializer>(items: &[Item], serializer: S) -> Result<S::Ok, S::Error> {
+  let mut rows = vec![];
+  for x in items {
+    let mut row = serde_json::to_value(x).map_err(S::Error::custom)?;
+    if let (Some(_), Value::Object(fields)) = (&x.takeout_id, &mut row) {
+      fields.remove("table_id");
+    }
+    rows.push(row);
+  }
+  rows.serialize(serializer)
+}
+
+pub fn sort_by_ready_at(items: &mut [Item]) {
+  sort(items, ItemSort::ReadyAt);
+}
+
+// ties are broken by uuid so that the order is the same on every request. held items come after
+// the scheduled ones in the order of ready_at since they have no fixed ready_at
+pub fn sort(items: &mut [Item], sort: ItemSort) {
+  match sort {
+    ItemSort::ReadyAt => items.sort_by(|a, b| {
+      (a.is_held, a.ready_at, &a.uuid).cmp(&(b.is_held, b.ready_at, &b.uuid))
+    }),
+    ItemSort::CreatedAt => items.sort_by(|a, b| (a.created_at, &a.uuid).cmp(&(b.created_at, &b.uuid))),
+    ItemSort::Name => items.sort_by(|a, b| {
+      (&a.name, a.is_held, a.ready_at, &a.uuid).cmp(&(&b.name, b.is_held, b.ready_at, &b.uuid))
+    }),
+  }
+}
+
+// merges items of a table into the items collected so far keeping at most limit + 1 of them
+// so that whether there is a next page is known
+pub fn merge(items: &mut Vec<Item>, more: Vec<Item>, limit: usize) {
+  items.extend(more);
+  sort_by_ready_at(items);
+  items.truncate(limit + 1);
+}
+
+pub fn page_of(mut items: Vec<Item>, limit: usize) -> ItemPage {
+  sort_by_ready_at(&mut items);
+  let next_cursor = if items.len() > limit {
+    items.truncate(limit);
+    items.last().map(|x| Cursor::of(x).to_string())
+  } else {
+    None
+  };
+  ItemPage { items, next_cursor }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn item_of(uuid: &str, table_id: usize, ready_at: i64) -> Item {
+    Item {
+      uuid: uuid.to_string(),
+      name: "ramen".to_string(),
+      table_id,
+      start_at: ready_at - 10,
+      ready_at,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_item_filter() {
+    let item = item_of("a", 1, 100);
+    assert!(ItemFilter::default().matches(&item, 0));
+    assert!(ItemFilter { table_id: Some(1), from: Some(100), to: Some(101), ..Default::default() }.matches(&item, 0));
+    assert!(!ItemFilter { to: Some(100), ..Default::default() }.matches(&item, 0));
+    assert!(!ItemFilter { name: Some("soba".to_string()), ..Default::default() }.matches(&item, 0));
+    assert!(ItemFilter { name_prefix: Some("ra".to_string()), ..Default::default() }.matches(&item, 0));
+    assert!(!ItemFilter { name_prefix: Some("so".to_string()), ..Default::default() }.matches(&item, 0));
+    assert!(!ItemFilter { seat: Some(0), ..Default::default() }.matches(&item, 0));
+    assert!(ItemFilter { state: Some(ItemState::Waiting), ..Default::default() }.matches(&item, 0));
+    assert!(ItemFilter { state: Some(ItemState::Cooking), ..Default::default() }.matches(&item, 95));
+
+    // held items are skipped by time windows, but match otherwise
+    let held = Item { is_held: true, ready_at: 0, ..item_of("b", 1, 100) };
+    assert!(ItemFilter::default().matches(&held, 0));
+    assert!(!ItemFilter { from: Some(0), ..Default::default() }.matches(&held, 0));
+    assert!(!ItemFilter { to: Some(1000), ..Default::default() }.matches(&held, 0));
+  }
+
+  #[test]
+  fn test_cursor() {
+    let cursor = "100_a-b_c".parse::<Cursor>().unwrap();
+    assert_eq!(Cursor { is_held: false, ready_at: 100, uuid: "a-b_c".to_string() }, cursor);
+    assert_eq!("100_a-b_c", cursor.to_string());
+    assert!(cursor.is_before(&item_of("b", 0, 100)));
+    assert!(!cursor.is_before(&item_of("a", 0, 100)));
+    assert!("100".parse::<Cursor>().is_err());
+    assert!("x_a".parse::<Cursor>().is_err());
+
+    // held items come after all scheduled ones
+    let cursor = "held_0_a".parse::<Cursor>().unwrap();
+    assert_eq!(Cursor { is_held: true, ready_at: 0, uuid: "a".to_string() }, cursor);
+    assert_eq!("held_0_a", cursor.to_string());
+    assert!(!cursor.is_before(&item_of("b", 0, 100)));
+    assert!(cursor.is_before(&Item { is_held: true, ready_at: 0, ..item_of("b", 0, 100) }));
+    assert!("held_a".parse::<Cursor>().is_err());
+  }
+
+  #[test]
+  fn test_sort() {
+    let mut items = vec![item_of("c", 0, 100), item_of("b", 0, 200), item_of("a", 0, 200)];
+    items[0].name = "soba".to_string();
+    items[0].created_at = 10;
+    let uuids_of = |xs: &[Item]| -> Vec<String> { xs.iter().map(|x| x.uuid.clone()).collect() };
+
+    sort(&mut items, ItemSort::ReadyAt);
+    assert_eq!(vec!["c", "a", "b"], uuids_of(&items));
+    sort(&mut items, ItemSort::CreatedAt);
+    assert_eq!(vec!["a", "b", "c"], uuids_of(&items));
+    sort(&mut items, ItemSort::Name);
+    assert_eq!(vec!["a", "b", "c"], uuids_of(&items));
+    assert!("foo".parse::<ItemSort>().is_err());
+
+    // held items come last
+    items.push(Item { is_held: true, ready_at: 0, ..item_of("d", 0, 100) });
+    sort(&mut items, ItemSort::ReadyAt);
+    assert_eq!(vec!["c", "a", "b", "d"], uuids_of(&items));
+  }
+
+  #[test]
+  fn test_page_of() {
+    let mut items = vec![];
+    merge(&mut items, vec![item_of("c", 0, 300), item_of("a", 0, 100)], 2);
+    merge(&mut items, vec![item_of("b", 1, 100), item_of("d", 1, 50)], 2);
+    assert_eq!(3, items.len());
+
+    let page = page_of(items, 2);
+    let uuids: Vec<&str> = page.items.iter().map(|x| x.uuid.as_str()).collect();
+    assert_eq!(vec!["d", "a"], uuids);
+    assert_eq!(Some("100_a".to_string()), page.next_cursor);
+    assert_eq!(None, page_of(vec![item_of("a", 0, 100)], 2).next_cursor);
+
+    // takeout items are listed w/o table_id
+    let takeout = Item { takeout_id: Some("t1".to_string()), ..item_of("t", 5, 100) };
+    let page = page_of(vec![item_of("a", 0, 100), takeout], 2);
+    let rows = serde_json::to_value(&page).unwrap()["items"].clone();
+    assert_eq!(Value::from(0), rows[0]["table_id"]);
+    assert_eq!(None, rows[1].get("table_id"));
+    assert_eq!(Value::from("t1"), rows[1]["takeout_id"]);
+  }
+}
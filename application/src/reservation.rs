@@ -0,0 +1,95 @@
+use crate::api::AddItemsParam;
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReservationStatus {
+  Booked,
+  Seated,
+  Cancelled,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Reservation {
+  pub id: String,
+  pub table_id: usize,
+  pub name: String,
+  pub party_size: usize,
+  pub at: i64,       // time the party arrives
+  pub serve_at: i64, // time to serve the pre-ordered items
+  pub pre_order: AddItemsParam,
+  pub status: ReservationStatus,
+  pub created_at: i64,
+}
+
+// reservations are kept after being seated or cancelled
+pub struct ReservationBook {
+  reservations: Vec<Reservation>,
+}
+
+impl ReservationBook {
+  pub fn new() -> ReservationBook {
+    ReservationBook {
+      reservations: vec![],
+    }
+  }
+
+  pub fn add(&mut self, reservation: Reservation) {
+    self.reservations.push(reservation);
+  }
+
+  pub fn get(&self, id: &str) -> Option<Reservation> {
+    self.reservations.iter().find(|x| x.id == id).cloned()
+  }
+
+  // reservations in the order of arrival
+  pub fn get_all(&self) -> Vec<Reservation> {
+    let mut reservations = self.reservations.clone();
+    reservations.sort_by(|a, b| (a.at, a.created_at).cmp(&(b.at, b.created_at)));
+    reservations
+  }
+
+  // updates a reservation only if it's in the specified status
+  pub fn update<F: FnOnce(&mut Reservation)>(&mut self, id: &str, status: ReservationStatus, f: F) -> Option<Reservation> {
+    let x = self.reservations.iter_mut().find(|x| x.id == id && x.status == status)?;
+    f(x);
+    Some(x.clone())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn reservation_of(id: &str, at: i64, created_at: i64) -> Reservation {
+    Reservation {
+      id: id.to_string(),
+      table_id: 0,
+      name: "Sato".to_string(),
+      party_size: 2,
+      at,
+      serve_at: at,
+      pre_order: AddItemsParam::default(),
+      status: ReservationStatus::Booked,
+      created_at,
+    }
+  }
+
+  #[test]
+  fn test_reservation_book() {
+    let mut book = ReservationBook::new();
+    book.add(reservation_of("r1", 200, 0));
+    book.add(reservation_of("r2", 100, 10));
+    book.add(reservation_of("r3", 100, 5));
+    let ids: Vec<String> = book.get_all().into_iter().map(|x| x.id).collect();
+    assert_eq!(vec!["r3", "r2", "r1"], ids);
+
+    match book.update("r1", ReservationStatus::Booked, |x| x.status = ReservationStatus::Seated) {
+      Some(x) => assert_eq!(ReservationStatus::Seated, x.status),
+      None => assert!(false),
+    }
+    assert!(book.update("r1", ReservationStatus::Booked, |x| x.at = 300).is_none());
+    assert_eq!(200, book.get("r1").unwrap().at);
+    assert!(book.get("r4").is_none());
+  }
+}
@@ -0,0 +1,147 @@
+use crate::{
+  item::Item,
+  menu::{Dish, Station},
+};
+use serde::{Serialize, Deserialize};
+use std::{
+  sync::Mutex,
+  collections::{
+    VecDeque,
+    hash_map::HashMap,
+  },
+};
+
+pub const DEFAULT_SLA_MINS: i64 = 10;
+const MAX_OVERDUE_EVENTS: usize = 10000;
+
+// minutes an item may wait to be served after ready_at. a dish overrides its station
+#[derive(Debug, Clone)]
+pub struct SlaConfig {
+  pub sla_mins: i64,
+  pub station_sla_mins: HashMap<Station, i64>,
+}
+
+impl Default for SlaConfig {
+  fn default() -> SlaConfig {
+    SlaConfig {
+      sla_mins: DEFAULT_SLA_MINS,
+      station_sla_mins: HashMap::new(),
+    }
+  }
+}
+
+impl SlaConfig {
+  pub fn mins_of(&self, dish: Option<&Dish>, station: Option<Station>) -> i64 {
+    dish.and_then(|x| x.sla_mins)
+      .or_else(|| station.and_then(|x| self.station_sla_mins.get(&x).cloned()))
+      .unwrap_or(self.sla_mins)
+  }
+}
+
+// seconds an item has been late beyond its SLA at now or when it was served. items not cooked on
+// a schedule and takeout items, which wait for pickup, are never late
+pub fn late_secs_of(item: &Item, sla_secs: i64, now: i64) -> Option<i64> {
+  if item.is_held || item.is_combo || item.is_removed || item.is_voided || item.takeout_id.is_some() {
+    return None
+  }
+  let late_secs = item.served_at.unwrap_or(now) - item.ready_at - sla_secs;
+  if late_secs > 0 {
+    Some(late_secs)
+  } else {
+    None
+  }
+}
+
+// raised once for each item when it exceeds its SLA
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct OverdueEvent {
+  pub uuid: String,
+  pub table_id: usize,
+  pub name: String,
+  pub station: Option<Station>,
+  pub ready_at: i64,
+  pub sla_secs: i64,
+  pub at: i64, // time the item became overdue i.e. ready_at + sla_secs
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct LateItem {
+  pub item: Item,
+  pub sla_secs: i64,
+  pub late_secs: i64,
+}
+
+pub struct OverdueLog {
+  events: Mutex<VecDeque<OverdueEvent>>, // latest MAX_OVERDUE_EVENTS events in chronological order
+}
+
+impl OverdueLog {
+  pub fn new() -> OverdueLog {
+    OverdueLog {
+      events: Mutex::new(VecDeque::new()),
+    }
+  }
+
+  pub fn add(&self, event: OverdueEvent) {
+    let mut events = self.events.lock().unwrap();
+    if events.len() == MAX_OVERDUE_EVENTS {
+      events.pop_front();
+    }
+    events.push_back(event);
+  }
+
+  // returns events of the station or all stations if not specified
+  pub fn get(&self, station: Option<Station>) -> Vec<OverdueEvent> {
+    self.events.lock().unwrap().iter()
+      .filter(|x| station.is_none() || x.station == station)
+      .cloned()
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_mins_of() {
+    let config = SlaConfig {
+      sla_mins: 10,
+      station_sla_mins: vec![(Station::Bar, 3)].into_iter().collect(),
+    };
+    let dish = Dish { name: "beer".to_string(), sla_mins: Some(1), ..Default::default() };
+    assert_eq!(1, config.mins_of(Some(&dish), Some(Station::Bar)));
+    assert_eq!(3, config.mins_of(None, Some(Station::Bar)));
+    assert_eq!(10, config.mins_of(None, Some(Station::Grill)));
+    assert_eq!(10, config.mins_of(None, None));
+  }
+
+  #[test]
+  fn test_late_secs_of() {
+    let item = Item { ready_at: 100, ..Default::default() };
+    assert_eq!(None, late_secs_of(&item, 60, 160));
+    assert_eq!(Some(1), late_secs_of(&item, 60, 161));
+    assert_eq!(None, late_secs_of(&Item { served_at: Some(150), ..item.clone() }, 60, 500));
+    assert_eq!(Some(40), late_secs_of(&Item { served_at: Some(200), ..item.clone() }, 60, 500));
+    assert_eq!(None, late_secs_of(&Item { is_held: true, ..item.clone() }, 60, 500));
+    assert_eq!(None, late_secs_of(&Item { takeout_id: Some("t".to_string()), ..item }, 60, 500));
+  }
+
+  #[test]
+  fn test_overdue_log() {
+    let log = OverdueLog::new();
+    let event_of = |uuid: &str, station| OverdueEvent {
+      uuid: uuid.to_string(),
+      table_id: 0,
+      name: "ramen".to_string(),
+      station,
+      ready_at: 0,
+      sla_secs: 60,
+      at: 60,
+    };
+    log.add(event_of("a", Some(Station::Grill)));
+    log.add(event_of("b", Some(Station::Bar)));
+    assert_eq!(2, log.get(None).len());
+    assert_eq!(vec![event_of("b", Some(Station::Bar))], log.get(Some(Station::Bar)));
+  }
+}
@@ -1,9 +1,15 @@
-use crate::item::Item;
+use crate::{
+  item::Item,
+  payment::payment::Payment,
+  allergen::Restriction,
+  floor::Party,
+};
 use std::{
   sync::Arc,
   cell::RefCell,
   collections::{
     hash_map::HashMap,
+    hash_set::HashSet,
     binary_heap::BinaryHeap,
   },
 };
@@ -11,6 +17,15 @@ use std::{
 pub struct TableOrders {
   heap: BinaryHeap<Arc<RefCell<Item>>>,
   hash: HashMap<String, Arc<RefCell<Item>>>,
+  session: Vec<Arc<RefCell<Item>>>, // all items added in the current session in added order
+  payments: Vec<Payment>, // payments made in the current session
+  charging: i64, // amount being charged by the payment processor w/o the lock of the orders
+  fired_courses: HashSet<usize>, // courses fired in the current session in addition to course 1
+  sync_ready: bool, // whether items added together are made ready at the same time
+  restrictions: Vec<Restriction>, // allergies and diets of the guests. at most one for each seat
+  party: Option<Party>,
+  takeout_id: Option<String>, // takeout order using the orders as its slot
+  overdue: HashSet<String>, // uuids of the items reported overdue in the current session
 }
 
 unsafe impl Sync for TableOrders {}
@@ -21,13 +36,32 @@ impl TableOrders {
     TableOrders {
       heap: BinaryHeap::new(),
       hash: HashMap::new(),
+      session: vec![],
+      payments: vec![],
+      charging: 0,
+      fired_courses: HashSet::new(),
+      sync_ready: false,
+      restrictions: vec![],
+      party: None,
+      takeout_id: None,
+      overdue: HashSet::new(),
     }
   }
 
+  // held items are not put into the heap until they are fired
   pub fn add(&mut self, item: Item) {
+    let is_held = item.is_held;
     let arc_item = Arc::new(RefCell::new(item));
-    self.heap.push(arc_item.clone());
+    if !is_held {
+      self.heap.push(arc_item.clone());
+    }
     self.hash.insert(arc_item.borrow().uuid.clone(), arc_item.clone());
+    self.session.push(arc_item);
+  }
+
+  // adds an item that is billed but not cooked e.g. combo
+  pub fn add_to_session(&mut self, item: Item) {
+    self.session.push(Arc::new(RefCell::new(item)));
   }
 
   pub fn get(&self, item_uuid: &str) -> Option<Item> {
@@ -38,15 +72,315 @@ impl TableOrders {
     self.hash.values().map(|x| TableOrders::unwrap_item(x.clone())).collect()
   }
 
-  pub fn remove(&mut self, item_uuid: &str) -> Option<Item> {
+  pub fn remove(&mut self, item_uuid: &str, at: i64) -> Option<Item> {
     if let Some(hash_item) = self.hash.remove(item_uuid) {
-      hash_item.borrow_mut().is_removed = true;
+      {
+        let mut x = hash_item.borrow_mut();
+        x.is_removed = true;
+        x.removed_at = Some(at);
+      }
       Some(TableOrders::unwrap_item(hash_item))
     } else {
       None
     }
   }
 
+  // puts a removed item of the current session back to the items being cooked. combos are only
+  // revived in the session since they are not cooked
+  pub fn revive(&mut self, item_uuid: &str) -> Option<Item> {
+    let arc_item = self.session.iter().find(|x| {
+      let x = x.borrow();
+      x.uuid == item_uuid && x.is_removed
+    })?.clone();
+    {
+      let mut x = arc_item.borrow_mut();
+      x.is_removed = false;
+      x.removed_at = None;
+    }
+    let (is_held, is_combo) = {
+      let x = arc_item.borrow();
+      (x.is_held, x.is_combo)
+    };
+    if !is_combo {
+      // the heap still has the item unless its ready_at has passed since it was removed
+      if !is_held && !self.heap.iter().any(|x| Arc::ptr_eq(x, &arc_item)) {
+        self.heap.push(arc_item.clone());
+      }
+      self.hash.insert(item_uuid.to_string(), arc_item.clone());
+    }
+    Some(TableOrders::unwrap_item(arc_item))
+  }
+
+  // permanently drops the items of the current session removed at or before the threshold
+  pub fn purge_removed(&mut self, threshold: i64) -> Vec<Item> {
+    let (purged, kept): (Vec<_>, Vec<_>) = self.session.drain(..)
+      .partition(|x| x.borrow().removed_at.map(|t| t <= threshold).unwrap_or(false));
+    self.session = kept;
+    purged.into_iter().map(TableOrders::unwrap_item).collect()
+  }
+
+  // returns all items of the current session including cooked and removed ones
+  pub fn get_session(&self) -> Vec<Item> {
+    self.session.iter().map(|x| TableOrders::unwrap_item(x.clone())).collect()
+  }
+
+  // marks an item of the current session as voided and drops it from the items being cooked.
+  // cancelled items cannot be voided
+  pub fn void(&mut self, item_uuid: &str) -> Option<Item> {
+    let arc_item = self.session.iter().find(|x| {
+      let x = x.borrow();
+      x.uuid == item_uuid && !x.is_removed && !x.is_voided
+    })?.clone();
+    arc_item.borrow_mut().is_voided = true;
+    if self.hash.remove(item_uuid).is_some() {
+      let items = std::mem::replace(&mut self.heap, BinaryHeap::new()).into_vec();
+      self.heap = items.into_iter().filter(|x| !Arc::ptr_eq(x, &arc_item)).collect();
+    }
+    Some(TableOrders::unwrap_item(arc_item))
+  }
+
+  pub fn add_payment(&mut self, payment: Payment) {
+    self.payments.push(payment);
+  }
+
+  pub fn get_payment(&self, payment_id: &str) -> Option<&Payment> {
+    self.payments.iter().find(|x| x.id == payment_id)
+  }
+
+  pub fn get_payments(&self) -> Vec<Payment> {
+    self.payments.clone()
+  }
+
+  pub fn get_charging(&self) -> i64 {
+    self.charging
+  }
+
+  // reserves the amount while it's being charged. negative amount releases it
+  pub fn add_charging(&mut self, amount: i64) {
+    self.charging += amount;
+  }
+
+  pub fn refund_payment(&mut self, payment_id: &str, amount: i64) -> Option<Payment> {
+    self.payments.iter_mut()
+      .find(|x| x.id == payment_id)
+      .map(|x| {
+        x.refunded += amount;
+        x.clone()
+      })
+  }
+
+  // starts a new session. items being cooked are kept, but left out of the new session
+  pub fn close_session(&mut self) {
+    self.session.clear();
+    self.payments.clear();
+    self.fired_courses.clear();
+    self.sync_ready = false;
+    self.restrictions.clear();
+    self.party = None;
+    self.takeout_id = None;
+    self.overdue.clear();
+  }
+
+  // returns true if the item has not been reported overdue yet
+  pub fn set_overdue(&mut self, item_uuid: &str) -> bool {
+    self.overdue.insert(item_uuid.to_string())
+  }
+
+  // table is free if no party is seated and nothing has been ordered in the current session
+  pub fn is_free(&self) -> bool {
+    self.party.is_none() && self.session.is_empty()
+  }
+
+  pub fn get_party(&self) -> Option<Party> {
+    self.party.clone()
+  }
+
+  pub fn set_party(&mut self, party: Option<Party>) {
+    self.party = party;
+  }
+
+  pub fn get_takeout_id(&self) -> Option<String> {
+    self.takeout_id.clone()
+  }
+
+  pub fn set_takeout_id(&mut self, takeout_id: Option<String>) {
+    self.takeout_id = takeout_id;
+  }
+
+  // replaces the restriction of the same seat. empty restriction just removes the existing one
+  pub fn set_restriction(&mut self, restriction: Restriction) {
+    self.restrictions.retain(|x| x.seat != restriction.seat);
+    if !restriction.is_empty() {
+      self.restrictions.push(restriction);
+    }
+  }
+
+  pub fn get_restrictions(&self) -> Vec<Restriction> {
+    self.restrictions.clone()
+  }
+
+  pub fn is_sync_ready(&self) -> bool {
+    self.sync_ready
+  }
+
+  pub fn set_sync_ready(&mut self, sync_ready: bool) {
+    self.sync_ready = sync_ready;
+  }
+
+  // course 1 is always fired
+  pub fn is_fired(&self, course: usize) -> bool {
+    course <= 1 || self.fired_courses.contains(&course)
+  }
+
+  pub fn set_fired(&mut self, course: usize) {
+    self.fired_courses.insert(course);
+  }
+
+  // returns held items of the course in the order of being added
+  pub fn get_held(&self, course: usize) -> Vec<Item> {
+    let mut items: Vec<Item> = self.hash.values()
+      .map(|x| TableOrders::unwrap_item(x.clone()))
+      .filter(|x| x.is_held && x.course == Some(course))
+      .collect();
+    items.sort_by(|a, b| (a.created_at, &a.name).cmp(&(b.created_at, &b.name)));
+    items
+  }
+
+  // returns held items to be served at a specified time that should start cooking by now
+  // in the order of the time to start
+  pub fn get_due(&self, now: i64) -> Vec<Item> {
+    let mut items: Vec<Item> = self.hash.values()
+      .map(|x| TableOrders::unwrap_item(x.clone()))
+      .filter(|x| x.is_held && x.serve_at.map(|t| t - x.cook_secs <= now).unwrap_or(false))
+      .collect();
+    items.sort_by(|a, b| (a.start_at, a.created_at, &a.name).cmp(&(b.start_at, b.created_at, &b.name)));
+    items
+  }
+
+  // releases a held item w/ its schedule
+  pub fn unhold(&mut self, item_uuid: &str, start_at: i64, ready_at: i64) -> Option<Item> {
+    let arc_item = match self.hash.get(item_uuid) {
+      Some(x) if x.borrow().is_held => x.clone(),
+      _ => return None,
+    };
+    {
+      let mut x = arc_item.borrow_mut();
+      x.is_held = false;
+      x.start_at = start_at;
+      x.ready_at = ready_at;
+    }
+    self.heap.push(arc_item.clone());
+    Some(TableOrders::unwrap_item(arc_item))
+  }
+
+  // returns the lowest held course and the time to fire it if all items of the preceding courses
+  // have been served. the course is fired when the last of them is served, but not before
+  // its own items are added. items not marked as served are taken as served when they are ready
+  pub fn next_auto_fire(&self) -> Option<(usize, i64)> {
+    let held: Vec<Item> = self.hash.values()
+      .map(|x| TableOrders::unwrap_item(x.clone()))
+      .filter(|x| x.is_held)
+      .collect();
+    let course = held.iter().filter_map(|x| x.course).min()?;
+
+    if self.hash.values().any(|x| x.borrow().course.map(|c| c < course).unwrap_or(false)) {
+      return None
+    }
+    let served_at = self.session.iter()
+      .map(|x| x.borrow())
+      .filter(|x| !x.is_removed && x.course.map(|c| c < course).unwrap_or(false))
+      .map(|x| x.served_at.unwrap_or(x.ready_at))
+      .max();
+    let added_at = held.iter()
+      .filter(|x| x.course == Some(course))
+      .map(|x| x.created_at)
+      .max();
+    Some((course, served_at.max(added_at).unwrap()))
+  }
+
+  // updates the schedule of an item being cooked
+  pub fn reschedule(&mut self, item_uuid: &str, start_at: i64, ready_at: i64) -> bool {
+    self.update(item_uuid, |x| {
+      x.start_at = start_at;
+      x.ready_at = ready_at;
+    }).is_some()
+  }
+
+  // applies f to an item being cooked and returns the updated item
+  pub fn update<F: FnOnce(&mut Item)>(&mut self, item_uuid: &str, f: F) -> Option<Item> {
+    let (prev_ready_at, item) = match self.hash.get(item_uuid) {
+      Some(x) => {
+        let prev_ready_at = x.borrow().ready_at;
+        f(&mut x.borrow_mut());
+        (prev_ready_at, TableOrders::unwrap_item(x.clone()))
+      },
+      None => return None,
+    };
+    // rebuild heap if the order of the item has changed
+    if prev_ready_at != item.ready_at {
+      let items = std::mem::replace(&mut self.heap, BinaryHeap::new()).into_vec();
+      self.heap = BinaryHeap::from(items);
+    }
+    Some(item)
+  }
+
+  // puts a cooked item of the current session back to the items being cooked w/ f applied
+  pub fn restore<F: FnOnce(&mut Item)>(&mut self, item_uuid: &str, f: F) -> Option<Item> {
+    if self.hash.contains_key(item_uuid) {
+      return None
+    }
+    let arc_item = self.session.iter().find(|x| {
+      let x = x.borrow();
+      x.uuid == item_uuid && !x.is_removed
+    })?.clone();
+
+    f(&mut arc_item.borrow_mut());
+    self.heap.push(arc_item.clone());
+    self.hash.insert(item_uuid.to_string(), arc_item.clone());
+    Some(TableOrders::unwrap_item(arc_item))
+  }
+
+  // marks a cooked item of the current session as served
+  pub fn serve(&mut self, item_uuid: &str, at: i64) -> Option<Item> {
+    if self.hash.contains_key(item_uuid) {
+      return None
+    }
+    self.session.iter()
+      .find(|x| {
+        let x = x.borrow();
+        x.uuid == item_uuid && !x.is_removed && x.served_at.is_none()
+      })
+      .map(|x| {
+        x.borrow_mut().served_at = Some(at);
+        TableOrders::unwrap_item(x.clone())
+      })
+  }
+
+  // updates an item of the current session that is not cancelled
+  pub fn update_in_session<F: FnOnce(&mut Item)>(&mut self, item_uuid: &str, f: F) -> Option<Item> {
+    let arc_item = self.session.iter().find(|x| {
+      let x = x.borrow();
+      x.uuid == item_uuid && !x.is_removed
+    })?;
+    f(&mut arc_item.borrow_mut());
+    Some(TableOrders::unwrap_item(arc_item.clone()))
+  }
+
+  // returns the components of a combo that are not cancelled
+  pub fn get_components(&self, parent_uuid: &str) -> Vec<Item> {
+    self.session.iter()
+      .map(|x| TableOrders::unwrap_item(x.clone()))
+      .filter(|x| x.parent_id.as_deref() == Some(parent_uuid) && !x.is_removed)
+      .collect()
+  }
+
+  // returns an item of the current session including cooked and removed ones
+  pub fn get_from_session(&self, item_uuid: &str) -> Option<Item> {
+    self.session.iter()
+      .find(|x| x.borrow().uuid == item_uuid)
+      .map(|x| TableOrders::unwrap_item(x.clone()))
+  }
+
   pub fn remove_before_eq_threshold(&mut self, threshold: i64) -> Vec<Item> {
     let mut res = vec![];
     loop {
@@ -75,31 +409,51 @@ impl TableOrders {
     self.hash.len()
   }
 
+  // sum of the quantities of the outstanding items
+  pub fn num_units(&self) -> usize {
+    self.hash.values().map(|x| x.borrow().quantity).sum()
+  }
+
+  // returns a copy that doesn't share the items w/ the original, so it can be changed freely e.g. to quote
+  pub fn copy(&self) -> TableOrders {
+    let mut copies: HashMap<*const RefCell<Item>, Arc<RefCell<Item>>> = HashMap::new();
+    let mut copy_of = |x: &Arc<RefCell<Item>>| copies.entry(Arc::as_ptr(x))
+      .or_insert_with(|| Arc::new(RefCell::new(x.borrow().clone())))
+      .clone();
+    TableOrders {
+      heap: self.heap.iter().map(&mut copy_of).collect(),
+      hash: self.hash.iter().map(|(k, v)| (k.clone(), copy_of(v))).collect(),
+      session: self.session.iter().map(&mut copy_of).collect(),
+      payments: self.payments.clone(),
+      charging: self.charging,
+      fired_courses: self.fired_courses.clone(),
+      sync_ready: self.sync_ready,
+      restrictions: self.restrictions.clone(),
+      party: self.party.clone(),
+      takeout_id: self.takeout_id.clone(),
+      overdue: self.overdue.clone(),
+    }
+  }
+
   fn unwrap_item(item: Arc<RefCell<Item>>) -> Item {
     let item = item.borrow();
-    Item {
-      uuid: item.uuid.clone(),
-      name: item.name.clone(),
-      table_id: item.table_id,
-      created_at: item.created_at,
-      ready_at: item.ready_at,
-      is_removed: item.is_removed,
-    }
+    item.clone()
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::payment::payment::Tender;
 
   fn item_of(id: &str, name: &str, created_at: i64, ready_at: i64, is_removed: bool) -> Item {
     Item {
       uuid: id.to_string(),
       name: name.to_string(),
-      table_id: 0,
       created_at,
       ready_at,
       is_removed,
+      ..Default::default()
     }
   }
 
@@ -231,7 +585,7 @@ mod tests {
     }
 
     // remove i2
-    if let Some(i2r) = to.remove(&i2.uuid) {
+    if let Some(i2r) = to.remove(&i2.uuid, 0) {
       assert_eq!(i2r.uuid, i2.uuid);
       assert_eq!(true, i2r.is_removed); // return item should be marked as removed
       assert_eq!(to.len(), 2);
@@ -246,17 +600,17 @@ mod tests {
     }
 
     // i2 cannot be removed again
-    if to.remove(&i2.uuid).is_some() {
+    if to.remove(&i2.uuid, 0).is_some() {
       assert!(false)
     }
 
     // i3 is already removed
-    if to.remove(&i3.uuid).is_some() {
+    if to.remove(&i3.uuid, 0).is_some() {
       assert!(false)
     }
 
     // remove i1
-    if let Some(i1r) = to.remove(&i1.uuid) {
+    if let Some(i1r) = to.remove(&i1.uuid, 0) {
       assert_eq!(i1r.uuid, i1.uuid);
       assert_eq!(true, i1r.is_removed);
       assert_eq!(to.len(), 0);
@@ -264,4 +618,385 @@ mod tests {
       assert!(false)
     }
   }
+
+  #[test]
+  fn test_get_session() {
+    let i1 = item_of("i1", "ramen", 0, 30, false);
+    let i2 = item_of("i2", "cake", 0, 15, false);
+    let i3 = item_of("i3", "spagetti", 0, 10, false);
+
+    let mut to = TableOrders::new();
+    for x in vec![&i1, &i2, &i3] {
+      to.add(x.clone());
+    }
+
+    // cooked and removed items should stay in the session
+    to.remove(&i2.uuid, 0);
+    to.remove_before_eq_threshold(10);
+    assert_eq!(1, to.len());
+
+    let items = to.get_session();
+    assert_eq!(3, items.len());
+    assert_eq!(i1.uuid, items[0].uuid);
+    assert_eq!(i2.uuid, items[1].uuid);
+    assert_eq!(true, items[1].is_removed);
+    assert_eq!(i3.uuid, items[2].uuid);
+  }
+
+  #[test]
+  fn test_revive() {
+    let i1 = item_of("i1", "ramen", 0, 30, false);
+    let i2 = item_of("i2", "cake", 0, 15, false);
+
+    let mut to = TableOrders::new();
+    for x in vec![&i1, &i2] {
+      to.add(x.clone());
+    }
+    to.remove(&i1.uuid, 5);
+    to.remove(&i2.uuid, 6);
+
+    // i2 whose ready_at has passed is revived as cooked
+    to.remove_before_eq_threshold(20);
+    match to.revive(&i2.uuid) {
+      Some(x) => assert_eq!((false, None), (x.is_removed, x.removed_at)),
+      None => assert!(false),
+    }
+    assert_eq!(1, to.remove_before_eq_threshold(20).len());
+    assert_eq!(None, to.revive(&i2.uuid));
+
+    // i1 is still in the heap and not pushed twice
+    if to.revive(&i1.uuid).is_none() {
+      assert!(false);
+    }
+    to.remove(&i1.uuid, 25);
+    assert_eq!(None, to.revive("foo"));
+
+    // removed items are purged after the threshold
+    assert_eq!(0, to.purge_removed(24).len());
+    assert_eq!(vec![i1.uuid.clone()], to.purge_removed(25).into_iter().map(|x| x.uuid).collect::<Vec<String>>());
+    assert_eq!(None, to.revive(&i1.uuid));
+    assert_eq!(1, to.get_session().len());
+  }
+
+  #[test]
+  fn test_void() {
+    let i1 = item_of("i1", "ramen", 0, 30, false);
+    let i2 = item_of("i2", "cake", 0, 15, false);
+
+    let mut to = TableOrders::new();
+    for x in vec![&i1, &i2] {
+      to.add(x.clone());
+    }
+
+    // cooked item can be voided
+    to.remove_before_eq_threshold(15);
+    match to.void(&i2.uuid) {
+      Some(x) => assert_eq!(true, x.is_voided),
+      None => assert!(false),
+    }
+    // but not twice
+    assert_eq!(None, to.void(&i2.uuid));
+
+    // removed item cannot be voided
+    to.remove(&i1.uuid, 0);
+    assert_eq!(None, to.void(&i1.uuid));
+
+    // non-existing item cannot be voided
+    assert_eq!(None, to.void("foo"));
+
+    // item being cooked is dropped when voided, but stays in the session
+    let i3 = item_of("i3", "spagetti", 0, 10, false);
+    to.add(i3.clone());
+    assert_eq!(1, to.len());
+    match to.void(&i3.uuid) {
+      Some(x) => assert_eq!(true, x.is_voided),
+      None => assert!(false),
+    }
+    assert_eq!(0, to.len());
+    assert_eq!(0, to.remove_before_eq_threshold(10).len());
+    assert_eq!(3, to.get_session().len());
+  }
+
+  #[test]
+  fn test_payments() {
+    let payment = Payment {
+      id: "p1".to_string(),
+      tender: Tender::Cash,
+      amount: 1000,
+      tip: 0,
+      refunded: 0,
+      created_at: 0,
+    };
+
+    let mut to = TableOrders::new();
+    to.add_payment(payment.clone());
+    assert_eq!(Some(&payment), to.get_payment("p1"));
+    assert_eq!(None, to.get_payment("p2"));
+
+    // refund is accumulated
+    to.refund_payment("p1", 300);
+    match to.refund_payment("p1", 200) {
+      Some(x) => assert_eq!(500, x.refunded),
+      None => assert!(false),
+    }
+    assert_eq!(None, to.refund_payment("p2", 100));
+    assert_eq!(1, to.get_payments().len());
+  }
+
+  #[test]
+  fn test_close_session() {
+    let i1 = item_of("i1", "ramen", 0, 30, false);
+    let i2 = item_of("i2", "cake", 0, 15, false);
+
+    let mut to = TableOrders::new();
+    for x in vec![&i1, &i2] {
+      to.add(x.clone());
+    }
+    to.add_payment(Payment {
+      id: "p1".to_string(),
+      tender: Tender::Card,
+      amount: 1000,
+      tip: 0,
+      refunded: 0,
+      created_at: 0,
+    });
+    to.remove_before_eq_threshold(15);
+
+    to.close_session();
+    assert_eq!(0, to.get_session().len());
+    assert_eq!(0, to.get_payments().len());
+
+    // item being cooked is still there
+    assert_eq!(1, to.len());
+  }
+
+  #[test]
+  fn test_reschedule() {
+    let i1 = item_of("i1", "ramen", 0, 30, false);
+    let i2 = item_of("i2", "cake", 0, 15, false);
+
+    let mut to = TableOrders::new();
+    for x in vec![&i1, &i2] {
+      to.add(x.clone());
+    }
+
+    // ramen is now ready before cake
+    assert_eq!(true, to.reschedule(&i1.uuid, 0, 10));
+    assert_eq!(10, to.get(&i1.uuid).unwrap().ready_at);
+    assert_eq!(false, to.reschedule("foo", 0, 10));
+
+    let r = to.remove_before_eq_threshold(10);
+    assert_eq!(1, r.len());
+    assert_eq!(i1.uuid, r[0].uuid);
+  }
+
+  #[test]
+  fn test_update() {
+    let i1 = item_of("i1", "ramen", 0, 30, false);
+    let i2 = item_of("i2", "cake", 0, 15, false);
+
+    let mut to = TableOrders::new();
+    for x in vec![&i1, &i2] {
+      to.add(x.clone());
+    }
+
+    match to.update(&i2.uuid, |x| x.started_at = Some(3)) {
+      Some(x) => assert_eq!(Some(3), x.started_at),
+      None => assert!(false),
+    }
+    assert_eq!(Some(3), to.get(&i2.uuid).unwrap().started_at);
+    assert_eq!(None, to.update("foo", |x| x.started_at = Some(3)));
+  }
+
+  #[test]
+  fn test_combo_components() {
+    let mut i1 = item_of("i1", "set", 0, 0, false);
+    i1.is_combo = true;
+    let mut i2 = item_of("i2", "soup", 0, 20, false);
+    i2.parent_id = Some("i1".to_string());
+
+    // combo is only in the session while its components are cooked
+    let mut to = TableOrders::new();
+    to.add_to_session(i1.clone());
+    to.add(i2.clone());
+    assert_eq!(None, to.get(&i1.uuid));
+    assert_eq!(1, to.len());
+    assert_eq!(vec![i2], to.get_components(&i1.uuid));
+
+    // items only in the session can be updated until cancelled
+    assert!(to.update_in_session(&i1.uuid, |x| x.is_removed = true).unwrap().is_removed);
+    assert_eq!(None, to.update_in_session(&i1.uuid, |x| x.quantity = 2));
+  }
+
+  #[test]
+  fn test_decrement() {
+    let i1 = item_of("i1", "ramen", 0, 30, false);
+    let i2 = item_of("i2", "cake", 0, 15, false);
+
+    let mut to = TableOrders::new();
+    for x in vec![&i1, &i2] {
+      to.add(x.clone());
+    }
+
+    // outstanding units follow the quantities of the items
+    to.update(&i1.uuid, |x| x.quantity = 3);
+    to.update(&i2.uuid, |x| x.quantity = 2);
+    assert_eq!(5, to.num_units());
+    to.update(&i1.uuid, |x| x.quantity -= 2);
+    assert_eq!(3, to.num_units());
+
+    // cooked items are not counted
+    to.remove_before_eq_threshold(15);
+    assert_eq!(1, to.num_units());
+  }
+
+  #[test]
+  fn test_restore() {
+    let i1 = item_of("i1", "ramen", 0, 30, false);
+    let i2 = item_of("i2", "cake", 0, 15, false);
+
+    let mut to = TableOrders::new();
+    for x in vec![&i1, &i2] {
+      to.add(x.clone());
+    }
+
+    // item being cooked cannot be restored
+    assert_eq!(None, to.restore(&i1.uuid, |_| {}));
+
+    // cooked item can be restored w/ a new ready_at
+    to.remove_before_eq_threshold(15);
+    assert_eq!(None, to.get(&i2.uuid));
+    assert_eq!(Some(i2.clone()), to.get_from_session(&i2.uuid));
+    match to.restore(&i2.uuid, |x| x.ready_at = 40) {
+      Some(x) => assert_eq!(40, x.ready_at),
+      None => assert!(false),
+    }
+    assert_eq!(2, to.len());
+    assert_eq!(2, to.get_session().len());
+
+    // restored item is popped at the new ready_at
+    let r = to.remove_before_eq_threshold(30);
+    assert_eq!(i1.uuid, r[0].uuid);
+    let r = to.remove_before_eq_threshold(40);
+    assert_eq!(i2.uuid, r[0].uuid);
+
+    // removed item cannot be restored
+    to.add(item_of("i3", "soba", 0, 50, false));
+    to.remove("i3", 0);
+    assert_eq!(None, to.restore("i3", |_| {}));
+  }
+
+  #[test]
+  fn test_serve() {
+    let i1 = item_of("i1", "ramen", 0, 30, false);
+
+    let mut to = TableOrders::new();
+    to.add(i1.clone());
+
+    // item being cooked cannot be served
+    assert_eq!(None, to.serve(&i1.uuid, 20));
+
+    // cooked item can be served only once
+    to.remove_before_eq_threshold(30);
+    match to.serve(&i1.uuid, 45) {
+      Some(x) => assert_eq!(Some(45), x.served_at),
+      None => assert!(false),
+    }
+    assert_eq!(None, to.serve(&i1.uuid, 46));
+
+    // removed item cannot be served
+    to.add(item_of("i2", "soba", 0, 50, false));
+    to.remove("i2", 0);
+    assert_eq!(None, to.serve("i2", 60));
+  }
+
+  #[test]
+  fn test_hold_and_fire() {
+    let course_of = |id: &str, course: usize, created_at: i64, ready_at: i64, is_held: bool| Item {
+      course: Some(course),
+      is_held,
+      ..item_of(id, id, created_at, ready_at, false)
+    };
+    let mut to = TableOrders::new();
+    to.add(course_of("i1", 1, 0, 10, false));
+    to.add(course_of("i2", 1, 0, 20, false));
+    to.add(course_of("i3", 2, 0, 0, true));
+    to.add(course_of("i4", 3, 5, 0, true));
+
+    assert!(to.is_fired(1));
+    assert!(!to.is_fired(2));
+
+    // held items are outstanding, but never served
+    assert_eq!(4, to.len());
+    assert_eq!(2, to.remove_before_eq_threshold(100).len());
+    assert_eq!(2, to.len());
+
+    // course 2 can be fired when the last item of course 1 is served
+    assert_eq!(Some((2, 20)), to.next_auto_fire());
+    to.serve("i2", 25);
+    assert_eq!(Some((2, 25)), to.next_auto_fire());
+    to.set_fired(2);
+    assert!(to.is_fired(2));
+    assert_eq!(vec!["i3".to_string()], to.get_held(2).into_iter().map(|x| x.uuid).collect::<Vec<String>>());
+    match to.unhold("i3", 20, 50) {
+      Some(x) => assert!(!x.is_held),
+      None => assert!(false),
+    }
+    assert_eq!(None, to.unhold("i3", 20, 50));
+
+    // course 3 waits for course 2
+    assert_eq!(None, to.next_auto_fire());
+    assert_eq!("i3", to.remove_before_eq_threshold(50)[0].uuid);
+    assert_eq!(Some((3, 50)), to.next_auto_fire());
+
+    to.close_session();
+    assert!(!to.is_fired(2));
+  }
+
+  #[test]
+  fn test_seat_party() {
+    let mut to = TableOrders::new();
+    assert!(to.is_free());
+
+    // seated party occupies the table even before ordering
+    to.set_party(Some(Party { name: "Sato".to_string(), party_size: 2, seated_at: 0 }));
+    assert!(!to.is_free());
+    assert_eq!(2, to.get_party().unwrap().party_size);
+
+    // party leaves w/ the session
+    to.close_session();
+    assert_eq!(None, to.get_party());
+    assert!(to.is_free());
+  }
+
+  #[test]
+  fn test_restrictions() {
+    let mut to = TableOrders::new();
+
+    // restriction of a seat replaces the previous one and an empty one just removes it
+    to.set_restriction(Restriction { seat: Some(1), allergies: vec!["nuts".to_string()], ..Default::default() });
+    to.set_restriction(Restriction { seat: Some(1), allergies: vec!["soy".to_string()], ..Default::default() });
+    assert_eq!(1, to.get_restrictions().len());
+    assert_eq!(vec!["soy".to_string()], to.get_restrictions()[0].allergies);
+    to.set_restriction(Restriction { seat: Some(1), ..Default::default() });
+    assert_eq!(0, to.get_restrictions().len());
+
+    // restrictions are cleared for the next session
+    to.set_restriction(Restriction { seat: None, diets: vec!["vegan".to_string()], ..Default::default() });
+    assert_eq!(1, to.get_restrictions().len());
+    to.close_session();
+    assert_eq!(0, to.get_restrictions().len());
+  }
+
+  #[test]
+  fn test_align_ready_at() {
+    let mut to = TableOrders::new();
+    assert!(!to.is_sync_ready());
+    to.set_sync_ready(true);
+    assert!(to.is_sync_ready());
+
+    // sync_ready is reset for the next session
+    to.close_session();
+    assert!(!to.is_sync_ready());
+  }
 }
@@ -0,0 +1,111 @@
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TakeoutKind {
+  Pickup,
+  Delivery,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TakeoutStatus {
+  Open,      // items are being cooked or waiting to be picked up
+  Completed, // picked up or handed to the driver
+  Cancelled,
+}
+
+// order not bound to a table. its items are kept in a takeout slot while the order is open
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Takeout {
+  pub id: String,
+  pub kind: TakeoutKind,
+  pub name: String,
+  pub phone: String,
+  pub address: Option<String>,
+  pub due_at: i64, // time the order is picked up or leaves for delivery
+  pub status: TakeoutStatus,
+  pub is_ready: bool, // all items are ready
+  pub ready_at: Option<i64>, // none if any item is held
+  pub created_at: i64,
+  #[serde(skip)]
+  pub slot: usize, // index of the orders that keep the items
+}
+
+// takeout orders are kept after being completed or cancelled
+pub struct TakeoutBook {
+  takeouts: Vec<Takeout>,
+}
+
+impl TakeoutBook {
+  pub fn new() -> TakeoutBook {
+    TakeoutBook {
+      takeouts: vec![],
+    }
+  }
+
+  pub fn add(&mut self, takeout: Takeout) {
+    self.takeouts.push(takeout);
+  }
+
+  pub fn get(&self, id: &str) -> Option<Takeout> {
+    self.takeouts.iter().find(|x| x.id == id).cloned()
+  }
+
+  // open orders in the order of due_at
+  pub fn get_open(&self) -> Vec<Takeout> {
+    let mut takeouts: Vec<Takeout> = self.takeouts.iter()
+      .filter(|x| x.status == TakeoutStatus::Open)
+      .cloned()
+      .collect();
+    takeouts.sort_by(|a, b| (a.due_at, a.created_at).cmp(&(b.due_at, b.created_at)));
+    takeouts
+  }
+
+  // updates an order only if it's in the specified status
+  pub fn update<F: FnOnce(&mut Takeout)>(&mut self, id: &str, status: TakeoutStatus, f: F) -> Option<Takeout> {
+    let x = self.takeouts.iter_mut().find(|x| x.id == id && x.status == status)?;
+    f(x);
+    Some(x.clone())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn takeout_of(id: &str, due_at: i64, created_at: i64) -> Takeout {
+    Takeout {
+      id: id.to_string(),
+      kind: TakeoutKind::Pickup,
+      name: "Sato".to_string(),
+      phone: "090-0000-0000".to_string(),
+      address: None,
+      due_at,
+      status: TakeoutStatus::Open,
+      is_ready: false,
+      ready_at: None,
+      created_at,
+      slot: 0,
+    }
+  }
+
+  #[test]
+  fn test_takeout_book() {
+    let mut book = TakeoutBook::new();
+    book.add(takeout_of("t1", 200, 0));
+    book.add(takeout_of("t2", 100, 10));
+    book.add(takeout_of("t3", 100, 5));
+    let ids: Vec<String> = book.get_open().into_iter().map(|x| x.id).collect();
+    assert_eq!(vec!["t3", "t2", "t1"], ids);
+
+    match book.update("t3", TakeoutStatus::Open, |x| x.status = TakeoutStatus::Completed) {
+      Some(x) => assert_eq!(TakeoutStatus::Completed, x.status),
+      None => assert!(false),
+    }
+    assert!(book.update("t3", TakeoutStatus::Open, |x| x.status = TakeoutStatus::Cancelled).is_none());
+    assert_eq!(2, book.get_open().len());
+    assert_eq!(TakeoutStatus::Completed, book.get("t3").unwrap().status);
+    assert!(book.get("t4").is_none());
+  }
+}
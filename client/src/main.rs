@@ -53,6 +53,7 @@ impl Client {
     let url = format!("{}/table/{}/items", self.base_url, table_id);
     let param = AddItemsParam {
       item_names: item_names.clone(),
+      ..Default::default()
     };
 
     let resp = self.http_client.post(&url)